@@ -1,7 +1,31 @@
-use anyhow::Result;
-use gumdrop::Options;
-use unlatex::format_with_opts;
-use std::{io::{self, Read, Write}, fs};
+use anyhow::{anyhow, Result};
+use gumdrop::{Options, ParsingStyle};
+use unlatex::{format_range, format_with_options, parse_with_diagnostics, span, Config, FormatOptions};
+use unlatex::cache::{self, CachedFormatter};
+use unlatex::compare::semantic_diff;
+use unlatex::diff::{diff, report};
+use unlatex::markup::mark_changes;
+use unlatex::chunked::{format_chunked, ChunkOptions};
+use unlatex::verify;
+use unlatex::analysis::citations::CiteIssue;
+use unlatex::diagnostics::{self, Diagnostic, Severity};
+use unlatex::bib::{self, Bibliography};
+use unlatex::flatten::{flatten_with, FlattenOptions};
+use unlatex::lint::Linter;
+use unlatex::project::{FsLoader, Loader, Project, ProjectIssue};
+use unlatex::outline::{outline, Section};
+use unlatex::stats::{Counts, SectionStats};
+use std::{io::{self, Read, Write}, fs, ops::Range, path::{Path, PathBuf}, str::FromStr};
+
+/// Subcommands, dispatched on the first argument.
+const COMMANDS: &str = "Commands:
+  outline    print the sectioning structure of documents
+  lint       check the style, includes, cross-references and citations of documents
+  flatten    inline the files included by a document
+  compare    check that two documents have the same meaning
+  diff       list the structural edits between two revisions of a document
+  latexdiff  mark up the changes between two revisions of a document
+  count      count the words, equations, figures, tables and citations of documents";
 
 #[derive(Debug, Options)]
 struct UnLaTexOptions {
@@ -15,48 +39,753 @@ struct UnLaTexOptions {
     overwrite: bool,
     #[options(help = "output file [default: stdout]")]
     output: Option<String>,
-    #[options(help = "maximum line length [default: 120]", default = "120")]
-    print_width: i32,
+    #[options(help = "maximum line length [default: 120]")]
+    print_width: Option<i32>,
     #[options(help = "whether to use tabs for indentation [default: false]")]
     use_tabs: bool,
-    #[options(help = "number of spaces to use for indentation [default: 2]", default = "2")]
-    tab_width: i32,
+    #[options(help = "indent with spaces even if the configuration uses tabs", no_short)]
+    no_use_tabs: bool,
+    #[options(help = "number of spaces to use for indentation [default: 2]")]
+    tab_width: Option<i32>,
     #[options(help = "whether to only format the document body [default: false]", short = "d")]
     document_only: bool,
+    #[options(help = "format the whole document even if the configuration formats only its body", no_short)]
+    no_document_only: bool,
+    #[options(help = "configuration file [default: discovered from the input directory]", short = "c")]
+    config: Option<String>,
+    #[options(help = "ignore configuration files", no_short)]
+    no_config: bool,
+    #[options(help = "path of the file read from stdin, used to resolve the configuration", no_short)]
+    stdin_filepath: Option<String>,
+    #[options(help = "offset where formatting starts [default: start of input]", no_short)]
+    range_start: Option<usize>,
+    #[options(help = "offset where formatting ends [default: end of input]", no_short)]
+    range_end: Option<usize>,
+    #[options(help = "interpret range offsets as 1-based line numbers instead of bytes", no_short)]
+    range_lines: bool,
+    #[options(help = "format large documents in chunks of paragraphs [default: false]", no_short)]
+    chunked: bool,
+    #[options(help = "number of threads formatting chunks [default: 1]", no_short)]
+    jobs: Option<usize>,
+    #[options(help = "check that formatting again changes nothing and that the document means the same", no_short)]
+    verify: bool,
+    #[options(help = "directory of the formatting cache [default: latexformat in the user cache directory]", no_short)]
+    cache_location: Option<String>,
+    #[options(help = "format every file even if it is cached", no_short)]
+    no_cache: bool,
+    #[options(help = "sort the entries of BibTeX files by key [default: false]", no_short)]
+    sort_entries: bool,
+    #[options(help = "do not format documents with syntax errors, such as an unclosed `{`, and report them", no_short)]
+    check_syntax: bool,
+    #[options(no_short, meta = "FORMAT", default = "human", help = "how syntax errors are printed with --check-syntax: human, short or json [default: human]")]
+    message_format: MessageFormat,
+}
+
+impl UnLaTexOptions {
+    /// The configuration applying to `path`, unless disabled.
+    fn config(&self, path: &Path) -> Result<Option<Config>> {
+        Ok(if self.no_config {
+            None
+        } else if let Some(config) = &self.config {
+            Some(Config::load(config)?)
+        } else {
+            Config::discover(path)?
+        })
+    }
+
+    /// Resolve the formatting options for `path`, in increasing precedence:
+    /// built-in defaults, configuration file, command line flags.
+    fn format_options(&self, path: &Path, config: Option<&Config>) -> Result<FormatOptions> {
+        let mut opts = FormatOptions {
+            print_width: 120,
+            use_tabs: false,
+            tab_width: 2,
+            document_only: false,
+        };
+        if let Some(config) = config {
+            opts = config.resolve(path, &opts);
+        }
+        if self.use_tabs && self.no_use_tabs {
+            return Err(anyhow!("--use-tabs and --no-use-tabs cannot be used together"));
+        }
+        if self.document_only && self.no_document_only {
+            return Err(anyhow!("--document-only and --no-document-only cannot be used together"));
+        }
+
+        if let Some(print_width) = self.print_width {
+            opts.print_width = print_width;
+        }
+        if self.use_tabs || self.no_use_tabs {
+            opts.use_tabs = self.use_tabs;
+        }
+        if let Some(tab_width) = self.tab_width {
+            opts.tab_width = tab_width;
+        }
+        if self.document_only || self.no_document_only {
+            opts.document_only = self.document_only;
+        }
+        Ok(opts)
+    }
+
+    /// Byte range of `input` to format, if a range was requested.
+    fn range(&self, input: &str) -> Result<Option<Range<usize>>> {
+        if self.range_start.is_none() && self.range_end.is_none() {
+            return Ok(None);
+        }
+        let range = if self.range_lines {
+            let start = self.range_start.unwrap_or(1);
+            let end = self.range_end.unwrap_or(usize::MAX);
+            span::line_range(input, start, end).ok_or_else(|| anyhow!("line range {start}..{end} is out of bounds"))?
+        } else {
+            self.range_start.unwrap_or(0)..self.range_end.unwrap_or(input.len())
+        };
+        Ok(Some(range))
+    }
+
+    /// Format `input`, read from `path`. Files with a `.bib` extension are formatted as
+    /// BibTeX databases.
+    fn format(&self, input: &str, path: &Path) -> Result<String> {
+        let config = self.config(path)?;
+        let opts = self.format_options(path, config.as_ref())?;
+        if path.extension().is_some_and(|ext| ext == "bib") {
+            if self.range(input)?.is_some() {
+                return Err(anyhow!("range formatting is not supported for BibTeX files"));
+            }
+            let mut bib_opts = config.map(|config| config.bib).unwrap_or_default();
+            if self.sort_entries {
+                bib_opts.sort_entries = true;
+            }
+            let index = span::LineIndex::new(input);
+            for (first, duplicate) in Bibliography::parse(input).duplicates() {
+                let (line, _) = index.line_col(duplicate.span.start);
+                let (first_line, _) = index.line_col(first.span.start);
+                eprintln!(
+                    "{}:{}: warning: entry `{}` is already defined at line {}",
+                    path.display(),
+                    line + 1,
+                    duplicate.key,
+                    first_line + 1
+                );
+            }
+            return Ok(bib::format(input, &opts, &bib_opts)?);
+        }
+        if self.check_syntax {
+            self.report_syntax_errors(input, path)?;
+        }
+        let range = self.range(input)?;
+        let formatted = match self.cache() {
+            // documents formatted as a whole are the ones cached
+            Some(cache) if range.is_none() => cache.format_with(input, &opts, |input| self.format_latex(input, &opts))?,
+            _ => self.format_latex(input, &opts)?,
+        };
+        if self.verify {
+            // only the formatted paragraphs of a range are stable
+            let checked = match range {
+                Some(_) => verify::check_equivalent(input, &formatted),
+                None => verify::verify(input, &formatted, &opts),
+            };
+            if let Err(err) = checked {
+                let name = if path == Path::new(".") { "<stdin>".to_string() } else { path.display().to_string() };
+                return Err(anyhow!("{name}: {err}"));
+            }
+        }
+        Ok(formatted)
+    }
+
+    /// Report the syntax errors of `input`, read from `path`, failing if there are any.
+    fn report_syntax_errors(&self, input: &str, path: &Path) -> Result<()> {
+        let (_, diagnostics) = parse_with_diagnostics(input)?;
+        if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+            let name = if path == Path::new(".") { "<stdin>".to_string() } else { path.display().to_string() };
+            let mut reporter = Reporter::new(self.message_format, io::stderr());
+            for diagnostic in &diagnostics {
+                reporter.report(&name, input, diagnostic)?;
+            }
+            return Err(anyhow!("{name}: not formatted because of syntax errors"));
+        }
+        Ok(())
+    }
+
+    /// Format the LaTeX document `input` with `opts`.
+    fn format_latex(&self, input: &str, opts: &FormatOptions) -> Result<String> {
+        let formatted = match self.range(input)? {
+            Some(range) => format_range(input, range, opts)?,
+            None if self.chunked || self.jobs.is_some() => {
+                let chunk_opts = ChunkOptions { jobs: self.jobs.unwrap_or(1), ..ChunkOptions::default() };
+                format_chunked(input, opts, &chunk_opts)?
+            }
+            None => format_with_options(input, opts)?,
+        };
+        Ok(formatted)
+    }
+
+    /// The formatting cache, unless disabled or there is nowhere to put it.
+    fn cache(&self) -> Option<CachedFormatter> {
+        if self.no_cache {
+            return None;
+        }
+        if let Some(location) = &self.cache_location {
+            return Some(CachedFormatter::new(location));
+        }
+        let env = |name| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+        let dir = env("XDG_CACHE_HOME")
+            .or_else(|| env("HOME").map(|home| home.join(".cache")))
+            .or_else(|| env("LOCALAPPDATA"))?;
+        Some(CachedFormatter::new(dir.join("latexformat")))
+    }
+
+    /// Path standing for stdin when resolving the configuration.
+    fn stdin_path(&self) -> &Path {
+        Path::new(self.stdin_filepath.as_deref().unwrap_or("."))
+    }
+}
+
+#[derive(Debug, Options)]
+struct OutlineOptions {
+    #[options(free, help = "input files [default: stdin]")]
+    files: Vec<String>,
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(help = "print the outline as JSON")]
+    json: bool,
+}
+
+#[derive(Debug, Options)]
+struct LintOptions {
+    #[options(free, help = "input files [default: stdin]")]
+    files: Vec<String>,
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(no_short, meta = "FILE", help = "check citations against this database instead of the ones the document names")]
+    bib: Vec<String>,
+    #[options(no_short, meta = "RULE", help = "run this lint rule even if it is disabled")]
+    enable: Vec<String>,
+    #[options(no_short, meta = "RULE", help = "do not run this lint rule")]
+    disable: Vec<String>,
+    #[options(no_short, help = "list the lint rules and exit")]
+    list_rules: bool,
+    #[options(no_short, help = "apply the fixes of the lint rules to the files, and report what is left")]
+    fix: bool,
+    #[options(no_short, meta = "FORMAT", default = "human", help = "how problems are printed: human, short or json [default: human]")]
+    message_format: MessageFormat,
+}
+
+/// How diagnostics are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MessageFormat {
+    /// Reports quoting the source, see [`diagnostics::render`].
+    Human,
+    /// One `file:line:column: severity: message` line per diagnostic.
+    Short,
+    /// One JSON object per line.
+    Json,
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "short" => Ok(MessageFormat::Short),
+            "json" => Ok(MessageFormat::Json),
+            _ => Err(format!("unknown message format `{s}`, expected human, short or json")),
+        }
+    }
+}
+
+/// Prints diagnostics in a [`MessageFormat`].
+struct Reporter<W> {
+    format: MessageFormat,
+    out: W,
+    /// Whether any diagnostic was printed.
+    found: bool,
+}
+
+impl<W: Write> Reporter<W> {
+    fn new(format: MessageFormat, out: W) -> Reporter<W> {
+        Reporter { format, out, found: false }
+    }
+
+    /// Print `diagnostic`, found in the source `src` of the file `name`.
+    fn report(&mut self, name: &str, src: &str, diagnostic: &Diagnostic) -> Result<()> {
+        self.found = true;
+        let (line, column) = span::LineIndex::new(src).line_col(diagnostic.span.start);
+        match self.format {
+            MessageFormat::Human => writeln!(self.out, "{}", diagnostics::render(diagnostic, name, src))?,
+            MessageFormat::Short => {
+                let code = diagnostic.code.as_ref().map(|code| format!(" [{code}]")).unwrap_or_default();
+                writeln!(self.out, "{name}:{}:{}: {}: {}{code}", line + 1, column + 1, diagnostic.severity, diagnostic.message)?;
+            }
+            MessageFormat::Json => {
+                let mut value = serde_json::to_value(diagnostic)?;
+                value["file"] = name.into();
+                value["line"] = (line + 1).into();
+                value["column"] = (column + 1).into();
+                writeln!(self.out, "{value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Options)]
+struct FlattenCommandOptions {
+    #[options(free, required, help = "root file of the document")]
+    root: String,
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(help = "output file [default: stdout]")]
+    output: Option<String>,
+    #[options(help = "remove comments", no_short)]
+    strip_comments: bool,
+    #[options(help = "replace \\bibliography with the .bbl file of the root", no_short)]
+    inline_bbl: bool,
+}
+
+#[derive(Debug, Options)]
+struct CompareOptions {
+    #[options(free, help = "the two documents to compare")]
+    files: Vec<String>,
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(help = "print the difference as JSON")]
+    json: bool,
+}
+
+#[derive(Debug, Options)]
+struct DiffOptions {
+    #[options(free, help = "the old and new revisions of the document")]
+    files: Vec<String>,
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(help = "print the edits as JSON, one per line")]
+    json: bool,
+}
+
+#[derive(Debug, Options)]
+struct LatexdiffOptions {
+    #[options(free, help = "the old and new revisions of the document")]
+    files: Vec<String>,
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(help = "output file [default: stdout]")]
+    output: Option<String>,
+    #[options(help = "inline the files included by both revisions first", no_short)]
+    flatten: bool,
+}
+
+#[derive(Debug, Options)]
+struct CountCommandOptions {
+    #[options(free, help = "input files [default: stdin]")]
+    files: Vec<String>,
+    #[options(help = "print help message")]
+    help: bool,
+    #[options(help = "print the counts as JSON")]
+    json: bool,
+}
+
+/// Parse `args` as options of `T`, exiting with a message on error.
+fn parse_args_or_exit<T: Options>(args: &[String]) -> T {
+    T::parse_args(args, ParsingStyle::default()).unwrap_or_else(|e| {
+        let program = std::env::args().next().unwrap_or_default();
+        eprintln!("{program}: {e}");
+        std::process::exit(2);
+    })
+}
+
+/// Read the content of `files`, or stdin if there are none, with their names.
+fn read_inputs(files: &[String]) -> Result<Vec<(String, String)>> {
+    if files.is_empty() {
+        let mut buffer = String::new();
+        io::stdin().read_to_string(&mut buffer)?;
+        return Ok(vec![("<stdin>".to_string(), buffer)]);
+    }
+    files.iter().map(|file| Ok((file.clone(), fs::read_to_string(file)?))).collect()
+}
+
+fn print_sections(sections: &[Section], depth: usize, out: &mut impl Write) -> Result<()> {
+    for section in sections {
+        let star = if section.starred { "*" } else { "" };
+        write!(out, "{:indent$}{}{star} {} (line {})", "", section.kind.macro_name(), section.title, section.position.start.line, indent = depth * 2)?;
+        for label in &section.labels {
+            write!(out, " [{}]", label.name)?;
+        }
+        writeln!(out)?;
+        print_sections(&section.children, depth + 1, out)?;
+    }
+    Ok(())
+}
+
+fn run_outline(args: &[String]) -> Result<()> {
+    let opts: OutlineOptions = parse_args_or_exit(args);
+    if opts.help {
+        println!("Usage: latexformat outline [OPTIONS] [FILES]\n\n{}", OutlineOptions::usage());
+        return Ok(());
+    }
+    let mut stdout = io::stdout();
+    for (_, input) in read_inputs(&opts.files)? {
+        let outline = outline(&unlatex::parse_borrowed(&input)?);
+        if opts.json {
+            serde_json::to_writer_pretty(&mut stdout, &outline)?;
+            writeln!(stdout)?;
+        } else {
+            print_sections(&outline.sections, 0, &mut stdout)?;
+        }
+    }
+    Ok(())
+}
+
+/// Serves the document read from stdin, and the files it includes from the file system.
+struct StdinLoader {
+    path: PathBuf,
+    source: String,
+}
+
+impl Loader for StdinLoader {
+    fn load(&self, path: &Path) -> io::Result<String> {
+        if path == self.path {
+            Ok(self.source.clone())
+        } else {
+            FsLoader.load(path)
+        }
+    }
+}
+
+fn run_lint(args: &[String]) -> Result<()> {
+    let opts: LintOptions = parse_args_or_exit(args);
+    if opts.help {
+        println!("Usage: latexformat lint [OPTIONS] [FILES]\n\n{}", LintOptions::usage());
+        return Ok(());
+    }
+    if opts.list_rules {
+        let linter = Linter::new(Default::default());
+        for rule in linter.rules() {
+            let off = if linter.is_enabled(rule) { "" } else { " (disabled)" };
+            println!("{:<20} {}: {}{off}", rule.id(), rule.severity(), rule.description());
+        }
+        return Ok(());
+    }
+    if opts.fix && opts.files.is_empty() {
+        return Err(anyhow!("--fix needs files to fix"));
+    }
+    let mut reporter = Reporter::new(opts.message_format, io::stdout());
+    for (name, input) in read_inputs(&opts.files)? {
+        // rules are configured for the whole document by the configuration of its root
+        let mut lint_options = Config::discover(&name)?.map(|config| config.lint).unwrap_or_default();
+        lint_options.enable.extend(opts.enable.iter().cloned());
+        lint_options.disable.extend(opts.disable.iter().cloned());
+        let linter = Linter::new(lint_options);
+
+        // each input is the root of a document, whose included files are checked with it
+        let loader = StdinLoader { path: PathBuf::from(&name), source: input };
+        let mut project = Project::load_with(&name, &loader)?;
+        if opts.fix {
+            for file in &project.files {
+                let fixed = linter.fix(&file.source)?;
+                if fixed != file.source {
+                    fs::write(&file.path, fixed)?;
+                }
+            }
+            project = Project::load(&name)?;
+        }
+        let maps: Vec<span::OffsetMap> = project.files.iter().map(|file| span::OffsetMap::new(&file.source)).collect();
+        let mut report = |file: usize, diagnostic: Diagnostic| {
+            let file = &project.files[file];
+            reporter.report(&file.path.display().to_string(), &file.source, &diagnostic)
+        };
+        let warning = |file: usize, message: String, position: &unlatex::info::PositionInfo| {
+            let span = maps[file].to_byte(position.start.offset)..maps[file].to_byte(position.end.offset);
+            Diagnostic::new(Severity::Warning, message, span)
+        };
+
+        for (index, file) in project.files.iter().enumerate() {
+            let mut diagnostics = diagnostics::check(&file.ast, &file.source);
+            diagnostics.extend(linter.check(&file.ast, &file.source).iter().map(|d| d.to_diagnostic(&maps[index])));
+            diagnostics.sort_by_key(|d| d.span.start);
+            for diagnostic in diagnostics {
+                report(index, diagnostic)?;
+            }
+        }
+        for issue in &project.issues {
+            let (file, include) = match issue {
+                ProjectIssue::MissingFile { file, include, .. } | ProjectIssue::Cycle { file, include } => (file, include),
+            };
+            report(*file, Diagnostic::new(Severity::Warning, issue.to_string(), include.range.clone()))?;
+        }
+        let mut issues = project.references().check();
+        issues.sort_by_key(|issue| (issue.file(), issue.position().start.offset));
+        for issue in issues {
+            report(issue.file(), warning(issue.file(), issue.to_string(), issue.position()))?;
+        }
+        lint_citations(&opts, &project, &mut reporter, &maps)?;
+    }
+    if reporter.found {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Check the citations of `project` against its databases, reporting the issues.
+fn lint_citations(opts: &LintOptions, project: &Project, reporter: &mut Reporter<impl Write>, maps: &[span::OffsetMap]) -> Result<()> {
+    let citations = project.citations();
+    let paths: Vec<PathBuf> = if opts.bib.is_empty() {
+        // databases are named relative to the root document, `.bib` being implied
+        let dir = project.root().path.parent().unwrap_or(Path::new(""));
+        citations
+            .databases
+            .iter()
+            .map(|db| {
+                let path = dir.join(db);
+                if path.extension().is_some() { path } else { path.with_extension("bib") }
+            })
+            .collect()
+    } else {
+        opts.bib.iter().map(PathBuf::from).collect()
+    };
+    if paths.is_empty() {
+        return Ok(());
+    }
+
+    let sources = paths
+        .iter()
+        .map(|path| fs::read_to_string(path).map_err(|e| anyhow!("{}: {e}", path.display())))
+        .collect::<Result<Vec<_>>>()?;
+    let databases: Vec<Bibliography> = sources.iter().map(|src| Bibliography::parse(src)).collect();
+    for ((path, src), db) in paths.iter().zip(&sources).zip(&databases) {
+        for error in &db.errors {
+            let diagnostic = Diagnostic::new(Severity::Warning, error.message.clone(), error.span.clone());
+            reporter.report(&path.display().to_string(), src, &diagnostic)?;
+        }
+    }
+    for issue in citations.check(&databases) {
+        match &issue {
+            CiteIssue::MissingEntry { position, file, .. } => {
+                let span = maps[*file].to_byte(position.start.offset)..maps[*file].to_byte(position.end.offset);
+                let file = &project.files[*file];
+                let diagnostic = Diagnostic::new(Severity::Warning, issue.to_string(), span);
+                reporter.report(&file.path.display().to_string(), &file.source, &diagnostic)?;
+            }
+            CiteIssue::UncitedEntry { database, span, .. } => {
+                let diagnostic = Diagnostic::new(Severity::Warning, issue.to_string(), span.clone());
+                reporter.report(&paths[*database].display().to_string(), &sources[*database], &diagnostic)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_flatten(args: &[String]) -> Result<()> {
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("Usage: latexformat flatten [OPTIONS] ROOT\n\n{}", FlattenCommandOptions::usage());
+        return Ok(());
+    }
+    let opts: FlattenCommandOptions = parse_args_or_exit(args);
+    let flatten_opts = FlattenOptions {
+        strip_comments: opts.strip_comments,
+        inline_bbl: opts.inline_bbl,
+    };
+    let flat = flatten_with(&opts.root, &flatten_opts, &FsLoader)?;
+    match &opts.output {
+        Some(output) => fs::write(output, flat)?,
+        None => io::stdout().write_all(flat.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn run_compare(args: &[String]) -> Result<()> {
+    let opts: CompareOptions = parse_args_or_exit(args);
+    if opts.help {
+        println!("Usage: latexformat compare [OPTIONS] LEFT RIGHT\n\n{}", CompareOptions::usage());
+        return Ok(());
+    }
+    let [left, right] = opts.files.as_slice() else {
+        return Err(anyhow!("compare needs two files"));
+    };
+    let (left_src, right_src) = (fs::read_to_string(left)?, fs::read_to_string(right)?);
+    let Some(difference) = semantic_diff(&left_src, &right_src)? else {
+        return Ok(());
+    };
+    let mut stdout = io::stdout();
+    if opts.json {
+        let mut value = serde_json::to_value(&difference)?;
+        for (side, name, src, span) in [("left", left, &left_src, &difference.left), ("right", right, &right_src, &difference.right)] {
+            let (line, column) = span::LineIndex::new(src).line_col(span.start);
+            value[format!("{side}_file")] = name.as_str().into();
+            value[format!("{side}_line")] = (line + 1).into();
+            value[format!("{side}_column")] = (column + 1).into();
+        }
+        writeln!(stdout, "{value}")?;
+    } else {
+        let message = format!("the documents differ: {difference}");
+        let left_diagnostic = Diagnostic::new(Severity::Error, message, difference.left.clone());
+        let right_diagnostic = Diagnostic::new(Severity::Info, format!("{} in {right}", difference.right_node), difference.right.clone());
+        writeln!(stdout, "{}", diagnostics::render(&left_diagnostic, left, &left_src))?;
+        write!(stdout, "{}", diagnostics::render(&right_diagnostic, right, &right_src))?;
+    }
+    std::process::exit(1);
+}
+
+fn run_diff(args: &[String]) -> Result<()> {
+    let opts: DiffOptions = parse_args_or_exit(args);
+    if opts.help {
+        println!("Usage: latexformat diff [OPTIONS] OLD NEW\n\n{}", DiffOptions::usage());
+        return Ok(());
+    }
+    let [old, new] = opts.files.as_slice() else {
+        return Err(anyhow!("diff needs two files"));
+    };
+    let (old_src, new_src) = (fs::read_to_string(old)?, fs::read_to_string(new)?);
+    let edits = diff(&old_src, &new_src)?;
+    let mut stdout = io::stdout();
+    if !opts.json {
+        write!(stdout, "{}", report(&edits, &old_src, &new_src))?;
+        return Ok(());
+    }
+    let (old_index, new_index) = (span::LineIndex::new(&old_src), span::LineIndex::new(&new_src));
+    for edit in &edits {
+        let mut value = serde_json::to_value(edit)?;
+        for (side, index, range) in [("old", &old_index, edit.old_range()), ("new", &new_index, edit.new_range())] {
+            if let Some(range) = range {
+                value[format!("{side}_line")] = (index.line_col(range.start).0 + 1).into();
+            }
+        }
+        writeln!(stdout, "{value}")?;
+    }
+    Ok(())
+}
+
+fn run_latexdiff(args: &[String]) -> Result<()> {
+    let opts: LatexdiffOptions = parse_args_or_exit(args);
+    if opts.help {
+        println!("Usage: latexformat latexdiff [OPTIONS] OLD NEW\n\n{}", LatexdiffOptions::usage());
+        return Ok(());
+    }
+    let [old, new] = opts.files.as_slice() else {
+        return Err(anyhow!("latexdiff needs two files"));
+    };
+    let read = |path: &String| -> Result<String> {
+        Ok(match opts.flatten {
+            true => flatten_with(path, &FlattenOptions::default(), &FsLoader)?,
+            false => fs::read_to_string(path)?,
+        })
+    };
+    let marked = mark_changes(&read(old)?, &read(new)?)?;
+    match &opts.output {
+        Some(output) => fs::write(output, marked)?,
+        None => io::stdout().write_all(marked.as_bytes())?,
+    }
+    Ok(())
+}
+
+fn print_counts(counts: &Counts, out: &mut impl Write) -> Result<()> {
+    writeln!(out, "Words in text: {}", counts.words)?;
+    writeln!(out, "Words in headers: {}", counts.header_words)?;
+    writeln!(out, "Words in captions: {}", counts.caption_words)?;
+    writeln!(out, "Words in footnotes: {}", counts.footnote_words)?;
+    writeln!(out, "Characters in text: {}", counts.characters)?;
+    writeln!(out, "Inline equations: {}", counts.inline_equations)?;
+    writeln!(out, "Display equations: {}", counts.display_equations)?;
+    writeln!(out, "Figures: {}", counts.figures)?;
+    writeln!(out, "Tables: {}", counts.tables)?;
+    writeln!(out, "Citations: {}", counts.citations)?;
+    Ok(())
+}
+
+fn print_section_counts(sections: &[SectionStats], depth: usize, out: &mut impl Write) -> Result<()> {
+    for section in sections {
+        let star = if section.starred { "*" } else { "" };
+        let Counts { words, header_words, caption_words, footnote_words, .. } = section.total;
+        writeln!(
+            out,
+            "{:indent$}{words}+{header_words}+{caption_words}+{footnote_words} {}{star} {} (line {})",
+            "",
+            section.kind.macro_name(),
+            section.title,
+            section.position.start.line,
+            indent = depth * 2 + 2,
+        )?;
+        print_section_counts(&section.children, depth + 1, out)?;
+    }
+    Ok(())
+}
+
+fn run_count(args: &[String]) -> Result<()> {
+    let opts: CountCommandOptions = parse_args_or_exit(args);
+    if opts.help {
+        println!("Usage: latexformat count [OPTIONS] [FILES]\n\n{}", CountCommandOptions::usage());
+        return Ok(());
+    }
+    let mut stdout = io::stdout();
+    let inputs = read_inputs(&opts.files)?;
+    for (i, (name, input)) in inputs.iter().enumerate() {
+        let count_options = Config::discover(name)?.map(|config| config.count).unwrap_or_default();
+        // included files are counted where they are included
+        let loader = StdinLoader { path: PathBuf::from(name), source: input.clone() };
+        let stats = Project::load_with(name, &loader)?.stats(&count_options);
+        if opts.json {
+            serde_json::to_writer_pretty(&mut stdout, &stats)?;
+            writeln!(stdout)?;
+            continue;
+        }
+        if inputs.len() > 1 {
+            writeln!(stdout, "{}{name}:", if i > 0 { "\n" } else { "" })?;
+        }
+        print_counts(&stats.total, &mut stdout)?;
+        if !stats.sections.is_empty() {
+            writeln!(stdout, "Words in text+headers+captions+footnotes per section:")?;
+            print_section_counts(&stats.sections, 0, &mut stdout)?;
+        }
+    }
+    Ok(())
 }
 
 fn main() -> Result<()> {
-    let opts = UnLaTexOptions::parse_args_default_or_exit();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("outline") => run_outline(&args[1..]),
+        Some("lint") => run_lint(&args[1..]),
+        Some("flatten") => run_flatten(&args[1..]),
+        Some("compare") => run_compare(&args[1..]),
+        Some("diff") => run_diff(&args[1..]),
+        Some("latexdiff") => run_latexdiff(&args[1..]),
+        Some("count") => run_count(&args[1..]),
+        _ => run_format(&args),
+    }
+}
+
+fn run_format(args: &[String]) -> Result<()> {
+    let opts: UnLaTexOptions = parse_args_or_exit(args);
     if opts.help {
-        println!("{}", UnLaTexOptions::usage());
+        println!("{}\n\n{COMMANDS}", UnLaTexOptions::usage());
     } else {
-        if let Some(workdir) = opts.workdir {
+        if let Some(workdir) = &opts.workdir {
             std::env::set_current_dir(workdir)?;
         }
         // get the thing we can read from
-        match (opts.files.len(), opts.output) {
+        match (opts.files.len(), &opts.output) {
             (0, Some(output)) => {
                 let mut buffer = String::new();
                 io::stdin().read_to_string(&mut buffer)?;
-                let formatted = format_with_opts(&buffer, opts.print_width, opts.use_tabs, opts.tab_width, opts.document_only)?;
+                let formatted = opts.format(&buffer, opts.stdin_path())?;
                 let mut f = fs::File::create(output)?;
                 f.write_all(formatted.as_bytes())?;
             }
             (0, None) => {
                 let mut buffer = String::new();
                 io::stdin().read_to_string(&mut buffer).unwrap();
-                let output = format_with_opts(&buffer, opts.print_width, opts.use_tabs, opts.tab_width, opts.document_only)?;
+                let output = opts.format(&buffer, opts.stdin_path())?;
                 io::stdout().write_all(output.as_bytes())?;
             }
             _ => {
-                for file in opts.files {
+                for file in &opts.files {
                     let mut buffer = String::new();
-                    let mut f = fs::File::open(&file)?;
+                    let mut f = fs::File::open(file)?;
                     f.read_to_string(&mut buffer)?;
-                    let formatted = format_with_opts(&buffer, opts.print_width, opts.use_tabs, opts.tab_width, opts.document_only)?;
+                    let formatted = opts.format(&buffer, Path::new(file))?;
 
                     if opts.overwrite {
-                        let mut f = fs::File::create(&file)?;
+                        let mut f = fs::File::create(file)?;
                         f.write_all(formatted.as_bytes())?;
                     } else {
                         io::stdout().write_all(formatted.as_bytes())?;
@@ -64,7 +793,11 @@ fn main() -> Result<()> {
                 }
             }
         };
+        if let Some(cache) = opts.cache() {
+            // a cache which cannot be pruned only takes more space
+            let _ = cache.prune(cache::MAX_AGE, cache::MAX_SIZE);
+        }
     };
 
     Ok(())
-}
\ No newline at end of file
+}
@@ -1,4 +1,40 @@
+use std::fs;
+use std::path::Path;
+
 fn main() {
     println!("cargo:rerun-if-changed=js/");
     println!("cargo:rerun-if-changed=vendor/");
+    println!("cargo:rerun-if-changed=src/");
+    // the cache of formatted documents is invalidated by any change to the sources
+    let mut digest = Fnv(0xcbf2_9ce4_8422_2325);
+    hash_dir(Path::new("src"), &mut digest);
+    println!("cargo:rustc-env=UNLATEX_SOURCE_DIGEST={:016x}", digest.0);
+}
+
+/// FNV-1a, as the standard hashers are not guaranteed to be stable.
+struct Fnv(u64);
+
+impl Fnv {
+    fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+/// Hash the paths and contents of the files under `dir`, in a stable order.
+fn hash_dir(dir: &Path, digest: &mut Fnv) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries.filter_map(|entry| entry.ok().map(|entry| entry.path())).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            hash_dir(&path, digest);
+        } else if let Ok(content) = fs::read(&path) {
+            digest.update(path.to_string_lossy().as_bytes());
+            digest.update(&content);
+        }
+    }
 }
@@ -0,0 +1,91 @@
+// Generate `src/native/db.rs`, the macros and environments known to the vendored
+// unified-latex parser, for the native parser:
+//
+//     node js/native-db.js > src/native/db.rs
+const fs = require("fs");
+const path = require("path");
+const vm = require("vm");
+
+let src = fs.readFileSync(path.join(__dirname, "../vendor/unlatex.umd.js"), "utf8");
+// expose the package definitions merged by the parser
+const marker = "Uc=Ws().use(";
+if (!src.includes(marker)) {
+    throw new Error("cannot find the parser in the vendored bundle");
+}
+src = src.replace(marker, "Uc=(globalThis.__db={macros:bc,environments:vc},Ws()).use(");
+const context = { console };
+context.globalThis = context;
+vm.createContext(context);
+vm.runInContext("var module=undefined, exports=undefined, define=undefined;" + src, context);
+
+const macros = Object.assign({}, ...Object.values(context.__db.macros));
+const environments = Object.assign({}, ...Object.values(context.__db.environments));
+
+const str = (s) => JSON.stringify(s);
+const option = (v, f = str) => (v == null ? "None" : `Some(${f(v)})`);
+const FLAGS = {
+    alignContent: "align_content",
+    inParMode: "in_par_mode",
+    pgfkeysArgs: "pgfkeys_args",
+    breakAround: "break_around",
+    inMathMode: "in_math_mode",
+    hangingIndent: "hanging_indent",
+};
+function render(info) {
+    const fields = [];
+    for (const [key, value] of Object.entries(info)) {
+        if (key === "namedArguments") {
+            fields.push(`named_arguments: &[${value.map((name) => option(name)).join(", ")}]`);
+        } else if (FLAGS[key] == null) {
+            throw new Error(`unknown render info ${key}`);
+        } else if (value) {
+            fields.push(`${FLAGS[key]}: true`);
+        }
+    }
+    return `Render { ${[...fields, "..Render::NONE"].join(", ")} }`;
+}
+function process(f) {
+    if (f == null) {
+        return "Process::None";
+    }
+    const source = String(f);
+    if (source.startsWith("function yc(")) {
+        return `Process::Items("item")`;
+    }
+    const items = source.match(/^e=>yc\(e,"(\w+)"\)$/);
+    if (items) {
+        return `Process::Items(${str(items[1])})`;
+    }
+    if (source === "e=>(dl(e),e)") {
+        return "Process::Trim";
+    }
+    throw new Error(`unknown content processing ${source}`);
+}
+const sorted = (object) => Object.entries(object).sort(([a], [b]) => (a < b ? -1 : a > b ? 1 : 0));
+
+const out = [];
+out.push("//! Macros and environments of the unified-latex packages, whose arguments the");
+out.push("//! [native parser](super) attaches.");
+out.push("//!");
+out.push("//! Generated from the vendored parser by `node js/native-db.js > src/native/db.rs`.");
+out.push("");
+out.push("use super::{Env, Macro, Process, Render};");
+out.push("");
+out.push("/// Known macros, sorted by name.");
+out.push("pub(super) const MACROS: &[(&str, Macro)] = &[");
+for (const [name, info] of sorted(macros)) {
+    out.push(
+        `    (${str(name)}, Macro { signature: ${option(info.signature)}, render: ${option(info.renderInfo, render)}, escape_token: ${option(info.escapeToken)} }),`
+    );
+}
+out.push("];");
+out.push("");
+out.push("/// Known environments, sorted by name.");
+out.push("pub(super) const ENVIRONMENTS: &[(&str, Env)] = &[");
+for (const [name, info] of sorted(environments)) {
+    out.push(
+        `    (${str(name)}, Env { signature: ${option(info.signature)}, render: ${option(info.renderInfo, render)}, process: ${process(info.processContent)} }),`
+    );
+}
+out.push("];");
+console.log(out.join("\n"));
@@ -0,0 +1,4 @@
+//! Analyses of documents over the AST.
+
+pub mod citations;
+pub mod refs;
@@ -0,0 +1,184 @@
+//! Citations of BibTeX and BibLaTeX entries.
+//!
+//! ```
+//! use unlatex::analysis::citations::{Citations, CiteIssue};
+//! use unlatex::bib::Bibliography;
+//!
+//! let ast = unlatex::parse(r"see \citep[p.~3]{knuth84,lamport94}").unwrap();
+//! let bib = Bibliography::parse("@book{knuth84, title = {TeX}}");
+//! let citations = Citations::collect(&ast);
+//! assert_eq!(citations.citations[0].postnote.as_deref(), Some("p.~3"));
+//! let issues = citations.check(&[bib]);
+//! assert!(matches!(&issues[..], [CiteIssue::MissingEntry { key, .. }] if key == "lamport94"));
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use crate::ast::Node;
+use crate::bib::Bibliography;
+use crate::info::PositionInfo;
+use crate::macros::{keys, macro_call, text, visit_lists};
+
+/// Macros citing a comma separated list of keys, from LaTeX, `natbib` and `biblatex`.
+pub const CITE_MACROS: &[&str] = &[
+    "cite", "nocite", "citep", "citet", "citealp", "citealt", "citeauthor", "citeyear", "citeyearpar",
+    "Citep", "Citet", "Citealp", "Citealt", "Citeauthor",
+    "parencite", "Parencite", "autocite", "Autocite", "textcite", "Textcite", "footcite", "footcitetext",
+    "smartcite", "Smartcite", "supercite", "fullcite", "footfullcite", "citetitle", "citeurl", "citedate",
+    "Cite",
+];
+
+/// Macros declaring the databases of a document.
+pub const DATABASE_MACROS: &[&str] = &["bibliography", "addbibresource"];
+
+/// A key cited by a citation macro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Citation {
+    /// The cited key.
+    pub key: String,
+    /// Name of the citing macro.
+    pub macro_name: String,
+    /// The pre-note, given as the first of two optional arguments.
+    pub prenote: Option<String>,
+    /// The post-note, given as the last optional argument.
+    pub postnote: Option<String>,
+    /// Position of the citing macro.
+    pub position: PositionInfo,
+    /// Index of the file the citation is in, see [`Project`](crate::project::Project).
+    /// Always 0 for a single document.
+    pub file: usize,
+}
+
+/// The citations of a document.
+#[derive(Debug, Clone, Default)]
+pub struct Citations {
+    /// Cited keys, in document order.
+    pub citations: Vec<Citation>,
+    /// Whether `\nocite{*}` cites every entry of the databases.
+    pub cite_all: bool,
+    /// Databases named by `\bibliography` and `\addbibresource`, as written.
+    pub databases: Vec<String>,
+}
+
+/// A problem found by [`Citations::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CiteIssue {
+    /// A cited key with no entry in any database.
+    MissingEntry {
+        /// The cited key.
+        key: String,
+        /// Position of the citing macro.
+        position: PositionInfo,
+        /// Index of the file of the citing macro, see [`Citation::file`].
+        file: usize,
+    },
+    /// An entry which is never cited.
+    UncitedEntry {
+        /// Citation key of the entry.
+        key: String,
+        /// Index of the database in the slice given to [`Citations::check`].
+        database: usize,
+        /// Byte range of the entry key in the database source.
+        span: Range<usize>,
+    },
+}
+
+impl Display for CiteIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CiteIssue::MissingEntry { key, .. } => write!(f, "citation of undefined entry `{key}`"),
+            CiteIssue::UncitedEntry { key, .. } => write!(f, "entry `{key}` is never cited"),
+        }
+    }
+}
+
+impl Citations {
+    /// Collect the citations of the document `root`.
+    pub fn collect(root: &Node) -> Citations {
+        let mut citations = Citations::default();
+        visit_lists(root, &mut |siblings| {
+            for i in 0..siblings.len() {
+                let Some(call) = macro_call(siblings, i, 1) else {
+                    continue;
+                };
+                let Some(arg) = call.mandatory.last() else {
+                    continue;
+                };
+                if DATABASE_MACROS.contains(&call.name) {
+                    citations.databases.extend(keys(arg));
+                    continue;
+                }
+                if !CITE_MACROS.contains(&call.name) {
+                    continue;
+                }
+                let Some(position) = call.node.position() else {
+                    continue;
+                };
+                let notes: Vec<String> = call.optional.iter().map(|note| text(note)).collect();
+                let (prenote, postnote) = match &notes[..] {
+                    [] => (None, None),
+                    [post] => (None, Some(post.clone())),
+                    [.., pre, post] => (Some(pre.clone()), Some(post.clone())),
+                };
+                for key in keys(arg) {
+                    if key == "*" && call.name == "nocite" {
+                        citations.cite_all = true;
+                        continue;
+                    }
+                    citations.citations.push(Citation {
+                        key,
+                        macro_name: call.name.to_string(),
+                        prenote: prenote.clone(),
+                        postnote: postnote.clone(),
+                        position: position.clone(),
+                        file: 0,
+                    });
+                }
+            }
+        });
+        citations.citations.sort_by_key(|c| c.position.start.offset);
+        citations
+    }
+
+    /// Add the citations of `other`, e.g. another file of the same document.
+    pub fn extend(&mut self, other: Citations) {
+        self.citations.extend(other.citations);
+        self.cite_all |= other.cite_all;
+        self.databases.extend(other.databases);
+    }
+
+    /// Find the citations missing from `databases` in document order, then the entries
+    /// never cited in database order.
+    pub fn check(&self, databases: &[Bibliography]) -> Vec<CiteIssue> {
+        let mut issues = vec![];
+
+        let defined: HashSet<&str> = databases.iter().flat_map(|db| db.entries()).map(|e| e.key.as_str()).collect();
+        for citation in &self.citations {
+            if !defined.contains(citation.key.as_str()) {
+                issues.push(CiteIssue::MissingEntry {
+                    key: citation.key.clone(),
+                    position: citation.position.clone(),
+                    file: citation.file,
+                });
+            }
+        }
+
+        if !self.cite_all {
+            let cited: HashSet<&str> = self.citations.iter().map(|c| c.key.as_str()).collect();
+            for (database, db) in databases.iter().enumerate() {
+                for entry in db.entries() {
+                    if !cited.contains(entry.key.as_str()) {
+                        issues.push(CiteIssue::UncitedEntry {
+                            key: entry.key.clone(),
+                            database,
+                            span: entry.key_span.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+}
@@ -0,0 +1,220 @@
+//! Cross-references between `\label` and `\ref`-like macros.
+//!
+//! ```
+//! use unlatex::analysis::refs::{check, RefIssue};
+//!
+//! let ast = unlatex::parse(r"\section{A}\label{sec:a} see \cref{sec:a,sec:b}").unwrap();
+//! let issues = check(&ast);
+//! assert!(matches!(&issues[..], [RefIssue::UndefinedReference { name, .. }] if name == "sec:b"));
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+use crate::ast::Node;
+use crate::info::PositionInfo;
+use crate::macros::{keys, macro_call, visit_lists};
+
+/// Macros defining a label.
+pub const LABEL_MACROS: &[&str] = &["label"];
+
+/// Macros referencing labels. All of them accept a comma separated list of labels,
+/// which is only meaningful for the `cleveref` ones.
+pub const REFERENCE_MACROS: &[&str] = &[
+    "ref", "eqref", "pageref", "autoref", "nameref", "vref", "vpageref",
+    "cref", "Cref", "cpageref", "Cpageref", "labelcref", "namecref", "nameCref",
+];
+
+/// A `\label` definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelDef {
+    /// The label key.
+    pub name: String,
+    /// Position of the `\label` macro.
+    pub position: PositionInfo,
+    /// Index of the file the label is in, see [`Project`](crate::project::Project).
+    /// Always 0 for a single document.
+    pub file: usize,
+}
+
+/// A reference to a label.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// The referenced label key.
+    pub name: String,
+    /// Name of the referencing macro.
+    pub macro_name: String,
+    /// Position of the referencing macro.
+    pub position: PositionInfo,
+    /// Index of the file the reference is in, see [`LabelDef::file`].
+    pub file: usize,
+}
+
+/// The labels and references of a document.
+#[derive(Debug, Clone, Default)]
+pub struct References {
+    /// Label definitions, in document order.
+    pub labels: Vec<LabelDef>,
+    /// References, in document order.
+    pub references: Vec<Reference>,
+}
+
+/// A problem found by [`References::check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefIssue {
+    /// A reference to a label which is not defined.
+    UndefinedReference {
+        /// The referenced label key.
+        name: String,
+        /// Position of the referencing macro.
+        position: PositionInfo,
+        /// Index of the file of the referencing macro.
+        file: usize,
+    },
+    /// A label defined more than once.
+    DuplicateLabel {
+        /// The label key.
+        name: String,
+        /// Position of the second definition.
+        position: PositionInfo,
+        /// Position of the first definition.
+        first: PositionInfo,
+        /// Index of the file of the second definition.
+        file: usize,
+        /// Index of the file of the first definition.
+        first_file: usize,
+    },
+    /// A label which is never referenced.
+    UnusedLabel {
+        /// The label key.
+        name: String,
+        /// Position of the `\label` macro.
+        position: PositionInfo,
+        /// Index of the file of the `\label` macro.
+        file: usize,
+    },
+}
+
+impl RefIssue {
+    /// Position of the macro the issue is about.
+    pub fn position(&self) -> &PositionInfo {
+        match self {
+            RefIssue::UndefinedReference { position, .. }
+            | RefIssue::DuplicateLabel { position, .. }
+            | RefIssue::UnusedLabel { position, .. } => position,
+        }
+    }
+
+    /// Index of the file of the macro the issue is about, see [`LabelDef::file`].
+    pub fn file(&self) -> usize {
+        match self {
+            RefIssue::UndefinedReference { file, .. }
+            | RefIssue::DuplicateLabel { file, .. }
+            | RefIssue::UnusedLabel { file, .. } => *file,
+        }
+    }
+}
+
+impl Display for RefIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RefIssue::UndefinedReference { name, .. } => write!(f, "reference to undefined label `{name}`"),
+            RefIssue::DuplicateLabel { name, first, .. } => {
+                write!(f, "label `{name}` is already defined at line {}", first.start.line)
+            }
+            RefIssue::UnusedLabel { name, .. } => write!(f, "label `{name}` is never referenced"),
+        }
+    }
+}
+
+impl References {
+    /// Collect the labels and references of the document `root`.
+    pub fn collect(root: &Node) -> References {
+        let mut refs = References::default();
+        visit_lists(root, &mut |siblings| {
+            for i in 0..siblings.len() {
+                let Some(call) = macro_call(siblings, i, 1) else {
+                    continue;
+                };
+                let Some(position) = call.node.position() else {
+                    continue;
+                };
+                let Some(arg) = call.mandatory.last() else {
+                    continue;
+                };
+                if LABEL_MACROS.contains(&call.name) {
+                    let name = crate::macros::text(arg);
+                    refs.labels.push(LabelDef { name, position: position.clone(), file: 0 });
+                } else if REFERENCE_MACROS.contains(&call.name) {
+                    for name in keys(arg) {
+                        refs.references.push(Reference {
+                            name,
+                            macro_name: call.name.to_string(),
+                            position: position.clone(),
+                            file: 0,
+                        });
+                    }
+                }
+            }
+        });
+        refs.labels.sort_by_key(|l| l.position.start.offset);
+        refs.references.sort_by_key(|r| r.position.start.offset);
+        refs
+    }
+
+    /// Add the labels and references of `other`, e.g. another file of the same document.
+    pub fn extend(&mut self, other: References) {
+        self.labels.extend(other.labels);
+        self.references.extend(other.references);
+    }
+
+    /// Find undefined references, duplicate labels and unused labels, in document order
+    /// within each kind.
+    pub fn check(&self) -> Vec<RefIssue> {
+        let mut issues = vec![];
+
+        let mut defined: HashMap<&str, &LabelDef> = HashMap::new();
+        for label in &self.labels {
+            match defined.get(label.name.as_str()) {
+                Some(first) => issues.push(RefIssue::DuplicateLabel {
+                    name: label.name.clone(),
+                    position: label.position.clone(),
+                    first: first.position.clone(),
+                    file: label.file,
+                    first_file: first.file,
+                }),
+                None => {
+                    defined.insert(&label.name, label);
+                }
+            }
+        }
+
+        for reference in &self.references {
+            if !defined.contains_key(reference.name.as_str()) {
+                issues.push(RefIssue::UndefinedReference {
+                    name: reference.name.clone(),
+                    position: reference.position.clone(),
+                    file: reference.file,
+                });
+            }
+        }
+
+        let used: HashSet<&str> = self.references.iter().map(|r| r.name.as_str()).collect();
+        let mut reported = HashSet::new();
+        for label in &self.labels {
+            if !used.contains(label.name.as_str()) && reported.insert(label.name.as_str()) {
+                issues.push(RefIssue::UnusedLabel {
+                    name: label.name.clone(),
+                    position: label.position.clone(),
+                    file: label.file,
+                });
+            }
+        }
+        issues
+    }
+}
+
+/// Check the cross-references of the document `root`, see [`References::check`].
+pub fn check(root: &Node) -> Vec<RefIssue> {
+    References::collect(root).check()
+}
@@ -4,6 +4,8 @@ use std::fmt::{Display, Formatter};
 use rquickjs::{Ctx, Error, FromJs, IntoAtom, Object, Value};
 use crate::info::{RenderInfo, PositionInfo};
 
+pub mod borrowed;
+
 #[inline]
 pub(crate) fn get_undefined<'js, K: IntoAtom<'js>, V: FromJs<'js> + Default>(object: &Object<'js>, k: K) -> rquickjs::Result<V> {
     object.get(k).or_else(|e| {
@@ -19,6 +21,15 @@ pub(crate) fn get_undefined<'js, K: IntoAtom<'js>, V: FromJs<'js> + Default>(obj
     })
 }
 
+/// Get the name of an environment, which math environments store as a string node.
+#[inline]
+fn get_env<'js>(object: &Object<'js>) -> rquickjs::Result<String> {
+    match object.get::<_, Value>("env")?.into_object() {
+        Some(env) => get_undefined(&env, "content"),
+        None => get_undefined(object, "env"),
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Node {
     Root {
@@ -71,6 +82,7 @@ pub enum Node {
     VerbatimEnvironment {
         env: String,
         args: Vec<Node>,
+        content: String,
         position: PositionInfo,
         render_info: Option<RenderInfo>,
     },
@@ -106,6 +118,250 @@ pub enum Node {
     Error,
 }
 
+impl Node {
+    /// Position of the node in the source, if the parser recorded one.
+    ///
+    /// Offsets count UTF-16 code units, see [`span`](crate::span) for conversions.
+    pub fn position(&self) -> Option<&PositionInfo> {
+        let position = match self {
+            Node::Root { position, .. }
+            | Node::String { position, .. }
+            | Node::WhiteSpace { position, .. }
+            | Node::Parbreak { position, .. }
+            | Node::Comment { position, .. }
+            | Node::Macro { position, .. }
+            | Node::Environment { position, .. }
+            | Node::MathEnv { position, .. }
+            | Node::VerbatimEnvironment { position, .. }
+            | Node::DisplayMath { position, .. }
+            | Node::Group { position, .. }
+            | Node::InlineMath { position, .. }
+            | Node::Verb { position, .. }
+            | Node::Argument { position, .. } => position,
+            Node::Error => return None,
+        };
+        // lines are 1-based, a zero line means the position was missing
+        (position.start.line > 0).then_some(position)
+    }
+
+    /// Child nodes making up the content of this node.
+    pub fn content(&self) -> &[Node] {
+        match self {
+            Node::Root { content, .. }
+            | Node::Environment { content, .. }
+            | Node::MathEnv { content, .. }
+            | Node::DisplayMath { content, .. }
+            | Node::Group { content, .. }
+            | Node::InlineMath { content, .. }
+            | Node::Argument { content, .. } => content,
+            _ => &[],
+        }
+    }
+
+    /// Arguments attached to a macro or an environment.
+    pub fn args(&self) -> &[Node] {
+        match self {
+            Node::Macro { args, .. }
+            | Node::Environment { args, .. }
+            | Node::MathEnv { args, .. }
+            | Node::VerbatimEnvironment { args, .. } => args,
+            _ => &[],
+        }
+    }
+
+    /// Print the node back to LaTeX source.
+    ///
+    /// The output is equivalent to the parsed source, but whitespace is normalized.
+    pub fn to_latex(&self) -> String {
+        let mut out = String::new();
+        self.write_latex(&mut out);
+        out
+    }
+
+    fn write_latex(&self, out: &mut String) {
+        let write_all = |nodes: &[Node], out: &mut String| nodes.iter().for_each(|n| n.write_latex(out));
+        match self {
+            Node::Root { content, .. } => write_all(content, out),
+            Node::String { content, .. } => out.push_str(content),
+            Node::WhiteSpace { .. } => out.push(' '),
+            Node::Parbreak { .. } => out.push_str("\n\n"),
+            Node::Comment { content, sameline, leading_whitespace, .. } => {
+                if *sameline && *leading_whitespace {
+                    out.push(' ');
+                }
+                out.push('%');
+                out.push_str(content);
+                out.push('\n');
+            }
+            Node::Macro { content, args, escape_token, .. } => {
+                out.push_str(escape_token.as_deref().unwrap_or("\\"));
+                out.push_str(content);
+                write_all(args, out);
+            }
+            Node::Environment { env, args, content, .. } | Node::MathEnv { env, args, content, .. } => {
+                out.push_str(&format!("\\begin{{{env}}}"));
+                write_all(args, out);
+                write_all(content, out);
+                out.push_str(&format!("\\end{{{env}}}"));
+            }
+            Node::VerbatimEnvironment { env, args, content, .. } => {
+                out.push_str(&format!("\\begin{{{env}}}"));
+                write_all(args, out);
+                out.push_str(content);
+                out.push_str(&format!("\\end{{{env}}}"));
+            }
+            Node::DisplayMath { content, .. } => {
+                out.push_str("\\[");
+                write_all(content, out);
+                out.push_str("\\]");
+            }
+            Node::Group { content, .. } => {
+                out.push('{');
+                write_all(content, out);
+                out.push('}');
+            }
+            Node::InlineMath { content, .. } => {
+                out.push('$');
+                write_all(content, out);
+                out.push('$');
+            }
+            Node::Verb { env, escape, content, .. } => {
+                out.push('\\');
+                out.push_str(env);
+                out.push_str(escape);
+                out.push_str(content);
+                out.push_str(escape);
+            }
+            Node::Argument { open_mark, close_mark, content, .. } => {
+                out.push_str(open_mark);
+                write_all(content, out);
+                out.push_str(close_mark);
+            }
+            Node::Error => {}
+        }
+    }
+}
+
+/// Print `nodes` back to LaTeX source, see [`Node::to_latex`].
+pub fn to_latex<N: Tree>(nodes: &[N]) -> String {
+    let mut out = String::new();
+    nodes.iter().for_each(|node| node.write_latex(&mut out));
+    out
+}
+
+/// What reading a tree needs of its nodes, so that the analyses going through the
+/// structure of a document take the [owned](Node) and the [borrowed](borrowed::Node)
+/// trees alike.
+pub trait Tree: Sized {
+    /// Child nodes making up the content of this node, see [`Node::content`].
+    fn content(&self) -> &[Self];
+
+    /// Arguments attached to a macro or an environment, see [`Node::args`].
+    fn args(&self) -> &[Self];
+
+    /// Position of the node in the source, see [`Node::position`].
+    fn position(&self) -> Option<&PositionInfo>;
+
+    /// Name of a macro, without escape token.
+    fn macro_name(&self) -> Option<&str>;
+
+    /// Text of a string.
+    fn string(&self) -> Option<&str>;
+
+    /// Opening delimiter of an argument.
+    fn open_mark(&self) -> Option<&str>;
+
+    /// Whether the node is a group.
+    fn is_group(&self) -> bool;
+
+    /// Whether the node is whitespace within a paragraph.
+    fn is_whitespace(&self) -> bool;
+
+    /// Name of an environment whose content is text, rather than math or verbatim.
+    fn environment(&self) -> Option<&str>;
+
+    /// Name of a math environment, such as `equation`.
+    fn math_environment(&self) -> Option<&str>;
+
+    /// Whether the node is math within a paragraph.
+    fn is_inline_math(&self) -> bool;
+
+    /// Whether the node is displayed math, delimited or a math environment.
+    fn is_display_math(&self) -> bool;
+
+    /// Print the node back to LaTeX source, see [`Node::to_latex`].
+    fn write_latex(&self, out: &mut String);
+}
+
+impl Tree for Node {
+    fn content(&self) -> &[Node] {
+        Node::content(self)
+    }
+
+    fn args(&self) -> &[Node] {
+        Node::args(self)
+    }
+
+    fn position(&self) -> Option<&PositionInfo> {
+        Node::position(self)
+    }
+
+    fn macro_name(&self) -> Option<&str> {
+        match self {
+            Node::Macro { content, .. } => Some(content),
+            _ => None,
+        }
+    }
+
+    fn string(&self) -> Option<&str> {
+        match self {
+            Node::String { content, .. } => Some(content),
+            _ => None,
+        }
+    }
+
+    fn open_mark(&self) -> Option<&str> {
+        match self {
+            Node::Argument { open_mark, .. } => Some(open_mark),
+            _ => None,
+        }
+    }
+
+    fn is_group(&self) -> bool {
+        matches!(self, Node::Group { .. })
+    }
+
+    fn is_whitespace(&self) -> bool {
+        matches!(self, Node::WhiteSpace { .. })
+    }
+
+    fn environment(&self) -> Option<&str> {
+        match self {
+            Node::Environment { env, .. } => Some(env),
+            _ => None,
+        }
+    }
+
+    fn math_environment(&self) -> Option<&str> {
+        match self {
+            Node::MathEnv { env, .. } => Some(env),
+            _ => None,
+        }
+    }
+
+    fn is_inline_math(&self) -> bool {
+        matches!(self, Node::InlineMath { .. })
+    }
+
+    fn is_display_math(&self) -> bool {
+        matches!(self, Node::DisplayMath { .. } | Node::MathEnv { .. })
+    }
+
+    fn write_latex(&self, out: &mut String) {
+        Node::write_latex(self, out)
+    }
+}
+
 impl<'js> FromJs<'js> for Node {
     fn from_js(ctx: Ctx<'js>, value: Value<'js>) -> rquickjs::Result<Self> {
         let object: Object = Object::from_js(ctx, value.clone())?;
@@ -160,7 +416,7 @@ impl<'js> FromJs<'js> for Node {
             }
             "environment" => {
                 Self::Environment {
-                    env: get_undefined(&object, "env")?,
+                    env: get_env(&object)?,
                     args: get_undefined(&object, "args")?,
                     content: get_undefined(&object, "content")?,
                     position: get_undefined(&object, "position")?,
@@ -169,7 +425,7 @@ impl<'js> FromJs<'js> for Node {
             }
             "mathenv" => {
                 Self::MathEnv {
-                    env: get_undefined(&object, "env")?,
+                    env: get_env(&object)?,
                     args: get_undefined(&object, "args")?,
                     content: get_undefined(&object, "content")?,
                     position: get_undefined(&object, "position")?,
@@ -180,6 +436,7 @@ impl<'js> FromJs<'js> for Node {
                 Self::VerbatimEnvironment {
                     env: get_undefined(&object, "env")?,
                     args: get_undefined(&object, "args")?,
+                    content: get_undefined(&object, "content")?,
                     position: get_undefined(&object, "position")?,
                     render_info: get_undefined(&object, "_renderInfo")?,
                 }
@@ -0,0 +1,627 @@
+//! A syntax tree borrowing its text from the source.
+//!
+//! The text of the nodes of [`parse_borrowed`](crate::parse_borrowed) is sliced from the
+//! parsed input wherever it appears there verbatim, so holding on to the tree of a large
+//! document costs little more than its structure. The [native parser](crate::native)
+//! builds the tree directly, while the tree of the JavaScript parser is converted. [`Node::into_owned`] gives back an
+//! [owned tree](super::Node).
+//!
+//! ```
+//! use std::borrow::Cow;
+//! use unlatex::ast::borrowed::Node;
+//!
+//! let input = r"\section{Intro} Hello";
+//! let ast = unlatex::parse_borrowed(input).unwrap();
+//! let Node::String { content: Cow::Borrowed(hello), .. } = &ast.content()[2] else { panic!() };
+//! assert_eq!(*hello, "Hello");
+//! assert_eq!(ast.into_owned().to_latex(), unlatex::parse(input).unwrap().to_latex());
+//! ```
+
+use std::borrow::Cow;
+
+use crate::ast::{self, Tree};
+use crate::info::{PositionInfo, RenderInfo};
+use crate::span::OffsetMap;
+
+/// A node of the syntax tree, see [`ast::Node`] for the owned equivalent.
+#[derive(Debug, Clone)]
+pub enum Node<'src> {
+    Root {
+        content: Vec<Node<'src>>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    String {
+        content: Cow<'src, str>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    WhiteSpace {
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    Parbreak {
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    Comment {
+        content: Cow<'src, str>,
+        sameline: bool,
+        suffix_parbreak: bool,
+        leading_whitespace: bool,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    Macro {
+        content: Cow<'src, str>,
+        args: Vec<Node<'src>>,
+        escape_token: Option<Cow<'src, str>>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    Environment {
+        env: Cow<'src, str>,
+        args: Vec<Node<'src>>,
+        content: Vec<Node<'src>>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    MathEnv {
+        env: Cow<'src, str>,
+        args: Vec<Node<'src>>,
+        content: Vec<Node<'src>>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    VerbatimEnvironment {
+        env: Cow<'src, str>,
+        args: Vec<Node<'src>>,
+        content: Cow<'src, str>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    DisplayMath {
+        content: Vec<Node<'src>>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    Group {
+        content: Vec<Node<'src>>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    InlineMath {
+        content: Vec<Node<'src>>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    Verb {
+        env: Cow<'src, str>,
+        escape: Cow<'src, str>,
+        content: Cow<'src, str>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    Argument {
+        open_mark: Cow<'src, str>,
+        close_mark: Cow<'src, str>,
+        content: Vec<Node<'src>>,
+        position: PositionInfo,
+        render_info: Option<RenderInfo>,
+    },
+    Error,
+}
+
+/// Slices the text of nodes from the source.
+struct Borrower<'src> {
+    source: &'src str,
+    offsets: OffsetMap,
+}
+
+impl<'src> Borrower<'src> {
+    /// The source of `position`, or `within` if the node has no position.
+    fn span(&self, position: &PositionInfo, within: &'src str) -> &'src str {
+        if position.start.line == 0 {
+            return within;
+        }
+        let start = self.offsets.to_byte(position.start.offset);
+        let end = self.offsets.to_byte(position.end.offset).max(start);
+        &self.source[start..end]
+    }
+
+    /// `text` sliced from `within`, or owned if it does not appear there.
+    fn text(&self, text: String, within: &'src str) -> Cow<'src, str> {
+        match within.find(text.as_str()) {
+            Some(i) => Cow::Borrowed(&within[i..i + text.len()]),
+            None => Cow::Owned(text),
+        }
+    }
+
+    fn nodes(&self, nodes: Vec<ast::Node>, within: &'src str) -> Vec<Node<'src>> {
+        nodes.into_iter().map(|node| self.node(node, within)).collect()
+    }
+
+    fn node(&self, node: ast::Node, within: &'src str) -> Node<'src> {
+        let within = node.position().map_or(within, |position| self.span(position, within));
+        match node {
+            ast::Node::Root { content, position, render_info } => {
+                Node::Root { content: self.nodes(content, within), position, render_info }
+            }
+            ast::Node::String { content, position, render_info } => {
+                Node::String { content: self.text(content, within), position, render_info }
+            }
+            ast::Node::WhiteSpace { position, render_info } => Node::WhiteSpace { position, render_info },
+            ast::Node::Parbreak { position, render_info } => Node::Parbreak { position, render_info },
+            ast::Node::Comment { content, sameline, suffix_parbreak, leading_whitespace, position, render_info } => {
+                Node::Comment {
+                    content: self.text(content, within),
+                    sameline,
+                    suffix_parbreak,
+                    leading_whitespace,
+                    position,
+                    render_info,
+                }
+            }
+            ast::Node::Macro { content, args, escape_token, position, render_info } => Node::Macro {
+                content: self.text(content, within),
+                args: self.nodes(args, within),
+                escape_token: escape_token.map(|token| self.text(token, within)),
+                position,
+                render_info,
+            },
+            ast::Node::Environment { env, args, content, position, render_info } => Node::Environment {
+                env: self.text(env, within),
+                args: self.nodes(args, within),
+                content: self.nodes(content, within),
+                position,
+                render_info,
+            },
+            ast::Node::MathEnv { env, args, content, position, render_info } => Node::MathEnv {
+                env: self.text(env, within),
+                args: self.nodes(args, within),
+                content: self.nodes(content, within),
+                position,
+                render_info,
+            },
+            ast::Node::VerbatimEnvironment { env, args, content, position, render_info } => Node::VerbatimEnvironment {
+                env: self.text(env, within),
+                args: self.nodes(args, within),
+                content: self.text(content, within),
+                position,
+                render_info,
+            },
+            ast::Node::DisplayMath { content, position, render_info } => {
+                Node::DisplayMath { content: self.nodes(content, within), position, render_info }
+            }
+            ast::Node::Group { content, position, render_info } => {
+                Node::Group { content: self.nodes(content, within), position, render_info }
+            }
+            ast::Node::InlineMath { content, position, render_info } => {
+                Node::InlineMath { content: self.nodes(content, within), position, render_info }
+            }
+            ast::Node::Verb { env, escape, content, position, render_info } => Node::Verb {
+                env: self.text(env, within),
+                escape: self.text(escape, within),
+                content: self.text(content, within),
+                position,
+                render_info,
+            },
+            ast::Node::Argument { open_mark, close_mark, content, position, render_info } => Node::Argument {
+                open_mark: self.text(open_mark, within),
+                close_mark: self.text(close_mark, within),
+                content: self.nodes(content, within),
+                position,
+                render_info,
+            },
+            ast::Node::Error => Node::Error,
+        }
+    }
+}
+
+fn owned(text: Cow<'_, str>) -> String {
+    text.into_owned()
+}
+
+/// Borrow the text of an owned tree, e.g. to read it with the analyses taking borrowed
+/// trees without copying its text.
+impl<'src> From<&'src ast::Node> for Node<'src> {
+    fn from(node: &'src ast::Node) -> Node<'src> {
+        let all = |nodes: &'src [ast::Node]| nodes.iter().map(Node::from).collect();
+        let text = |text: &'src String| Cow::Borrowed(text.as_str());
+        match node {
+            ast::Node::Root { content, position, render_info } => {
+                Node::Root { content: all(content), position: position.clone(), render_info: render_info.clone() }
+            }
+            ast::Node::String { content, position, render_info } => {
+                Node::String { content: text(content), position: position.clone(), render_info: render_info.clone() }
+            }
+            ast::Node::WhiteSpace { position, render_info } => {
+                Node::WhiteSpace { position: position.clone(), render_info: render_info.clone() }
+            }
+            ast::Node::Parbreak { position, render_info } => {
+                Node::Parbreak { position: position.clone(), render_info: render_info.clone() }
+            }
+            ast::Node::Comment { content, sameline, suffix_parbreak, leading_whitespace, position, render_info } => {
+                Node::Comment {
+                    content: text(content),
+                    sameline: *sameline,
+                    suffix_parbreak: *suffix_parbreak,
+                    leading_whitespace: *leading_whitespace,
+                    position: position.clone(),
+                    render_info: render_info.clone(),
+                }
+            }
+            ast::Node::Macro { content, args, escape_token, position, render_info } => Node::Macro {
+                content: text(content),
+                args: all(args),
+                escape_token: escape_token.as_ref().map(text),
+                position: position.clone(),
+                render_info: render_info.clone(),
+            },
+            ast::Node::Environment { env, args, content, position, render_info } => Node::Environment {
+                env: text(env),
+                args: all(args),
+                content: all(content),
+                position: position.clone(),
+                render_info: render_info.clone(),
+            },
+            ast::Node::MathEnv { env, args, content, position, render_info } => Node::MathEnv {
+                env: text(env),
+                args: all(args),
+                content: all(content),
+                position: position.clone(),
+                render_info: render_info.clone(),
+            },
+            ast::Node::VerbatimEnvironment { env, args, content, position, render_info } => Node::VerbatimEnvironment {
+                env: text(env),
+                args: all(args),
+                content: text(content),
+                position: position.clone(),
+                render_info: render_info.clone(),
+            },
+            ast::Node::DisplayMath { content, position, render_info } => {
+                Node::DisplayMath { content: all(content), position: position.clone(), render_info: render_info.clone() }
+            }
+            ast::Node::Group { content, position, render_info } => {
+                Node::Group { content: all(content), position: position.clone(), render_info: render_info.clone() }
+            }
+            ast::Node::InlineMath { content, position, render_info } => {
+                Node::InlineMath { content: all(content), position: position.clone(), render_info: render_info.clone() }
+            }
+            ast::Node::Verb { env, escape, content, position, render_info } => Node::Verb {
+                env: text(env),
+                escape: text(escape),
+                content: text(content),
+                position: position.clone(),
+                render_info: render_info.clone(),
+            },
+            ast::Node::Argument { open_mark, close_mark, content, position, render_info } => Node::Argument {
+                open_mark: text(open_mark),
+                close_mark: text(close_mark),
+                content: all(content),
+                position: position.clone(),
+                render_info: render_info.clone(),
+            },
+            ast::Node::Error => Node::Error,
+        }
+    }
+}
+
+impl<'src> Node<'src> {
+    /// Convert an owned tree parsed from `source`, slicing its text from `source` where it
+    /// appears verbatim in the span of its node.
+    ///
+    /// Text that does not, such as math parsed again from its printed content, stays owned.
+    pub fn from_owned(node: ast::Node, source: &'src str) -> Node<'src> {
+        Borrower { source, offsets: OffsetMap::new(source) }.node(node, source)
+    }
+
+    /// Convert to an owned tree, copying the borrowed text.
+    pub fn into_owned(self) -> ast::Node {
+        let all = |nodes: Vec<Node<'_>>| nodes.into_iter().map(Node::into_owned).collect();
+        match self {
+            Node::Root { content, position, render_info } => ast::Node::Root { content: all(content), position, render_info },
+            Node::String { content, position, render_info } => {
+                ast::Node::String { content: owned(content), position, render_info }
+            }
+            Node::WhiteSpace { position, render_info } => ast::Node::WhiteSpace { position, render_info },
+            Node::Parbreak { position, render_info } => ast::Node::Parbreak { position, render_info },
+            Node::Comment { content, sameline, suffix_parbreak, leading_whitespace, position, render_info } => {
+                ast::Node::Comment {
+                    content: owned(content),
+                    sameline,
+                    suffix_parbreak,
+                    leading_whitespace,
+                    position,
+                    render_info,
+                }
+            }
+            Node::Macro { content, args, escape_token, position, render_info } => ast::Node::Macro {
+                content: owned(content),
+                args: all(args),
+                escape_token: escape_token.map(owned),
+                position,
+                render_info,
+            },
+            Node::Environment { env, args, content, position, render_info } => ast::Node::Environment {
+                env: owned(env),
+                args: all(args),
+                content: all(content),
+                position,
+                render_info,
+            },
+            Node::MathEnv { env, args, content, position, render_info } => ast::Node::MathEnv {
+                env: owned(env),
+                args: all(args),
+                content: all(content),
+                position,
+                render_info,
+            },
+            Node::VerbatimEnvironment { env, args, content, position, render_info } => ast::Node::VerbatimEnvironment {
+                env: owned(env),
+                args: all(args),
+                content: owned(content),
+                position,
+                render_info,
+            },
+            Node::DisplayMath { content, position, render_info } => {
+                ast::Node::DisplayMath { content: all(content), position, render_info }
+            }
+            Node::Group { content, position, render_info } => ast::Node::Group { content: all(content), position, render_info },
+            Node::InlineMath { content, position, render_info } => {
+                ast::Node::InlineMath { content: all(content), position, render_info }
+            }
+            Node::Verb { env, escape, content, position, render_info } => ast::Node::Verb {
+                env: owned(env),
+                escape: owned(escape),
+                content: owned(content),
+                position,
+                render_info,
+            },
+            Node::Argument { open_mark, close_mark, content, position, render_info } => ast::Node::Argument {
+                open_mark: owned(open_mark),
+                close_mark: owned(close_mark),
+                content: all(content),
+                position,
+                render_info,
+            },
+            Node::Error => ast::Node::Error,
+        }
+    }
+
+    /// Detach the tree from its source, owning the text it borrows.
+    pub fn into_static(self) -> Node<'static> {
+        let all = |nodes: Vec<Node<'_>>| nodes.into_iter().map(Node::into_static).collect();
+        let own = |text: Cow<'_, str>| Cow::Owned(text.into_owned());
+        match self {
+            Node::Root { content, position, render_info } => Node::Root { content: all(content), position, render_info },
+            Node::String { content, position, render_info } => Node::String { content: own(content), position, render_info },
+            Node::WhiteSpace { position, render_info } => Node::WhiteSpace { position, render_info },
+            Node::Parbreak { position, render_info } => Node::Parbreak { position, render_info },
+            Node::Comment { content, sameline, suffix_parbreak, leading_whitespace, position, render_info } => Node::Comment {
+                content: own(content),
+                sameline,
+                suffix_parbreak,
+                leading_whitespace,
+                position,
+                render_info,
+            },
+            Node::Macro { content, args, escape_token, position, render_info } => Node::Macro {
+                content: own(content),
+                args: all(args),
+                escape_token: escape_token.map(own),
+                position,
+                render_info,
+            },
+            Node::Environment { env, args, content, position, render_info } => {
+                Node::Environment { env: own(env), args: all(args), content: all(content), position, render_info }
+            }
+            Node::MathEnv { env, args, content, position, render_info } => {
+                Node::MathEnv { env: own(env), args: all(args), content: all(content), position, render_info }
+            }
+            Node::VerbatimEnvironment { env, args, content, position, render_info } => {
+                Node::VerbatimEnvironment { env: own(env), args: all(args), content: own(content), position, render_info }
+            }
+            Node::DisplayMath { content, position, render_info } => Node::DisplayMath { content: all(content), position, render_info },
+            Node::Group { content, position, render_info } => Node::Group { content: all(content), position, render_info },
+            Node::InlineMath { content, position, render_info } => Node::InlineMath { content: all(content), position, render_info },
+            Node::Verb { env, escape, content, position, render_info } => {
+                Node::Verb { env: own(env), escape: own(escape), content: own(content), position, render_info }
+            }
+            Node::Argument { open_mark, close_mark, content, position, render_info } => Node::Argument {
+                open_mark: own(open_mark),
+                close_mark: own(close_mark),
+                content: all(content),
+                position,
+                render_info,
+            },
+            Node::Error => Node::Error,
+        }
+    }
+
+    /// Position of the node in the source, see [`ast::Node::position`].
+    pub fn position(&self) -> Option<&PositionInfo> {
+        let position = match self {
+            Node::Root { position, .. }
+            | Node::String { position, .. }
+            | Node::WhiteSpace { position, .. }
+            | Node::Parbreak { position, .. }
+            | Node::Comment { position, .. }
+            | Node::Macro { position, .. }
+            | Node::Environment { position, .. }
+            | Node::MathEnv { position, .. }
+            | Node::VerbatimEnvironment { position, .. }
+            | Node::DisplayMath { position, .. }
+            | Node::Group { position, .. }
+            | Node::InlineMath { position, .. }
+            | Node::Verb { position, .. }
+            | Node::Argument { position, .. } => position,
+            Node::Error => return None,
+        };
+        (position.start.line > 0).then_some(position)
+    }
+
+    /// Child nodes making up the content of this node.
+    pub fn content(&self) -> &[Node<'src>] {
+        match self {
+            Node::Root { content, .. }
+            | Node::Environment { content, .. }
+            | Node::MathEnv { content, .. }
+            | Node::DisplayMath { content, .. }
+            | Node::Group { content, .. }
+            | Node::InlineMath { content, .. }
+            | Node::Argument { content, .. } => content,
+            _ => &[],
+        }
+    }
+
+    /// Arguments attached to a macro or an environment.
+    pub fn args(&self) -> &[Node<'src>] {
+        match self {
+            Node::Macro { args, .. }
+            | Node::Environment { args, .. }
+            | Node::MathEnv { args, .. }
+            | Node::VerbatimEnvironment { args, .. } => args,
+            _ => &[],
+        }
+    }
+
+    /// Print the node back to LaTeX source, see [`ast::Node::to_latex`].
+    pub fn to_latex(&self) -> String {
+        let mut out = String::new();
+        self.write_latex(&mut out);
+        out
+    }
+
+    fn write_latex(&self, out: &mut String) {
+        let write_all = |nodes: &[Node], out: &mut String| nodes.iter().for_each(|n| n.write_latex(out));
+        match self {
+            Node::Root { content, .. } => write_all(content, out),
+            Node::String { content, .. } => out.push_str(content),
+            Node::WhiteSpace { .. } => out.push(' '),
+            Node::Parbreak { .. } => out.push_str("\n\n"),
+            Node::Comment { content, sameline, leading_whitespace, .. } => {
+                if *sameline && *leading_whitespace {
+                    out.push(' ');
+                }
+                out.push('%');
+                out.push_str(content);
+                out.push('\n');
+            }
+            Node::Macro { content, args, escape_token, .. } => {
+                out.push_str(escape_token.as_deref().unwrap_or("\\"));
+                out.push_str(content);
+                write_all(args, out);
+            }
+            Node::Environment { env, args, content, .. } | Node::MathEnv { env, args, content, .. } => {
+                out.push_str(&format!("\\begin{{{env}}}"));
+                write_all(args, out);
+                write_all(content, out);
+                out.push_str(&format!("\\end{{{env}}}"));
+            }
+            Node::VerbatimEnvironment { env, args, content, .. } => {
+                out.push_str(&format!("\\begin{{{env}}}"));
+                write_all(args, out);
+                out.push_str(content);
+                out.push_str(&format!("\\end{{{env}}}"));
+            }
+            Node::DisplayMath { content, .. } => {
+                out.push_str("\\[");
+                write_all(content, out);
+                out.push_str("\\]");
+            }
+            Node::Group { content, .. } => {
+                out.push('{');
+                write_all(content, out);
+                out.push('}');
+            }
+            Node::InlineMath { content, .. } => {
+                out.push('$');
+                write_all(content, out);
+                out.push('$');
+            }
+            Node::Verb { env, escape, content, .. } => {
+                out.push('\\');
+                out.push_str(env);
+                out.push_str(escape);
+                out.push_str(content);
+                out.push_str(escape);
+            }
+            Node::Argument { open_mark, close_mark, content, .. } => {
+                out.push_str(open_mark);
+                write_all(content, out);
+                out.push_str(close_mark);
+            }
+            Node::Error => {}
+        }
+    }
+}
+
+impl<'src> Tree for Node<'src> {
+    fn content(&self) -> &[Node<'src>] {
+        Node::content(self)
+    }
+
+    fn args(&self) -> &[Node<'src>] {
+        Node::args(self)
+    }
+
+    fn position(&self) -> Option<&PositionInfo> {
+        Node::position(self)
+    }
+
+    fn macro_name(&self) -> Option<&str> {
+        match self {
+            Node::Macro { content, .. } => Some(content),
+            _ => None,
+        }
+    }
+
+    fn string(&self) -> Option<&str> {
+        match self {
+            Node::String { content, .. } => Some(content),
+            _ => None,
+        }
+    }
+
+    fn open_mark(&self) -> Option<&str> {
+        match self {
+            Node::Argument { open_mark, .. } => Some(open_mark),
+            _ => None,
+        }
+    }
+
+    fn is_group(&self) -> bool {
+        matches!(self, Node::Group { .. })
+    }
+
+    fn is_whitespace(&self) -> bool {
+        matches!(self, Node::WhiteSpace { .. })
+    }
+
+    fn environment(&self) -> Option<&str> {
+        match self {
+            Node::Environment { env, .. } => Some(env),
+            _ => None,
+        }
+    }
+
+    fn math_environment(&self) -> Option<&str> {
+        match self {
+            Node::MathEnv { env, .. } => Some(env),
+            _ => None,
+        }
+    }
+
+    fn is_inline_math(&self) -> bool {
+        matches!(self, Node::InlineMath { .. })
+    }
+
+    fn is_display_math(&self) -> bool {
+        matches!(self, Node::DisplayMath { .. } | Node::MathEnv { .. })
+    }
+
+    fn write_latex(&self, out: &mut String) {
+        Node::write_latex(self, out)
+    }
+}
@@ -0,0 +1,417 @@
+//! BibTeX and BibLaTeX databases.
+//!
+//! ```
+//! use unlatex::bib::Bibliography;
+//!
+//! let bib = Bibliography::parse(r#"
+//! @string{acm = "ACM"}
+//! @Article{knuth84, author = {Donald E. Knuth}, publisher = acm # " Press", year = 1984}
+//! "#);
+//! let entry = bib.get("knuth84").unwrap();
+//! assert_eq!(entry.entry_type, "Article");
+//! assert_eq!(bib.resolve(&entry.field("publisher").unwrap().value), "ACM Press");
+//! ```
+//!
+//! Parsing is lenient: malformed items are skipped and reported in [`Bibliography::errors`].
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+mod format;
+
+pub use format::{format, BibOptions, Delimiter, VERBATIM_FIELDS};
+
+/// A piece of a field value, pieces are concatenated with `#`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValuePart {
+    /// Text delimited by braces, without the outer braces.
+    Braced(String),
+    /// Text delimited by double quotes, without the quotes.
+    Quoted(String),
+    /// A bare number.
+    Number(String),
+    /// A reference to a `@string` macro.
+    Macro(String),
+}
+
+/// The value of a field or of a `@string` definition.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Value {
+    /// The pieces of the value, in order.
+    pub parts: Vec<ValuePart>,
+}
+
+/// A `name = value` field of an entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// Name of the field, as written.
+    pub name: String,
+    /// Value of the field.
+    pub value: Value,
+    /// Byte range of the field in the source.
+    pub span: Range<usize>,
+}
+
+/// A bibliography entry such as `@article{key, ...}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Type of the entry, as written.
+    pub entry_type: String,
+    /// Citation key.
+    pub key: String,
+    /// Fields, in source order.
+    pub fields: Vec<Field>,
+    /// Byte range of the entry in the source, from `@` to the closing delimiter.
+    pub span: Range<usize>,
+    /// Byte range of the citation key in the source.
+    pub key_span: Range<usize>,
+}
+
+impl Entry {
+    /// The field called `name`, compared case-insensitively.
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.name.eq_ignore_ascii_case(name))
+    }
+}
+
+/// A top-level item of a database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    /// A bibliography entry.
+    Entry(Entry),
+    /// A `@string{name = value}` macro definition.
+    String {
+        /// Name of the macro.
+        name: String,
+        /// Value of the macro.
+        value: Value,
+        /// Byte range in the source.
+        span: Range<usize>,
+    },
+    /// A `@preamble{...}`.
+    Preamble {
+        /// Content of the preamble.
+        value: Value,
+        /// Byte range in the source.
+        span: Range<usize>,
+    },
+    /// A `@comment{...}` or text outside of any item.
+    Comment {
+        /// Text of the comment, as written.
+        text: String,
+        /// Byte range in the source.
+        span: Range<usize>,
+    },
+}
+
+/// An item which could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// What went wrong.
+    pub message: String,
+    /// Byte range of the skipped source.
+    pub span: Range<usize>,
+}
+
+/// A parsed BibTeX database.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bibliography {
+    /// The items of the database, in source order.
+    pub items: Vec<Item>,
+    /// Items which could not be parsed.
+    pub errors: Vec<ParseError>,
+}
+
+/// Predefined month macros.
+const MONTHS: [(&str, &str); 12] = [
+    ("jan", "January"), ("feb", "February"), ("mar", "March"), ("apr", "April"),
+    ("may", "May"), ("jun", "June"), ("jul", "July"), ("aug", "August"),
+    ("sep", "September"), ("oct", "October"), ("nov", "November"), ("dec", "December"),
+];
+
+impl Bibliography {
+    /// Parse a database.
+    pub fn parse(src: &str) -> Bibliography {
+        let mut parser = Parser { src, pos: 0 };
+        let mut bib = Bibliography::default();
+        while parser.pos < src.len() {
+            let start = parser.pos;
+            let at = src[start..].find('@').map_or(src.len(), |i| start + i);
+            if !src[start..at].trim().is_empty() {
+                bib.items.push(Item::Comment { text: src[start..at].to_string(), span: start..at });
+            }
+            if at == src.len() {
+                break;
+            }
+            parser.pos = at;
+            match parser.item() {
+                Ok(item) => bib.items.push(item),
+                Err(message) => {
+                    // resume at the next item starting a line
+                    let next = src[at + 1..].find("\n@").map_or(src.len(), |i| at + 2 + i);
+                    bib.errors.push(ParseError { message, span: at..next });
+                    parser.pos = next;
+                }
+            }
+        }
+        bib
+    }
+
+    /// The bibliography entries.
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.items.iter().filter_map(|item| match item {
+            Item::Entry(entry) => Some(entry),
+            _ => None,
+        })
+    }
+
+    /// The entry with citation key `key`.
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries().find(|entry| entry.key == key)
+    }
+
+    /// Entries whose key was already used by an earlier entry, with that earlier entry.
+    ///
+    /// Keys are compared case-insensitively, as BibTeX does.
+    pub fn duplicates(&self) -> Vec<(&Entry, &Entry)> {
+        let mut seen: HashMap<String, &Entry> = HashMap::new();
+        let mut duplicates = vec![];
+        for entry in self.entries() {
+            match seen.get(&entry.key.to_lowercase()) {
+                Some(first) => duplicates.push((*first, entry)),
+                None => {
+                    seen.insert(entry.key.to_lowercase(), entry);
+                }
+            }
+        }
+        duplicates
+    }
+
+    /// The `@string` macros, by lowercase name.
+    pub fn strings(&self) -> HashMap<String, &Value> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                Item::String { name, value, .. } => Some((name.to_lowercase(), value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Text of `value`, expanding `@string` macros and month names.
+    pub fn resolve(&self, value: &Value) -> String {
+        let strings = self.strings();
+        let mut out = String::new();
+        resolve_into(value, &strings, &mut out, 0);
+        out
+    }
+}
+
+fn resolve_into(value: &Value, strings: &HashMap<String, &Value>, out: &mut String, depth: usize) {
+    for part in &value.parts {
+        match part {
+            ValuePart::Braced(text) | ValuePart::Quoted(text) | ValuePart::Number(text) => out.push_str(text),
+            ValuePart::Macro(name) => {
+                let lower = name.to_lowercase();
+                match strings.get(&lower) {
+                    // guard against macros defined in terms of themselves
+                    Some(value) if depth < 16 => resolve_into(value, strings, out, depth + 1),
+                    _ => match MONTHS.iter().find(|(short, _)| *short == lower) {
+                        Some((_, month)) => out.push_str(month),
+                        None => out.push_str(name),
+                    },
+                }
+            }
+        }
+    }
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+type PResult<T> = std::result::Result<T, String>;
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn expect(&mut self, c: char) -> PResult<()> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(found) if found == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            Some(found) => Err(format!("expected `{c}`, found `{found}`")),
+            None => Err(format!("expected `{c}`, found end of input")),
+        }
+    }
+
+    /// Read a name made of characters allowed in entry types, keys and field names.
+    fn name(&mut self, what: &str) -> PResult<&'a str> {
+        self.skip_whitespace();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "{}()=,#\"@".contains(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(format!("expected {what}"));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// Read text up to the brace closing the one just consumed, returning it without the brace.
+    fn balanced(&mut self) -> PResult<&'a str> {
+        let start = self.pos;
+        let mut depth = 0usize;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' if depth == 0 => {
+                    self.pos = start + i + 1;
+                    return Ok(&self.src[start..start + i]);
+                }
+                '}' => depth -= 1,
+                _ => {}
+            }
+        }
+        Err("unbalanced braces".to_string())
+    }
+
+    fn quoted(&mut self) -> PResult<&'a str> {
+        let start = self.pos;
+        let mut depth = 0usize;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '{' => depth += 1,
+                '}' => depth = depth.saturating_sub(1),
+                '"' if depth == 0 => {
+                    self.pos = start + i + 1;
+                    return Ok(&self.src[start..start + i]);
+                }
+                _ => {}
+            }
+        }
+        Err("unterminated quoted value".to_string())
+    }
+
+    fn value(&mut self) -> PResult<Value> {
+        let mut value = Value::default();
+        loop {
+            self.skip_whitespace();
+            let part = match self.peek() {
+                Some('{') => {
+                    self.pos += 1;
+                    ValuePart::Braced(self.balanced()?.to_string())
+                }
+                Some('"') => {
+                    self.pos += 1;
+                    ValuePart::Quoted(self.quoted()?.to_string())
+                }
+                Some(c) if c.is_ascii_digit() => {
+                    let rest = self.rest();
+                    let len = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+                    self.pos += len;
+                    ValuePart::Number(rest[..len].to_string())
+                }
+                _ => ValuePart::Macro(self.name("a value")?.to_string()),
+            };
+            value.parts.push(part);
+            self.skip_whitespace();
+            if self.peek() == Some('#') {
+                self.pos += 1;
+            } else {
+                return Ok(value);
+            }
+        }
+    }
+
+    /// Parse the item starting at the `@` under the cursor.
+    fn item(&mut self) -> PResult<Item> {
+        let start = self.pos;
+        self.pos += 1;
+        let entry_type = self.name("an entry type")?;
+        self.skip_whitespace();
+        let close = match self.peek() {
+            Some('{') => '}',
+            Some('(') => ')',
+            _ => return Err(format!("expected `{{` or `(` after `@{entry_type}`")),
+        };
+        self.pos += 1;
+
+        match entry_type.to_lowercase().as_str() {
+            "comment" => {
+                let text = if close == '}' { self.balanced()? } else { self.until(')')? };
+                Ok(Item::Comment { text: text.to_string(), span: start..self.pos })
+            }
+            "preamble" => {
+                let value = self.value()?;
+                self.expect(close)?;
+                Ok(Item::Preamble { value, span: start..self.pos })
+            }
+            "string" => {
+                let name = self.name("a string name")?.to_string();
+                self.expect('=')?;
+                let value = self.value()?;
+                self.expect(close)?;
+                Ok(Item::String { name, value, span: start..self.pos })
+            }
+            _ => {
+                self.skip_whitespace();
+                let key_start = self.pos;
+                let rest = self.rest();
+                let len = rest.find(|c: char| c.is_whitespace() || c == ',' || c == close).unwrap_or(rest.len());
+                self.pos += len;
+                let key = rest[..len].to_string();
+                let key_span = key_start..self.pos;
+
+                let mut fields = vec![];
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(c) if c == close => {
+                            self.pos += 1;
+                            break;
+                        }
+                        Some(',') => {
+                            self.pos += 1;
+                            continue;
+                        }
+                        None => return Err(format!("entry `{key}` is not closed")),
+                        _ => {}
+                    }
+                    let field_start = self.pos;
+                    let name = self.name("a field name")?.to_string();
+                    self.expect('=')?;
+                    let value = self.value()?;
+                    fields.push(Field { name, value, span: field_start..self.pos });
+                    self.skip_whitespace();
+                    if !matches!(self.peek(), Some(',')) && self.peek() != Some(close) {
+                        return Err(format!("expected `,` or `{close}` after field in entry `{key}`"));
+                    }
+                }
+                Ok(Item::Entry(Entry { entry_type: entry_type.to_string(), key, fields, span: start..self.pos, key_span }))
+            }
+        }
+    }
+
+    fn until(&mut self, c: char) -> PResult<&'a str> {
+        let rest = self.rest();
+        let i = rest.find(c).ok_or_else(|| format!("expected `{c}`"))?;
+        self.pos += i + 1;
+        Ok(&rest[..i])
+    }
+}
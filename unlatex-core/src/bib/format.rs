@@ -0,0 +1,279 @@
+//! Printing BibTeX databases in a canonical layout.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use super::{Bibliography, Entry, Item, Value, ValuePart};
+use crate::error::{Error, Result};
+use crate::options::FormatOptions;
+use crate::span::LineIndex;
+
+/// Fields whose value is taken verbatim by BibLaTeX: their whitespace is kept and they
+/// are never wrapped.
+pub const VERBATIM_FIELDS: &[&str] = &["url", "doi", "eprint", "file", "pdf", "verba", "verbb", "verbc"];
+
+/// Delimiters of textual field values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Delimiter {
+    /// `{...}`
+    #[default]
+    Braces,
+    /// `"..."`, values containing a top-level `"` keep their braces.
+    Quotes,
+}
+
+/// Options specific to BibTeX databases, the layout options are taken from [`FormatOptions`].
+///
+/// In a configuration file, they are set in a `[bib]` table:
+///
+/// ```toml
+/// [bib]
+/// field-order = ["author", "title", "year"]
+/// sort-entries = true
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct BibOptions {
+    /// Fields printed first, in this order. The other fields keep their relative order.
+    pub field_order: Vec<String>,
+    /// Sort the entries by citation key.
+    pub sort_entries: bool,
+    /// Delimiters of textual values.
+    pub delimiter: Delimiter,
+    /// Put a comma after the last field of each entry.
+    pub trailing_comma: bool,
+}
+
+impl Default for BibOptions {
+    fn default() -> Self {
+        BibOptions {
+            field_order: vec![],
+            sort_entries: false,
+            delimiter: Delimiter::Braces,
+            trailing_comma: true,
+        }
+    }
+}
+
+/// Format a BibTeX database.
+///
+/// Entry types and field names are lowercased, fields are indented one level and their
+/// `=` aligned, and long values are wrapped at [`FormatOptions::print_width`].
+///
+/// ```
+/// use unlatex::bib::{format, BibOptions};
+///
+/// let src = r#"@Book{knuth84,Title="The {\TeX}book",YEAR=1984}"#;
+/// let formatted = format(src, &Default::default(), &BibOptions::default()).unwrap();
+/// assert_eq!(formatted, "@book{knuth84,\n  title = {The {\\TeX}book},\n  year  = 1984,\n}\n");
+/// ```
+///
+/// Fails with [`Error::Bib`] if an item cannot be parsed, rather than dropping it.
+pub fn format(input: &str, opts: &FormatOptions, bib_opts: &BibOptions) -> Result<String> {
+    let bib = Bibliography::parse(input);
+    if let Some(error) = bib.errors.first() {
+        let (line, _) = LineIndex::new(input).line_col(error.span.start);
+        return Err(Error::Bib {
+            line: line + 1,
+            message: error.message.clone(),
+        });
+    }
+
+    let printer = Printer { src: input, opts, bib_opts };
+    let items: Vec<&Item> = if bib_opts.sort_entries { sorted(&bib.items) } else { bib.items.iter().collect() };
+    let mut out = String::new();
+    for item in items {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        printer.item(item, &mut out);
+    }
+    Ok(out)
+}
+
+/// Order `items` by entry key. `@string` and `@preamble` items, and comments before the
+/// first entry, stay at the top; other comments move with the entry following them.
+fn sorted(items: &[Item]) -> Vec<&Item> {
+    let mut header = vec![];
+    let mut groups: Vec<(&str, Vec<&Item>)> = vec![];
+    let mut pending = vec![];
+    for item in items {
+        match item {
+            Item::Entry(entry) => {
+                pending.push(item);
+                groups.push((&entry.key, std::mem::take(&mut pending)));
+            }
+            Item::Comment { .. } if !groups.is_empty() => pending.push(item),
+            _ => header.push(item),
+        }
+    }
+    groups.sort_by_cached_key(|(key, _)| key.to_lowercase());
+    header.extend(groups.into_iter().flat_map(|(_, items)| items));
+    header.extend(pending);
+    header
+}
+
+struct Printer<'a> {
+    src: &'a str,
+    opts: &'a FormatOptions,
+    bib_opts: &'a BibOptions,
+}
+
+impl Printer<'_> {
+    fn indent(&self) -> String {
+        if self.opts.use_tabs {
+            "\t".to_string()
+        } else {
+            " ".repeat(self.opts.tab_width.max(0) as usize)
+        }
+    }
+
+    fn width(&self, line: &str) -> usize {
+        line.chars().map(|c| if c == '\t' { self.opts.tab_width.max(0) as usize } else { 1 }).sum()
+    }
+
+    fn item(&self, item: &Item, out: &mut String) {
+        match item {
+            Item::Entry(entry) => self.entry(entry, out),
+            Item::String { name, value, .. } => {
+                out.push_str(&format!("@string{{{name} = {}}}\n", self.value(value, true)));
+            }
+            Item::Preamble { value, .. } => {
+                out.push_str(&format!("@preamble{{{}}}\n", self.value(value, false)));
+            }
+            Item::Comment { span, .. } => {
+                for line in self.src[span.clone()].trim().lines() {
+                    out.push_str(line.trim_end());
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    fn entry(&self, entry: &Entry, out: &mut String) {
+        let entry_type = entry.entry_type.to_lowercase();
+        if entry.fields.is_empty() {
+            out.push_str(&format!("@{entry_type}{{{}}}\n", entry.key));
+            return;
+        }
+        out.push_str(&format!("@{entry_type}{{{},\n", entry.key));
+
+        let rank: HashMap<String, usize> = self
+            .bib_opts
+            .field_order
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (name.to_lowercase(), i))
+            .collect();
+        let mut fields: Vec<_> = entry.fields.iter().map(|f| (f.name.to_lowercase(), f)).collect();
+        fields.sort_by_key(|(name, _)| rank.get(name).copied().unwrap_or(usize::MAX));
+
+        let indent = self.indent();
+        let name_width = fields.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+        for (i, (name, field)) in fields.iter().enumerate() {
+            let verbatim = VERBATIM_FIELDS.contains(&name.as_str());
+            let prefix = format!("{indent}{name:name_width$} = ");
+            let value = self.value(&field.value, !verbatim);
+            let comma = if i + 1 < fields.len() || self.bib_opts.trailing_comma { "," } else { "" };
+            let line = format!("{prefix}{value}{comma}");
+            if verbatim || self.width(&line) <= self.opts.print_width.max(0) as usize {
+                out.push_str(&line);
+            } else {
+                self.wrap(&prefix, &value, comma, out);
+            }
+            out.push('\n');
+        }
+        out.push_str("}\n");
+    }
+
+    /// Print `value` after `prefix`, breaking it at spaces to fit the print width.
+    /// Continuation lines are aligned after the opening delimiter.
+    fn wrap(&self, prefix: &str, value: &str, comma: &str, out: &mut String) {
+        let continuation = format!("{}{}", self.indent(), " ".repeat(self.width(prefix) - self.width(&self.indent()) + 1));
+        let width = self.opts.print_width.max(0) as usize;
+        let mut line = prefix.to_string();
+        let mut words = value.split(' ').peekable();
+        let mut first = true;
+        while let Some(word) = words.next() {
+            let word = if words.peek().is_none() { format!("{word}{comma}") } else { word.to_string() };
+            if !first && self.width(&line) + 1 + self.width(&word) > width {
+                out.push_str(&line);
+                out.push('\n');
+                line = format!("{continuation}{word}");
+            } else {
+                if !first {
+                    line.push(' ');
+                }
+                line.push_str(&word);
+            }
+            first = false;
+        }
+        out.push_str(&line);
+    }
+
+    /// Print `value` on one line, collapsing whitespace if `normalize` is set.
+    ///
+    /// The spaces around a piece are only removed when it is the whole value, since
+    /// they separate words in concatenations.
+    fn value(&self, value: &Value, normalize: bool) -> String {
+        let single = value.parts.len() == 1;
+        let text = |text: &str| {
+            if !normalize {
+                return text.to_string();
+            }
+            let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            if single {
+                return collapsed;
+            }
+            let before = if text.starts_with(char::is_whitespace) { " " } else { "" };
+            let after = if text.ends_with(char::is_whitespace) && !collapsed.is_empty() { " " } else { "" };
+            format!("{before}{collapsed}{after}")
+        };
+        let parts: Vec<String> = value
+            .parts
+            .iter()
+            .map(|part| match part {
+                ValuePart::Braced(t) | ValuePart::Quoted(t) => {
+                    let t = text(t);
+                    let use_quotes = match self.bib_opts.delimiter {
+                        Delimiter::Quotes => !has_top_level_quote(&t),
+                        Delimiter::Braces => !is_balanced(&t),
+                    };
+                    if use_quotes { format!("\"{t}\"") } else { format!("{{{t}}}") }
+                }
+                ValuePart::Number(t) | ValuePart::Macro(t) => t.clone(),
+            })
+            .collect();
+        parts.join(" # ")
+    }
+}
+
+/// Whether `text` contains a `"` outside of braces, which would end a quoted value.
+fn has_top_level_quote(text: &str) -> bool {
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            '"' if depth == 0 => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Whether the braces of `text` are balanced, which a braced value requires.
+fn is_balanced(text: &str) -> bool {
+    let mut depth = 0usize;
+    for c in text.chars() {
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return false,
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    depth == 0
+}
@@ -0,0 +1,162 @@
+//! On-disk cache of formatted documents.
+//!
+//! [`CachedFormatter`] keeps the output of the formatter in a directory, keyed by a hash
+//! of the input, the [options](FormatOptions) and the version of the formatter, including
+//! a digest of its Rust sources and the bundled JavaScript code. Documents formatted before
+//! with the same options are not formatted again, and rebuilding the formatter from changed
+//! sources invalidates every entry.
+//!
+//! ```
+//! use unlatex::cache::CachedFormatter;
+//! use unlatex::FormatOptions;
+//!
+//! let dir = std::env::temp_dir().join("unlatex-cache-doctest");
+//! let formatter = CachedFormatter::new(&dir);
+//! let opts = FormatOptions::default();
+//! let formatted = formatter.format("E =   mc^2", &opts).unwrap();
+//! // the second time is read from the cache
+//! assert_eq!(formatter.format("E =   mc^2", &opts).unwrap(), formatted);
+//! # std::fs::remove_dir_all(dir).ok();
+//! ```
+//!
+//! The cache is best-effort: entries that cannot be read or written are formatted again,
+//! and the directory may be deleted at any time. [`CachedFormatter::prune`] removes old
+//! entries, such as those left by previous versions, to bound its size.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
+
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::{format_with_options, FormatOptions, Result};
+
+/// Tag of entries whose output is the input.
+const UNCHANGED: &str = "=";
+
+/// Tag of entries followed by their output.
+const CHANGED: &str = ">";
+
+/// Age after which [`CachedFormatter::prune`] removes entries by default.
+pub const MAX_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Total size of the entries [`CachedFormatter::prune`] keeps by default.
+pub const MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Formats documents, reusing the output cached in a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedFormatter {
+    dir: PathBuf,
+}
+
+impl CachedFormatter {
+    /// Cache entries in `dir`, which is created when needed.
+    pub fn new(dir: impl Into<PathBuf>) -> CachedFormatter {
+        CachedFormatter { dir: dir.into() }
+    }
+
+    /// Directory of the cache entries.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Format `input` like [`format_with_options`], unless it is cached.
+    pub fn format(&self, input: &str, opts: &FormatOptions) -> Result<String> {
+        self.format_with(input, opts, |input| format_with_options(input, opts))
+    }
+
+    /// Format `input` with `format` unless it is cached, caching its output if it succeeds.
+    ///
+    /// `format` must give the output of [`format_with_options`] with `opts`, like
+    /// [`format_chunked`](crate::chunked::format_chunked), as it is cached as such.
+    pub fn format_with<E>(
+        &self,
+        input: &str,
+        opts: &FormatOptions,
+        format: impl FnOnce(&str) -> Result<String, E>,
+    ) -> Result<String, E> {
+        let path = self.dir.join(key(input, opts));
+        if let Some(output) = read_entry(&path, input) {
+            return Ok(output);
+        }
+        let output = format(input)?;
+        // a cache which cannot be written only costs formatting again
+        let _ = write_entry(&self.dir, &path, input, &output);
+        Ok(output)
+    }
+
+    /// Remove the entries written more than `max_age` ago, then the oldest ones until those
+    /// left take at most `max_size` bytes.
+    pub fn prune(&self, max_age: Duration, max_size: u64) -> std::io::Result<()> {
+        let now = SystemTime::now();
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() {
+                continue;
+            }
+            let age = metadata.modified().ok().and_then(|modified| now.duration_since(modified).ok()).unwrap_or_default();
+            entries.push((age, metadata.len(), entry.path()));
+        }
+        // the youngest entries first
+        entries.sort_by_key(|(age, ..)| *age);
+        let mut size = 0;
+        for (age, len, path) in entries {
+            size += len;
+            if age > max_age || size > max_size {
+                // another formatter may have pruned it already
+                match fs::remove_file(path) {
+                    Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hash of the code of the formatter, which entries are invalidated with.
+fn version() -> u128 {
+    static VERSION: OnceLock<u128> = OnceLock::new();
+    *VERSION.get_or_init(|| {
+        let mut hasher = Xxh3::new();
+        hasher.update(env!("CARGO_PKG_VERSION").as_bytes());
+        hasher.update(env!("UNLATEX_SOURCE_DIGEST").as_bytes());
+        hasher.update(crate::JS_SRC.as_bytes());
+        hasher.digest128()
+    })
+}
+
+/// Name of the entry of `input` formatted with `opts`.
+fn key(input: &str, opts: &FormatOptions) -> String {
+    let mut hasher = Xxh3::new();
+    hasher.update(&version().to_le_bytes());
+    hasher.update(&opts.print_width.to_le_bytes());
+    hasher.update(&[opts.use_tabs as u8]);
+    hasher.update(&opts.tab_width.to_le_bytes());
+    hasher.update(&[opts.document_only as u8]);
+    hasher.update(input.as_bytes());
+    format!("{:032x}", hasher.digest128())
+}
+
+fn read_entry(path: &Path, input: &str) -> Option<String> {
+    let entry = fs::read_to_string(path).ok()?;
+    if entry == UNCHANGED {
+        Some(input.to_string())
+    } else {
+        entry.strip_prefix(CHANGED).map(str::to_string)
+    }
+}
+
+fn write_entry(dir: &Path, path: &Path, input: &str, output: &str) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let entry = if output == input { UNCHANGED.to_string() } else { format!("{CHANGED}{output}") };
+    // entries are renamed into place so concurrent formatters never read half of one
+    let partial = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&partial, entry)?;
+    fs::rename(&partial, path).inspect_err(|_| {
+        let _ = fs::remove_file(&partial);
+    })
+}
@@ -0,0 +1,576 @@
+//! Formatting large documents in chunks.
+//!
+//! Formatting a whole document at once gets slow, and may exhaust the stack of the
+//! JavaScript engine, as documents grow to megabytes. [`format_chunked`] splits the body
+//! of a document at its paragraph breaks, formats runs of paragraphs on their own,
+//! optionally on several threads, and stitches them back together. Sections start their
+//! own paragraph in most documents, so they are split at as well.
+//!
+//! The output is the one of [`format_with_options`](crate::format_with_options) as long
+//! as paragraphs do not share context. Documents where they may, such as those with
+//! unbalanced braces, `\makeatletter` or suppression comments spanning paragraphs, are
+//! formatted as a whole. Paragraph breaks are found by scanning the input for groups,
+//! environments and math rather than by parsing it, so that the whole of a large document
+//! never goes through the JavaScript engine.
+//!
+//! ```
+//! use unlatex::chunked::{format_chunked, ChunkOptions};
+//! use unlatex::FormatOptions;
+//!
+//! let input = "\\begin{document}\nFirst.\n\n\\section{Two}\nSecond   one.\n\nThird.\n\\end{document}\n";
+//! let opts = FormatOptions { document_only: false, ..FormatOptions::default() };
+//! let chunked = format_chunked(input, &opts, &ChunkOptions { chunk_size: 0, jobs: 2 }).unwrap();
+//! assert_eq!(chunked, unlatex::format_with_options(input, &opts).unwrap());
+//! ```
+//!
+//! # Alignment
+//!
+//! The printer lays out the paragraphs of a body as a single Prettier `fill`, which
+//! alternates between content and separators. How a paragraph wraps therefore depends on
+//! whether an odd or even number of items precedes it, which is context a chunk does not
+//! have. Rather than counting items the way the printer does, a probe paragraph wrapping
+//! differently in either case is formatted after each chunk and in place of the chunks,
+//! and chunks are shifted by a one-word paragraph where needed.
+
+use std::ops::Range;
+use std::sync::{mpsc, Mutex};
+
+use crate::document::is_fragile_text;
+use crate::error::{Error, Result};
+use crate::ignore::{IGNORE, IGNORE_FILE, OFF, ON};
+use crate::{format_with_options, FormatOptions};
+
+/// Name of the macro starting the probe paragraph.
+const PROBE: &str = "latexformatprobe";
+
+/// Paragraph shifting the alignment of what follows it by one item.
+const SHIFT: &str = "x\n\n";
+
+/// Stack size of the threads formatting chunks, as large as the usual main thread's.
+const STACK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Options of [`format_chunked`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkOptions {
+    /// Size in bytes chunks are grown to, by whole paragraphs.
+    pub chunk_size: usize,
+    /// Number of threads formatting chunks, each with its own engine.
+    pub jobs: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        ChunkOptions { chunk_size: 64 * 1024, jobs: 1 }
+    }
+}
+
+/// Format LaTeX document in chunks of paragraphs, giving the same output as
+/// [`format_with_options`] for documents whose paragraphs do not share context.
+///
+/// The body of the `document` environment is split if there is one, otherwise the whole
+/// document. Its first and last paragraphs are formatted along with what surrounds them.
+///
+/// Fails with [`Error::Chunking`] rather than formatting the document as a whole if the
+/// formatted chunks cannot be aligned with the rest of it.
+pub fn format_chunked(input: &str, opts: &FormatOptions, chunk_opts: &ChunkOptions) -> Result<String> {
+    // the printer leaves documents untouched when asked to only format their body
+    if opts.document_only && input.contains("\\begin{document}") {
+        return format_with_options(input, opts);
+    }
+    let Some(split) = split(input, chunk_opts.chunk_size) else {
+        return format_with_options(input, opts);
+    };
+    stitch(input, opts, chunk_opts.jobs, &split)?.ok_or_else(|| Error::Chunking {
+        message: "the formatted chunks do not line up with the rest of the document".into(),
+    })
+}
+
+/// Paragraphs of a document to format in chunks.
+struct Split {
+    /// Byte range of the chunks, between the first and last paragraph breaks of the body.
+    middle: Range<usize>,
+    chunks: Vec<Range<usize>>,
+    /// Whether the body is the one of the `document` environment.
+    in_document: bool,
+}
+
+/// Split the paragraphs of `input` between its first and last paragraph breaks into
+/// chunks of at least `chunk_size` bytes. `None` if there is nothing to split or
+/// paragraphs may share context.
+fn split(input: &str, chunk_size: usize) -> Option<Split> {
+    if input.contains(PROBE) || is_fragile_text(input) {
+        return None;
+    }
+    let scan = scan(input)?;
+    let leading = scan.root.iter().take_while(|item| !matches!(item, Item::Other));
+    if leading.clone().any(|item| matches!(item, Item::Comment(text) if text.trim() == IGNORE_FILE)) {
+        return None;
+    }
+    let content: Vec<Item> = match &scan.document {
+        Some(body) => {
+            // the `document` environment may be excluded from formatting as a whole
+            let (off, ignore_next, _) = paragraph_breaks(&scan.root[..body.index]);
+            if off || ignore_next {
+                return None;
+            }
+            // a comment on the line of `\begin{document}` stays ahead of the trimmed content
+            let (comment, content) = body.content.split_at(usize::from(body.sameline && !body.content.is_empty()));
+            comment.iter().chain(trim(content)).cloned().collect()
+        }
+        None => trim(&scan.root).to_vec(),
+    };
+    let (_, _, breaks) = paragraph_breaks(&content);
+    if breaks.len() < 2 {
+        return None;
+    }
+    let middle = breaks[0].end..breaks[breaks.len() - 1].start;
+    let in_document = scan.document.is_some();
+    // the printer does not lay out documents with a `\documentclass` as a `fill`
+    if input[middle.clone()].contains("\\documentclass") || !in_document && input.contains("\\documentclass") {
+        return None;
+    }
+
+    let mut chunks: Vec<Range<usize>> = vec![];
+    for pair in breaks.windows(2) {
+        let paragraph = pair[0].end..pair[1].start;
+        match chunks.last_mut() {
+            Some(chunk) if chunk.len() < chunk_size => chunk.end = paragraph.end,
+            _ => chunks.push(paragraph),
+        }
+    }
+    Some(Split { middle, chunks, in_document })
+}
+
+/// The paragraph breaks of `content` along with whether a `% latexformat: off` region or
+/// a `% latexformat-ignore` comment is still open after it.
+///
+/// Paragraph breaks between suppression comments and what they exclude are kept.
+fn paragraph_breaks(content: &[Item]) -> (bool, bool, Vec<Range<usize>>) {
+    let (mut off, mut ignore_next) = (false, false);
+    let mut breaks = vec![];
+    for item in content {
+        match item {
+            Item::Comment(text) if text.trim() == OFF => off = true,
+            Item::Comment(text) if text.trim() == ON => off = false,
+            Item::Comment(text) if text.trim() == IGNORE => ignore_next = true,
+            Item::Comment(_) => {}
+            Item::Parbreak(range) if !off && !ignore_next => breaks.push(range.clone()),
+            Item::Parbreak(_) => {}
+            Item::Other => ignore_next = false,
+        }
+    }
+    (off, ignore_next, breaks)
+}
+
+/// `content` without the paragraph breaks starting and ending it, which the parser trims.
+fn trim<'a, 'b>(content: &'b [Item<'a>]) -> &'b [Item<'a>] {
+    let start = content.iter().take_while(|item| matches!(item, Item::Parbreak(_))).count();
+    let end = content.iter().rposition(|item| !matches!(item, Item::Parbreak(_))).map_or(start, |i| i + 1);
+    &content[start..end.max(start)]
+}
+
+/// What a level of a document is made of, as far as splitting it is concerned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Item<'a> {
+    /// Byte range of a paragraph break.
+    Parbreak(Range<usize>),
+    /// Text of a comment, following the `%`.
+    Comment(&'a str),
+    /// Anything else but whitespace, such as text, a macro or a whole group.
+    Other,
+}
+
+/// The items of the top level of a document and of its `document` environment.
+#[derive(Debug)]
+struct Scan<'a> {
+    root: Vec<Item<'a>>,
+    /// The first `document` environment of the top level.
+    document: Option<Body<'a>>,
+}
+
+/// The items of a `document` environment.
+#[derive(Debug)]
+struct Body<'a> {
+    /// Index of the environment in the items of the top level.
+    index: usize,
+    /// Whether the environment starts with a comment on the line of `\begin{document}`.
+    sameline: bool,
+    content: Vec<Item<'a>>,
+}
+
+/// What is open while scanning a document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame<'a> {
+    Group,
+    Environment(&'a str),
+    /// Math ended by the given delimiter.
+    Math(&'static str),
+}
+
+/// Scan `input` for the paragraph breaks and comments of its top level and of its
+/// `document` environment, without parsing it.
+///
+/// The scan follows how the parser splits documents into groups, environments and math.
+/// `None` if one of them is not closed, or closed out of order, where the parser would
+/// leave what it could not match in the tree.
+fn scan(input: &str) -> Option<Scan<'_>> {
+    let bytes = input.as_bytes();
+    let mut stack: Vec<Frame> = vec![];
+    let mut scan = Scan { root: vec![], document: None };
+    // optional arguments may span paragraph breaks, which are not split at then
+    let mut brackets = 0usize;
+    // whether the first `document` environment is open
+    let mut in_body = false;
+    let mut p = 0;
+    while p < bytes.len() {
+        let at_root = stack.is_empty();
+        let in_document = in_body && stack.len() == 1;
+        let rest = &input[p..];
+        let (item, end) = match bytes[p] {
+            b'%' => {
+                let end = rest.find(['\n', '\r']).map_or(input.len(), |i| p + i);
+                let text = &input[p + 1..end];
+                // suppression comments within what may be an argument do not apply here
+                if brackets > 0 && [IGNORE, IGNORE_FILE, OFF, ON].contains(&text.trim()) {
+                    return None;
+                }
+                (Some(Item::Comment(text)), end)
+            }
+            b' ' | b'\t' | b'\n' | b'\r' => {
+                let end = rest.find(|c| !matches!(c, ' ' | '\t' | '\n' | '\r')).map_or(input.len(), |i| p + i);
+                let run = &input[p..end];
+                // the formatter reads `\r\n` as a single line break, unlike the parser alone
+                if run.matches(['\n', '\r']).count() - run.matches("\r\n").count() < 2 || brackets > 0 {
+                    (None, end)
+                } else {
+                    // spaces before a comment belong to it
+                    let last = p + run.rfind(['\n', '\r']).expect("line break in paragraph break") + 1;
+                    let stop = if bytes.get(end) == Some(&b'%') { last } else { end };
+                    (Some(Item::Parbreak(p..stop)), stop)
+                }
+            }
+            b'{' => {
+                stack.push(Frame::Group);
+                (Some(Item::Other), p + 1)
+            }
+            b'}' => {
+                if stack.pop() != Some(Frame::Group) {
+                    return None;
+                }
+                (None, p + 1)
+            }
+            b'$' => {
+                let display = rest.starts_with("$$");
+                match stack.last() {
+                    Some(Frame::Math("$$")) if display => {
+                        stack.pop();
+                        (None, p + 2)
+                    }
+                    Some(Frame::Math("$")) => {
+                        stack.pop();
+                        (None, p + 1)
+                    }
+                    // a lone `$` in other math is left as is by the parser
+                    Some(Frame::Math(_)) if !display => return None,
+                    _ => {
+                        stack.push(Frame::Math(if display { "$$" } else { "$" }));
+                        (Some(Item::Other), p + if display { 2 } else { 1 })
+                    }
+                }
+            }
+            b'\\' => {
+                let end = escape(input, p, &mut stack)?;
+                if stack.len() == 1 && scan.document.is_none() && rest.starts_with("\\begin{document}") {
+                    // the environment may be the argument of a macro
+                    if brackets > 0 {
+                        return None;
+                    }
+                    let sameline = input[end..].trim_start_matches([' ', '\t']).starts_with('%');
+                    scan.document = Some(Body { index: scan.root.len(), sameline, content: vec![] });
+                    in_body = true;
+                }
+                // the end of an environment belongs to it, like closing braces and math
+                ((!rest.starts_with("\\end{")).then_some(Item::Other), end)
+            }
+            b'[' | b']' => {
+                if at_root || in_document {
+                    brackets = if bytes[p] == b'[' { brackets + 1 } else { brackets.saturating_sub(1) };
+                }
+                (Some(Item::Other), p + 1)
+            }
+            _ => {
+                let end = rest.find(['%', ' ', '\t', '\n', '\r', '{', '}', '$', '\\', '[', ']']).map_or(input.len(), |i| p + i);
+                (Some(Item::Other), end)
+            }
+        };
+        match (item, &mut scan.document) {
+            (Some(item), _) if at_root => scan.root.push(item),
+            (Some(item), Some(body)) if in_document => body.content.push(item),
+            _ => {}
+        }
+        in_body &= !stack.is_empty();
+        p = end;
+    }
+    stack.is_empty().then_some(scan)
+}
+
+/// Environments whose content is not parsed, as the parser knows them.
+const VERBATIM_ENVIRONMENTS: &[&str] = &["verbatim*", "verbatim", "filecontents*", "filecontents", "comment", "lstlisting"];
+
+/// Scan what starts with the escape character at `p` of `input`, opening and closing
+/// environments and math on `stack`, and return where it ends. `None` if it cannot be
+/// matched the way the parser would.
+fn escape<'a>(input: &'a str, p: usize, stack: &mut Vec<Frame<'a>>) -> Option<usize> {
+    let rest = &input[p + 1..];
+    // `\verb` is followed by its delimiter, even within a macro name like the parser does
+    if let Some(verb) = rest.strip_prefix("verb") {
+        let verb = verb.strip_prefix('*').unwrap_or(verb);
+        let delimiter = verb.chars().next()?;
+        let start = input.len() - verb.len() + delimiter.len_utf8();
+        return input[start..].find(delimiter).map(|i| start + i + delimiter.len_utf8());
+    }
+    if let Some(name) = rest.strip_prefix("begin{") {
+        let name = &name[..name.find('}')?];
+        let start = p + "\\begin{".len() + name.len() + 1;
+        if VERBATIM_ENVIRONMENTS.contains(&name) {
+            let end = format!("\\end{{{name}}}");
+            return input[start..].find(&end).map(|i| start + i + end.len());
+        }
+        stack.push(Frame::Environment(name));
+        return Some(start);
+    }
+    if let Some(name) = rest.strip_prefix("end{") {
+        let name = &name[..name.find('}')?];
+        if stack.pop() != Some(Frame::Environment(name)) {
+            return None;
+        }
+        return Some(p + "\\end{".len() + name.len() + 1);
+    }
+    let letters = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+    if matches!(&rest[..letters], "begin" | "end") {
+        return None;
+    }
+    if letters > 0 {
+        return Some(p + 1 + letters);
+    }
+    let Some(c) = rest.chars().next() else {
+        return Some(input.len());
+    };
+    match c {
+        '[' => stack.push(Frame::Math("\\]")),
+        '(' => stack.push(Frame::Math("\\)")),
+        ']' if stack.pop() != Some(Frame::Math("\\]")) => return None,
+        ')' if stack.pop() != Some(Frame::Math("\\)")) => return None,
+        _ => {}
+    }
+    Some(p + 1 + c.len_utf8())
+}
+
+/// A chunk formatted in the context of its body.
+struct Formatted {
+    lines: Vec<String>,
+    /// Whether the chunk was shifted.
+    shifted: bool,
+    /// Whether what follows the chunk is aligned as if shifted.
+    shifted_after: bool,
+}
+
+/// Formats text in the context of the body being split.
+struct Context<'a> {
+    opts: &'a FormatOptions,
+    /// Text around the body.
+    before: &'static str,
+    after: &'static str,
+    probe: String,
+    /// Formatted probe, unshifted and shifted.
+    references: [Vec<String>; 2],
+}
+
+impl Context<'_> {
+    /// Format `text` in the body, followed by the probe, returning the lines of the
+    /// output along with the range of those of the probe.
+    fn format(&self, text: &str) -> Result<Option<(Vec<String>, Range<usize>)>> {
+        let source = format!("{}{text}{}\n\n{SHIFT}{}", self.before, self.probe, self.after);
+        let output = format_with_options(&source, self.opts)?;
+        let lines: Vec<String> = output.split('\n').map(String::from).collect();
+        Ok(probe_lines(&lines).map(|probe| (lines, probe)))
+    }
+
+    /// Whether the probe `lines` were formatted shifted.
+    fn alignment(&self, lines: &[String]) -> Option<bool> {
+        self.references.iter().position(|reference| reference == lines).map(|i| i == 1)
+    }
+
+    /// Format the chunk `text`, shifted or not.
+    fn format_chunk(&self, text: &str, shifted: bool) -> Result<Option<Formatted>> {
+        let shift = if shifted { SHIFT } else { "" };
+        let Some((lines, probe)) = self.format(&format!("{shift}{text}\n\n"))? else {
+            return Ok(None);
+        };
+        let Some(shifted_after) = self.alignment(&lines[probe.clone()]) else {
+            return Ok(None);
+        };
+        // the lines of `before`, of the shift and the blank line before the probe
+        let start = self.before.matches('\n').count() + if shifted { 2 } else { 0 };
+        if probe.start < start + 2 || !lines[probe.start - 1].is_empty() || shifted && lines[start - 2].trim() != "x" {
+            return Ok(None);
+        }
+        let lines = lines[start..probe.start - 1].to_vec();
+        Ok(Some(Formatted { lines, shifted, shifted_after }))
+    }
+}
+
+/// Range of the lines of the probe paragraph in `lines`.
+fn probe_lines(lines: &[String]) -> Option<Range<usize>> {
+    let start = lines.iter().rposition(|line| line.trim_start().starts_with(&format!("\\{PROBE}")))?;
+    let end = lines[start..].iter().position(|line| line.is_empty()).map_or(lines.len(), |i| start + i);
+    Some(start..end)
+}
+
+/// Format the chunks of `split` and stitch them into the rest of `input`, or `None` if the
+/// output of the printer is not as expected.
+fn stitch(input: &str, opts: &FormatOptions, jobs: usize, split: &Split) -> Result<Option<String>> {
+    let words = opts.print_width.max(0) as usize + 10;
+    let mut context = Context {
+        opts,
+        before: if split.in_document { "\\begin{document}\n" } else { "" },
+        after: if split.in_document { "\n\\end{document}" } else { "" },
+        probe: format!("\\{PROBE}{}{}{}", " x".repeat(words), " xx".repeat(words), " xxx".repeat(words)),
+        references: [vec![], vec![]],
+    };
+    for (i, shift) in ["", SHIFT].into_iter().enumerate() {
+        let Some((lines, probe)) = context.format(shift)? else {
+            return Ok(None);
+        };
+        context.references[i] = lines[probe].to_vec();
+    }
+    if context.references[0] == context.references[1] {
+        return Ok(None);
+    }
+    // whether the probe itself shifts what follows it
+    let Some(probe_shifts) = context.format_chunk(&context.probe, false)?.map(|formatted| formatted.shifted_after) else {
+        return Ok(None);
+    };
+
+    let outer = |shift: &str| -> Result<Option<(Vec<String>, Range<usize>)>> {
+        let source = format!("{}{shift}{}{}", &input[..split.middle.start], context.probe, &input[split.middle.end..]);
+        let lines: Vec<String> = format_with_options(&source, opts)?.split('\n').map(String::from).collect();
+        Ok(probe_lines(&lines).map(|probe| (lines, probe)))
+    };
+    let Some((mut lines, mut probe)) = outer("")? else {
+        return Ok(None);
+    };
+    let Some(shifted) = context.alignment(&lines[probe.clone()]) else {
+        return Ok(None);
+    };
+
+    let chunks: Vec<&str> = split.chunks.iter().map(|range| &input[range.clone()]).collect();
+    let Some(formatted) = format_all(&context, &chunks, shifted, jobs)? else {
+        return Ok(None);
+    };
+
+    // the probe stands for the chunks, so it has to shift what follows like they do
+    let shifted_after = formatted.last().map_or(shifted, |formatted| formatted.shifted_after);
+    if shifted ^ probe_shifts != shifted_after {
+        let Some((shifted_lines, shifted_probe)) = outer(SHIFT)? else {
+            return Ok(None);
+        };
+        if shifted_probe.start < 2 || shifted_lines[shifted_probe.start - 2].trim() != "x" {
+            return Ok(None);
+        }
+        lines = shifted_lines;
+        probe = shifted_probe.start - 2..shifted_probe.end;
+    }
+
+    let mut output: Vec<&str> = lines[..probe.start].iter().map(String::as_str).collect();
+    for (i, chunk) in formatted.iter().enumerate() {
+        if i > 0 {
+            output.push("");
+        }
+        output.extend(chunk.lines.iter().map(String::as_str));
+    }
+    output.extend(lines[probe.end..].iter().map(String::as_str));
+    Ok(Some(output.join("\n")))
+}
+
+/// Format `chunks` on `jobs` threads with an engine each, the first one `shifted` and the
+/// others aligned with what precedes them.
+fn format_all(context: &Context, chunks: &[&str], shifted: bool, jobs: usize) -> Result<Option<Vec<Formatted>>> {
+    if jobs <= 1 {
+        let mut formatted: Vec<Formatted> = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            let shifted = formatted.last().map_or(shifted, |formatted| formatted.shifted_after);
+            let Some(chunk) = context.format_chunk(chunk, shifted)? else {
+                return Ok(None);
+            };
+            formatted.push(chunk);
+        }
+        return Ok(Some(formatted));
+    }
+
+    let (tasks, queue) = mpsc::channel::<(usize, bool)>();
+    let queue = Mutex::new(queue);
+    std::thread::scope(|scope| {
+        // the threads stop once the closure returns and drops the sender
+        let tasks = tasks;
+        let (results, received) = mpsc::channel();
+        for _ in 0..jobs.min(chunks.len()) {
+            let (queue, results) = (&queue, results.clone());
+            std::thread::Builder::new()
+                .stack_size(STACK_SIZE)
+                .spawn_scoped(scope, move || {
+                    // the engine of the thread is kept for all the chunks it formats
+                    while let Ok((i, shifted)) = queue.lock().expect("poisoned chunk queue").recv() {
+                        if results.send((i, context.format_chunk(chunks[i], shifted))).is_err() {
+                            break;
+                        }
+                    }
+                })
+                .expect("failed to spawn formatting thread");
+        }
+        drop(results);
+        let run = |batch: &[(usize, bool)]| -> Result<Option<Vec<(usize, Formatted)>>> {
+            for &task in batch {
+                tasks.send(task).expect("formatting threads exited");
+            }
+            let mut formatted = Vec::with_capacity(batch.len());
+            for _ in batch {
+                let (i, chunk) = received.recv().expect("formatting threads exited");
+                match chunk? {
+                    Some(chunk) => formatted.push((i, chunk)),
+                    None => return Ok(None),
+                }
+            }
+            Ok(Some(formatted))
+        };
+
+        // chunks are formatted unshifted but the first, then again where they should have been
+        let guesses: Vec<(usize, bool)> = (0..chunks.len()).map(|i| (i, i == 0 && shifted)).collect();
+        let Some(first) = run(&guesses)? else {
+            return Ok(None);
+        };
+        let mut formatted: Vec<Option<Formatted>> = (0..chunks.len()).map(|_| None).collect();
+        for (i, chunk) in first {
+            formatted[i] = Some(chunk);
+        }
+        let mut formatted: Vec<Formatted> = formatted.into_iter().map(|chunk| chunk.expect("missing formatted chunk")).collect();
+        let mut expected = Vec::with_capacity(chunks.len());
+        let mut shift = shifted;
+        for chunk in &formatted {
+            expected.push(shift);
+            // items of the chunk shift what follows whatever the chunk starts with
+            shift ^= chunk.shifted ^ chunk.shifted_after;
+        }
+        let redo: Vec<(usize, bool)> = (0..chunks.len()).filter(|&i| formatted[i].shifted != expected[i]).map(|i| (i, expected[i])).collect();
+        let Some(again) = run(&redo)? else {
+            return Ok(None);
+        };
+        for (i, chunk) in again {
+            if chunk.shifted_after == formatted[i].shifted_after {
+                return Ok(None);
+            }
+            formatted[i] = chunk;
+        }
+        Ok(Some(formatted))
+    })
+}
@@ -0,0 +1,236 @@
+//! Removing comments, notes and disabled code from a document, e.g. before publishing
+//! its source.
+//!
+//! ```
+//! use unlatex::cleanup::{cleanup, CleanupOptions};
+//!
+//! let src = "Text% joined\nmore. \\todo{check}\n% remark\n\\iffalse old\\else new\\fi\n";
+//! let clean = cleanup(src, &CleanupOptions::default()).unwrap();
+//! assert_eq!(clean, "Text%\nmore.\nnew\n");
+//! ```
+//!
+//! Everything which is not removed is kept byte for byte.
+
+use std::ops::Range;
+
+use crate::ast::Node;
+use crate::error::Result;
+use crate::macros::{macro_call, visit_lists};
+use crate::span::{apply_edits, node_range, OffsetMap};
+
+/// What [`cleanup`] removes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CleanupOptions {
+    /// Remove comments, keeping a `%` where it joins two lines.
+    pub comments: bool,
+    /// Environments removed with their content.
+    pub environments: Vec<String>,
+    /// Macros removed with their arguments.
+    pub macros: Vec<String>,
+    /// Remove `\iffalse ... \fi` blocks, keeping the `\else` branch if any.
+    pub conditionals: bool,
+}
+
+impl Default for CleanupOptions {
+    fn default() -> Self {
+        CleanupOptions {
+            comments: true,
+            environments: vec!["comment".to_string()],
+            macros: vec!["todo".to_string()],
+            conditionals: true,
+        }
+    }
+}
+
+impl CleanupOptions {
+    /// Options only removing comments.
+    pub fn comments_only() -> Self {
+        CleanupOptions {
+            comments: true,
+            environments: vec![],
+            macros: vec![],
+            conditionals: false,
+        }
+    }
+}
+
+/// Remove what `opts` selects from `input`.
+pub fn cleanup(input: &str, opts: &CleanupOptions) -> Result<String> {
+    let root = crate::parse(input)?;
+    Ok(apply_edits(input, cleanup_edits(&root, input, opts)))
+}
+
+/// The edits removing what `opts` selects from `src`, parsed as `root`, as byte ranges
+/// and replacements. Edits may overlap, the first one starting wins.
+pub fn cleanup_edits(root: &Node, src: &str, opts: &CleanupOptions) -> Vec<(Range<usize>, String)> {
+    let map = OffsetMap::new(src);
+    let mut edits = vec![];
+    let range = |node: &Node| node_range(node, src, &map);
+
+    visit_lists(root, &mut |siblings| {
+        for (i, node) in siblings.iter().enumerate() {
+            match node {
+                Node::Comment { sameline, leading_whitespace, .. } if opts.comments => {
+                    if let Some(r) = range(node).or_else(|| comment_range(siblings, i, src, &map)) {
+                        edits.push(strip_comment(src, r, *sameline, *leading_whitespace));
+                    }
+                }
+                Node::Environment { env, .. } | Node::VerbatimEnvironment { env, .. }
+                    if opts.environments.contains(env) =>
+                {
+                    if let Some(r) = range(node) {
+                        edits.push((remove_range(src, r), String::new()));
+                    }
+                }
+                Node::Macro { content, .. } if opts.macros.contains(content) => {
+                    let (Some(call), Some(r)) = (macro_call(siblings, i, 1), range(node)) else {
+                        continue;
+                    };
+                    let end = match call.consumed {
+                        0 => r.end,
+                        n => range(&siblings[i + n]).map_or(r.end, |last| last.end),
+                    };
+                    edits.push((remove_range(src, r.start..end), String::new()));
+                }
+                Node::Macro { content, .. } if opts.conditionals && content == "iffalse" => {
+                    edits.extend(disabled_block(siblings, i, src, &map));
+                }
+                _ => {}
+            }
+        }
+    });
+    edits
+}
+
+/// Byte range of the comment `siblings[i]` when the parser left it without position, as it
+/// does for comments indented at the start of a line, found by scanning the source after
+/// the closest sibling before it with a position, or else before the closest one after it.
+///
+/// The range spans the comment like those of positioned comments: from the start of its
+/// line if it is alone on it, to the end of its line included.
+fn comment_range(siblings: &[Node], i: usize, src: &str, map: &OffsetMap) -> Option<Range<usize>> {
+    let comment = |node: &Node| match node {
+        Node::Comment { content, .. } => Some(format!("%{content}")),
+        _ => None,
+    };
+    // `text` at `offset` is the whole comment, not an escaped `\%` or the start of a longer one
+    let whole = |offset: usize, text: &str| {
+        let rest = &src[offset + text.len()..];
+        !src[..offset].ends_with('\\') && (rest.is_empty() || rest.starts_with(['\r', '\n']))
+    };
+
+    let before = (0..i).rev().find_map(|j| node_range(&siblings[j], src, map).map(|r| (j, r.end)));
+    let offset = if let Some((first, mut cursor)) = before {
+        // the comments in between without position come first, in order
+        let mut offset = None;
+        for node in &siblings[first + 1..=i] {
+            let Some(text) = comment(node) else {
+                continue;
+            };
+            let found = src[cursor..].match_indices(text.as_str()).map(|(k, _)| cursor + k).find(|&k| whole(k, &text))?;
+            cursor = found + text.len();
+            offset = Some(found);
+        }
+        offset?
+    } else {
+        let (last, mut cursor) = (i + 1..siblings.len()).find_map(|j| node_range(&siblings[j], src, map).map(|r| (j, r.start)))?;
+        let mut offset = None;
+        for node in siblings[i..last].iter().rev() {
+            let Some(text) = comment(node) else {
+                continue;
+            };
+            let found = src[..cursor].rmatch_indices(text.as_str()).map(|(k, _)| k).find(|&k| whole(k, &text))?;
+            cursor = found;
+            offset = Some(found);
+        }
+        offset?
+    };
+
+    let Node::Comment { content, sameline, .. } = &siblings[i] else {
+        return None;
+    };
+    let line_start = src[..offset].rfind('\n').map_or(0, |k| k + 1);
+    let start = if *sameline { offset } else { line_start };
+    let end = offset + 1 + content.len();
+    let end = src[end..].find('\n').map_or(src.len(), |k| end + k + 1);
+    Some(start..end)
+}
+
+/// Whether `name` is a TeX conditional closed by `\fi`.
+fn is_conditional(name: &str) -> bool {
+    name.starts_with("if") && name != "iff" && name != "ifthenelse"
+}
+
+/// The edits removing the `\iffalse` block starting at `siblings[start]`, if it is closed.
+fn disabled_block(siblings: &[Node], start: usize, src: &str, map: &OffsetMap) -> Vec<(Range<usize>, String)> {
+    let mut depth = 0usize;
+    let mut otherwise = None;
+    for (j, node) in siblings.iter().enumerate().skip(start + 1) {
+        let Node::Macro { content, .. } = node else {
+            continue;
+        };
+        match content.as_str() {
+            "fi" if depth == 0 => {
+                let range = |k: usize| node_range(&siblings[k], src, map);
+                let (Some(first), Some(fi)) = (range(start), range(j)) else {
+                    return vec![];
+                };
+                return match otherwise.and_then(range) {
+                    Some(otherwise) => {
+                        // spaces after the control word `\else` are not part of the branch
+                        let rest = &src[otherwise.end..];
+                        let end = otherwise.end + rest.len() - rest.trim_start_matches([' ', '\t']).len();
+                        vec![
+                            (remove_range(src, first.start..end), String::new()),
+                            (remove_range(src, fi), String::new()),
+                        ]
+                    }
+                    None => vec![(remove_range(src, first.start..fi.end), String::new())],
+                };
+            }
+            "fi" => depth -= 1,
+            "else" if depth == 0 => otherwise = Some(j),
+            name if is_conditional(name) => depth += 1,
+            _ => {}
+        }
+    }
+    vec![]
+}
+
+/// Extend `range` to its whole line if nothing else but whitespace or a comment is on
+/// it, or over the spaces before it if it is followed by whitespace.
+fn remove_range(src: &str, range: Range<usize>) -> Range<usize> {
+    let line_start = src[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = src[range.end..].find('\n').map_or(src.len(), |i| range.end + i);
+    let after = src[range.end..line_end].trim_start();
+    if src[line_start..range.start].trim().is_empty() && (after.is_empty() || after.starts_with('%')) {
+        return line_start..(line_end + 1).min(src.len());
+    }
+    if src[range.end..].starts_with(char::is_whitespace) {
+        let before = src[..range.start].trim_end_matches([' ', '\t']);
+        return before.len()..range.end;
+    }
+    range
+}
+
+/// The edit removing the comment at `range` without merging the lines or paragraphs
+/// around it.
+fn strip_comment(src: &str, range: Range<usize>, sameline: bool, leading_whitespace: bool) -> (Range<usize>, String) {
+    let newline = if src[range.clone()].ends_with('\n') { "\n" } else { "" };
+    if !sameline {
+        // a comment alone on its line goes with its line, the leading whitespace of the
+        // comment may include the end of the previous line. When a paragraph break
+        // follows, the newline is left to it.
+        let text = &src[range.clone()];
+        let indent = text.len() - text.trim_start().len();
+        let start = text[..indent].rfind('\n').map_or(range.start, |i| range.start + i + 1);
+        let end = if newline.is_empty() && src[range.end..].starts_with('\n') { range.end + 1 } else { range.end };
+        return (start..end, String::new());
+    }
+    if leading_whitespace {
+        (range, newline.to_string())
+    } else {
+        // `%` directly after text also swallows the line break
+        (range, format!("%{newline}"))
+    }
+}
@@ -0,0 +1,258 @@
+//! Comparing documents by meaning rather than text.
+//!
+//! [`semantic_diff`] parses two documents and walks their trees side by side, returning
+//! the first node where they differ. Layout is not part of the comparison:
+//!
+//! - whitespace is ignored, so reflowed paragraphs are equal;
+//! - runs of paragraph breaks count as one, and those starting or ending a group,
+//!   environment or the document are ignored;
+//! - comments are compared in document order apart from the tree, so moving a comment to
+//!   its own line or the end of the previous one is not a difference.
+//!
+//! ```
+//! let a = "Some \\emph{text} % note\nhere.\n\n\n\\section{Next}";
+//! let b = "Some \\emph{text}\n% note\nhere.\n\n\\section{Next}\n";
+//! assert!(unlatex::semantic_eq(a, b).unwrap());
+//!
+//! let difference = unlatex::compare::semantic_diff(a, "Some \\textbf{text} here.").unwrap().unwrap();
+//! assert_eq!(difference.to_string(), "`\\emph` became `\\textbf`");
+//! assert_eq!(&a[difference.left], "\\emph{text}");
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::ast::Node;
+use crate::span::{node_range, OffsetMap};
+use crate::{parse, Result};
+
+/// The first place where two documents differ.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Difference {
+    /// Byte range of the differing node in the first document, empty if it has none there.
+    pub left: Range<usize>,
+    /// Byte range of the differing node in the second document, empty if it has none there.
+    pub right: Range<usize>,
+    /// Description of the node of the first document, such as `` `\emph` ``, or `nothing`.
+    pub left_node: String,
+    /// Description of the node of the second document.
+    pub right_node: String,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} became {}", self.left_node, self.right_node)
+    }
+}
+
+/// Whether documents `a` and `b` have the same meaning, see [`semantic_diff`].
+pub fn semantic_eq(a: &str, b: &str) -> Result<bool> {
+    Ok(semantic_diff(a, b)?.is_none())
+}
+
+/// The first difference in meaning between documents `a` and `b`, in the order of `a`.
+pub fn semantic_diff(a: &str, b: &str) -> Result<Option<Difference>> {
+    let (a, b) = (Source::new(a), Source::new(b));
+    let (a_root, b_root) = (parse(&a.text)?, parse(&b.text)?);
+    let found = difference(&a_root, &b_root, (&a_root, &b_root)).or_else(|| {
+        let (a_comments, b_comments) = (comments(&a_root), comments(&b_root));
+        let differs = |(a, b): &(Option<&Node>, Option<&Node>)| match (a, b) {
+            (Some(Node::Comment { content: a, .. }), Some(Node::Comment { content: b, .. })) => a.trim() != b.trim(),
+            _ => true,
+        };
+        let mut pairs = (0..a_comments.len().max(b_comments.len())).map(|i| (a_comments.get(i).copied(), b_comments.get(i).copied()));
+        pairs.find(differs).map(|(left, right)| {
+            // a missing comment is placed at the end of the document
+            (Side { node: left, at: end(&a_root) }, Side { node: right, at: end(&b_root) })
+        })
+    });
+    Ok(found.map(|(left, right)| Difference {
+        left: a.span(left),
+        right: b.span(right),
+        left_node: describe(left.node),
+        right_node: describe(right.node),
+    }))
+}
+
+/// A document with `\r\n` line breaks turned into `\n`, as the parser takes the former
+/// for two line breaks.
+pub(crate) struct Source {
+    pub(crate) text: String,
+    offsets: OffsetMap,
+    /// Byte offsets in `text` of the line breaks which followed a `\r`.
+    returns: Vec<usize>,
+}
+
+impl Source {
+    pub(crate) fn new(original: &str) -> Source {
+        let text = original.replace("\r\n", "\n");
+        let returns = match text.len() == original.len() {
+            true => vec![],
+            false => original.match_indices("\r\n").enumerate().map(|(i, (offset, _))| offset - i).collect(),
+        };
+        Source { offsets: OffsetMap::new(&text), text, returns }
+    }
+
+    /// Byte offset in the original document of the UTF-16 `offset` of the parsed text.
+    fn original(&self, offset: usize) -> usize {
+        let byte = self.offsets.to_byte(offset);
+        byte + self.returns.partition_point(|&line_break| line_break < byte)
+    }
+
+    fn span(&self, side: Side<'_>) -> Range<usize> {
+        self.range(side.node, side.at)
+    }
+
+    /// Byte range in the original document of `node`, or of the UTF-16 offset `at` if there
+    /// is no node or it has no position.
+    pub(crate) fn range(&self, node: Option<&Node>, at: usize) -> Range<usize> {
+        let range = node.and_then(|node| node_range(node, &self.text, &self.offsets));
+        match range {
+            Some(mut range) => {
+                // comments span the whitespace before them
+                if let Some(Node::Comment { .. }) = node {
+                    let text = &self.text[range.clone()];
+                    range.start += text.len() - text.trim_start().len();
+                }
+                let start = self.original(self.offsets.to_utf16(range.start));
+                start..self.original(self.offsets.to_utf16(range.end)).max(start)
+            }
+            None => self.original(at)..self.original(at),
+        }
+    }
+}
+
+/// A node of a tree where two trees differ, `None` past the end of the content of a node.
+#[derive(Clone, Copy)]
+struct Side<'a> {
+    node: Option<&'a Node>,
+    /// UTF-16 offset standing for the difference when there is no node or it has no position.
+    at: usize,
+}
+
+fn end(node: &Node) -> usize {
+    node.position().map_or(0, |position| position.end.offset)
+}
+
+/// The first nodes where `a` and `b` differ, `anchors` being their closest ancestors with
+/// a position.
+fn difference<'a>(a: &'a Node, b: &'a Node, anchors: (&'a Node, &'a Node)) -> Option<(Side<'a>, Side<'a>)> {
+    let anchor = |node: &'a Node, anchor| if node.position().is_some() { node } else { anchor };
+    let anchors = (anchor(a, anchors.0), anchor(b, anchors.1));
+    let start = |node: &Node| node.position().map_or(0, |position| position.start.offset);
+    if !same_node(a, b) {
+        return Some((Side { node: Some(a), at: start(anchors.0) }, Side { node: Some(b), at: start(anchors.1) }));
+    }
+    for (left, right) in [(a.args(), b.args()), (a.content(), b.content())] {
+        let (mut left, mut right) = (meaningful(left).into_iter(), meaningful(right).into_iter());
+        // where a missing node would be: after the previous one, or at the start of the parent
+        let mut at = (start(anchors.0), start(anchors.1));
+        loop {
+            match (left.next(), right.next()) {
+                (None, None) => break,
+                (Some(left), Some(right)) => {
+                    if let Some(difference) = difference(left, right, anchors) {
+                        return Some(difference);
+                    }
+                    at = (left.position().map_or(at.0, |p| p.end.offset), right.position().map_or(at.1, |p| p.end.offset));
+                }
+                (left, right) => return Some((Side { node: left, at: at.0 }, Side { node: right, at: at.1 })),
+            }
+        }
+    }
+    None
+}
+
+/// The nodes of `nodes` which are compared: without whitespace and comments, with one
+/// paragraph break standing for a run of them and none at either end.
+pub(crate) fn meaningful(nodes: &[Node]) -> Vec<&Node> {
+    let mut kept: Vec<&Node> = Vec::with_capacity(nodes.len());
+    let mut parbreak = None;
+    for node in nodes {
+        match node {
+            Node::WhiteSpace { .. } | Node::Comment { .. } => {}
+            Node::Parbreak { .. } => {
+                parbreak = parbreak.or(Some(node));
+            }
+            node => {
+                if let Some(parbreak) = parbreak.take() {
+                    if !kept.is_empty() {
+                        kept.push(parbreak);
+                    }
+                }
+                kept.push(node);
+            }
+        }
+    }
+    kept
+}
+
+/// Comments of the tree under `node`, in document order.
+fn comments(node: &Node) -> Vec<&Node> {
+    fn walk<'a>(node: &'a Node, found: &mut Vec<&'a Node>) {
+        if let Node::Comment { .. } = node {
+            found.push(node);
+        }
+        for child in node.args().iter().chain(node.content()) {
+            walk(child, found);
+        }
+    }
+    let mut found = Vec::new();
+    walk(node, &mut found);
+    found
+}
+
+/// Whether the nodes are of the same kind with the same text, regardless of their children.
+pub(crate) fn same_node(a: &Node, b: &Node) -> bool {
+    match (a, b) {
+        (Node::Root { .. }, Node::Root { .. })
+        | (Node::Parbreak { .. }, Node::Parbreak { .. })
+        | (Node::DisplayMath { .. }, Node::DisplayMath { .. })
+        | (Node::Group { .. }, Node::Group { .. })
+        | (Node::InlineMath { .. }, Node::InlineMath { .. })
+        | (Node::Error, Node::Error) => true,
+        (Node::String { content: a, .. }, Node::String { content: b, .. }) => a == b,
+        (Node::Macro { content: a, escape_token: a_escape, .. }, Node::Macro { content: b, escape_token: b_escape, .. }) => {
+            a == b && a_escape == b_escape
+        }
+        (Node::Environment { env: a, .. }, Node::Environment { env: b, .. })
+        | (Node::MathEnv { env: a, .. }, Node::MathEnv { env: b, .. }) => a == b,
+        (
+            Node::VerbatimEnvironment { env: a_env, content: a, .. },
+            Node::VerbatimEnvironment { env: b_env, content: b, .. },
+        ) => a_env == b_env && a == b,
+        (Node::Verb { env: a_env, escape: a_escape, content: a, .. }, Node::Verb { env: b_env, escape: b_escape, content: b, .. }) => {
+            a_env == b_env && a_escape == b_escape && a == b
+        }
+        (
+            Node::Argument { open_mark: a_open, close_mark: a_close, .. },
+            Node::Argument { open_mark: b_open, close_mark: b_close, .. },
+        ) => a_open == b_open && a_close == b_close,
+        _ => false,
+    }
+}
+
+pub(crate) fn describe(node: Option<&Node>) -> String {
+    let Some(node) = node else {
+        return "nothing".to_string();
+    };
+    match node {
+        Node::Root { .. } => "the document".to_string(),
+        Node::String { content, .. } => format!("`{content}`"),
+        Node::WhiteSpace { .. } => "whitespace".to_string(),
+        Node::Parbreak { .. } => "a paragraph break".to_string(),
+        Node::Comment { content, .. } => format!("comment `%{content}`"),
+        Node::Macro { content, escape_token, .. } => format!("`{}{content}`", escape_token.as_deref().unwrap_or("\\")),
+        Node::Environment { env, .. } | Node::MathEnv { env, .. } | Node::VerbatimEnvironment { env, .. } => {
+            format!("environment `{env}`")
+        }
+        Node::DisplayMath { .. } => "display math".to_string(),
+        Node::Group { .. } => "a group".to_string(),
+        Node::InlineMath { .. } => "inline math".to_string(),
+        Node::Verb { env, .. } => format!("`\\{env}`"),
+        Node::Argument { open_mark, close_mark, .. } => format!("argument `{open_mark}…{close_mark}`"),
+        Node::Error => "an error".to_string(),
+    }
+}
@@ -0,0 +1,293 @@
+//! Project configuration files.
+//!
+//! Formatting options can be stored next to the documents they apply to, in a
+//! `.latexformat.toml` file (or a `[tool.latexformat]` section of `pyproject.toml`).
+//! The configuration is looked up from the directory of the formatted file upward,
+//! and the first one found wins.
+//!
+//! ```toml
+//! print-width = 100
+//! tab-width = 4
+//!
+//! # wider lines for hand-made tables
+//! [[overrides]]
+//! files = ["tables/*.tex"]
+//! print-width = 200
+//! ```
+//!
+//! Options for `.bib` files go in a `[bib]` table, see [`BibOptions`], the rules of
+//! `latexformat lint` are configured in a `[lint]` table, see [`LintOptions`], and how
+//! `latexformat count` counts words in a `[count]` table, see [`CountOptions`].
+//!
+//! Override patterns are matched against the path of the file relative to the
+//! configuration file. Patterns without a `/` match the file name in any directory.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobMatcher};
+use serde::Deserialize;
+
+use crate::bib::BibOptions;
+use crate::error::{Error, Result};
+use crate::lint::LintOptions;
+use crate::options::FormatOptions;
+use crate::stats::CountOptions;
+
+/// Names of dedicated configuration files, in lookup order.
+pub const CONFIG_FILE_NAMES: &[&str] = &[".latexformat.toml", "latexformat.toml"];
+
+/// Name of the Python project file that may carry a `[tool.latexformat]` section.
+pub const PYPROJECT_FILE_NAME: &str = "pyproject.toml";
+
+/// Keys of [`ConfigOptions`], which serde cannot check for typos as they are flattened
+/// into the tables holding them.
+const OPTION_KEYS: &[&str] = &["print-width", "use-tabs", "tab-width", "document-only"];
+
+/// Keys of the configuration besides [`OPTION_KEYS`].
+const TABLE_KEYS: &[&str] = &["overrides", "bib", "lint", "count"];
+
+/// Formatting options as written in a configuration file.
+///
+/// Every field is optional, unset fields leave the corresponding option untouched.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ConfigOptions {
+    /// See [`FormatOptions::print_width`].
+    pub print_width: Option<i32>,
+    /// See [`FormatOptions::use_tabs`].
+    pub use_tabs: Option<bool>,
+    /// See [`FormatOptions::tab_width`].
+    pub tab_width: Option<i32>,
+    /// See [`FormatOptions::document_only`].
+    pub document_only: Option<bool>,
+}
+
+impl ConfigOptions {
+    /// Overwrite the options in `opts` which are set in `self`.
+    pub fn apply_to(&self, opts: &mut FormatOptions) {
+        if let Some(print_width) = self.print_width {
+            opts.print_width = print_width;
+        }
+        if let Some(use_tabs) = self.use_tabs {
+            opts.use_tabs = use_tabs;
+        }
+        if let Some(tab_width) = self.tab_width {
+            opts.tab_width = tab_width;
+        }
+        if let Some(document_only) = self.document_only {
+            opts.document_only = document_only;
+        }
+    }
+}
+
+/// Options applied only to the files matching one of `files`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Override {
+    /// Glob patterns selecting the files this override applies to.
+    pub files: Vec<String>,
+    /// Options for the matching files.
+    #[serde(flatten)]
+    pub options: ConfigOptions,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RawConfig {
+    #[serde(flatten)]
+    options: ConfigOptions,
+    #[serde(default)]
+    overrides: Vec<Override>,
+    #[serde(default)]
+    bib: BibOptions,
+    #[serde(default)]
+    lint: LintOptions,
+    #[serde(default)]
+    count: CountOptions,
+}
+
+/// A loaded configuration file.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Path of the configuration file.
+    pub path: PathBuf,
+    /// Options applied to every file.
+    pub options: ConfigOptions,
+    /// Per-glob options, applied in order after [`options`](Config::options).
+    pub overrides: Vec<Override>,
+    /// Options for BibTeX databases, from the `[bib]` table.
+    pub bib: BibOptions,
+    /// Lint rule configuration, from the `[lint]` table.
+    pub lint: LintOptions,
+    /// Word counting rules, from the `[count]` table.
+    pub count: CountOptions,
+    matchers: Vec<Vec<(GlobMatcher, bool)>>,
+}
+
+impl Config {
+    /// Load the configuration file at `path`.
+    ///
+    /// If the file has a `[tool.latexformat]` section, only that section is read.
+    pub fn load(path: impl AsRef<Path>) -> Result<Config> {
+        let path = path.as_ref();
+        let src = fs::read_to_string(path)?;
+        let config = Config::from_toml(&src, path)?;
+        match config {
+            Some(config) => Ok(config),
+            None => Ok(Config::empty(&std::path::absolute(path)?)),
+        }
+    }
+
+    /// Find the configuration applying to `path`, looking in its directory and then in
+    /// each parent directory.
+    ///
+    /// `path` does not need to exist, which allows resolving the configuration of
+    /// unsaved editor buffers.
+    pub fn discover(path: impl AsRef<Path>) -> Result<Option<Config>> {
+        let path = std::path::absolute(path.as_ref())?;
+        let start = if path.is_dir() { path.as_path() } else { path.parent().unwrap_or(&path) };
+
+        for dir in start.ancestors() {
+            for name in CONFIG_FILE_NAMES {
+                let candidate = dir.join(name);
+                if candidate.is_file() {
+                    return Config::load(candidate).map(Some);
+                }
+            }
+            let candidate = dir.join(PYPROJECT_FILE_NAME);
+            if candidate.is_file() {
+                let src = fs::read_to_string(&candidate)?;
+                if let Some(config) = Config::from_toml(&src, &candidate)? {
+                    return Ok(Some(config));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parse a configuration from TOML source, as if it was read from `path`.
+    ///
+    /// Returns `None` for a `pyproject.toml` without a `[tool.latexformat]` section.
+    pub fn from_toml(src: &str, path: impl AsRef<Path>) -> Result<Option<Config>> {
+        let path = path.as_ref();
+        let invalid = |message: String| Error::Config {
+            path: path.display().to_string(),
+            message,
+        };
+
+        let mut table: toml::Table = toml::from_str(src).map_err(|e| invalid(e.message().to_string()))?;
+        let section = table
+            .remove("tool")
+            .and_then(|tool| match tool {
+                toml::Value::Table(mut tool) => tool.remove("latexformat"),
+                _ => None,
+            });
+        let table = match section {
+            Some(section) => section,
+            None if path.file_name().is_some_and(|name| name == PYPROJECT_FILE_NAME) => return Ok(None),
+            None => toml::Value::Table(table),
+        };
+
+        check_keys(&table, TABLE_KEYS).map_err(&invalid)?;
+        if let Some(toml::Value::Array(overrides)) = table.get("overrides") {
+            for table in overrides {
+                check_keys(table, &["files"]).map_err(&invalid)?;
+            }
+        }
+        let raw: RawConfig = table.try_into().map_err(|e: toml::de::Error| invalid(e.message().to_string()))?;
+        let matchers = raw
+            .overrides
+            .iter()
+            .map(|o| {
+                o.files
+                    .iter()
+                    .map(|pattern| {
+                        let glob = GlobBuilder::new(pattern)
+                            .literal_separator(true)
+                            .build()
+                            .map_err(|e| invalid(e.to_string()))?;
+                        Ok((glob.compile_matcher(), !pattern.contains('/')))
+                    })
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Some(Config {
+            path: std::path::absolute(path)?,
+            options: raw.options,
+            overrides: raw.overrides,
+            bib: raw.bib,
+            lint: raw.lint,
+            count: raw.count,
+            matchers,
+        }))
+    }
+
+    fn empty(path: &Path) -> Config {
+        Config {
+            path: path.to_path_buf(),
+            options: ConfigOptions::default(),
+            overrides: vec![],
+            bib: BibOptions::default(),
+            lint: LintOptions::default(),
+            count: CountOptions::default(),
+            matchers: vec![],
+        }
+    }
+
+    /// Directory the override patterns are relative to.
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new(""))
+    }
+
+    /// Apply the options of this configuration for the file at `path` on top of `base`.
+    pub fn resolve(&self, path: impl AsRef<Path>, base: &FormatOptions) -> FormatOptions {
+        let mut opts = base.clone();
+        self.options.apply_to(&mut opts);
+
+        let relative = std::path::absolute(path.as_ref())
+            .ok()
+            .and_then(|path| path.strip_prefix(self.root()).ok().map(Path::to_path_buf));
+        let Some(relative) = relative else {
+            return opts;
+        };
+        let file_name = relative.file_name().map(PathBuf::from).unwrap_or_default();
+
+        for (o, matchers) in self.overrides.iter().zip(&self.matchers) {
+            let matched = matchers.iter().any(|(matcher, basename)| {
+                if *basename {
+                    matcher.is_match(&file_name)
+                } else {
+                    matcher.is_match(&relative)
+                }
+            });
+            if matched {
+                o.options.apply_to(&mut opts);
+            }
+        }
+        opts
+    }
+}
+
+/// Check that the keys of `table` are either [`OPTION_KEYS`] or `other`.
+fn check_keys(table: &toml::Value, other: &[&str]) -> std::result::Result<(), String> {
+    let Some(table) = table.as_table() else {
+        return Ok(());
+    };
+    match table.keys().find(|key| !OPTION_KEYS.contains(&key.as_str()) && !other.contains(&key.as_str())) {
+        Some(key) => {
+            let expected: Vec<String> = OPTION_KEYS.iter().chain(other).map(|key| format!("`{key}`")).collect();
+            Err(format!("unknown field `{key}`, expected one of {}", expected.join(", ")))
+        }
+        None => Ok(()),
+    }
+}
+
+/// Resolve the options for the file at `path` using the configuration discovered
+/// from its directory, falling back to `base` for unset options.
+pub fn resolve_options(path: impl AsRef<Path>, base: &FormatOptions) -> Result<FormatOptions> {
+    let path = path.as_ref();
+    Ok(match Config::discover(path)? {
+        Some(config) => config.resolve(path, base),
+        None => base.clone(),
+    })
+}
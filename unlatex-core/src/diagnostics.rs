@@ -0,0 +1,367 @@
+//! Problems found in malformed documents.
+//!
+//! The parser is lenient: the delimiters it cannot match are left in the tree as plain
+//! tokens, e.g. an unclosed `{` as a string. [`check`] reports them.
+//!
+//! ```
+//! use unlatex::diagnostics::Severity;
+//!
+//! let src = "\\begin{itemize}\n\\item {a\n";
+//! let (_ast, diagnostics) = unlatex::parse_with_diagnostics(src).unwrap();
+//! let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+//! assert_eq!(messages, ["environment `itemize` is never closed", "`{` is never closed"]);
+//! assert_eq!(diagnostics[1].severity, Severity::Error);
+//! assert_eq!(&src[diagnostics[1].span.clone()], "{");
+//!
+//! let report = unlatex::diagnostics::render(&diagnostics[1], "list.tex", src);
+//! assert_eq!(report, "error: `{` is never closed
+//!  --> list.tex:2:7
+//!   |
+//! 2 | \\item {a
+//!   |       ^
+//! ");
+//! ```
+
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::Node;
+use crate::macros::macro_call;
+use crate::macros::text;
+use crate::span::{node_range, LineIndex, OffsetMap};
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Likely to break the document.
+    Error,
+    /// Questionable style.
+    Warning,
+    /// A suggestion.
+    Info,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        })
+    }
+}
+
+/// A problem found while parsing a document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    /// Description of the problem.
+    pub message: String,
+    /// Severity of the problem.
+    pub severity: Severity,
+    /// Byte range of the offending source.
+    pub span: Range<usize>,
+    /// ID of the check which found the problem, such as a [lint rule](crate::lint::Rule).
+    pub code: Option<String>,
+}
+
+impl Diagnostic {
+    /// A diagnostic without code.
+    pub fn new(severity: Severity, message: impl Into<String>, span: Range<usize>) -> Diagnostic {
+        Diagnostic { message: message.into(), severity, span, code: None }
+    }
+
+    fn error(message: impl Into<String>, span: Range<usize>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message, span)
+    }
+}
+
+/// Render `diagnostic`, found in the source `src` of the file `name`, as a report
+/// quoting the offending lines:
+///
+/// ```text
+/// warning[ellipsis]: use `\dots` instead of `...`
+///  --> main.tex:3:5
+///   |
+/// 3 | Wait... see
+///   |     ^^^
+/// ```
+///
+/// Only the first and last lines of a span over several lines are quoted. Columns are
+/// counted in UTF-16 code units, like the other positions of this crate.
+pub fn render(diagnostic: &Diagnostic, name: &str, src: &str) -> String {
+    let index = LineIndex::new(src);
+    let start = diagnostic.span.start.min(src.len());
+    let end = diagnostic.span.end.clamp(start, src.len());
+    let (first, column) = index.line_col(start);
+    // a span ending with a line break does not reach the next line
+    let (last, _) = index.line_col(if end > start { end - 1 } else { end });
+
+    let mut out = String::new();
+    match &diagnostic.code {
+        Some(code) => out.push_str(&format!("{}[{code}]: {}\n", diagnostic.severity, diagnostic.message)),
+        None => out.push_str(&format!("{}: {}\n", diagnostic.severity, diagnostic.message)),
+    }
+    let width = (last + 1).to_string().len();
+    let gutter = " ".repeat(width);
+    out.push_str(&format!("{gutter}--> {name}:{}:{}\n", first + 1, column + 1));
+    out.push_str(&format!("{gutter} |\n"));
+
+    let line_text = |line: usize| {
+        let line_start = index.offset(line, 0);
+        let line_end = src[line_start..].find('\n').map_or(src.len(), |i| line_start + i);
+        (line_start, src[line_start..line_end].trim_end_matches('\r'))
+    };
+    let quote = |line: usize, from: usize, to: Option<usize>| {
+        let (line_start, text) = line_text(line);
+        let from = (from - line_start).min(text.len());
+        let to = to.map_or(text.len(), |to| (to - line_start).min(text.len())).max(from);
+        // keep tabs so that the carets line up with the quoted text
+        let indent: String = text[..from].chars().map(|c| if c == '\t' { '\t' } else { ' ' }).collect();
+        let carets = text[from..to].chars().count().max(1);
+        format!("{:>width$} | {text}\n{gutter} | {indent}{}\n", line + 1, "^".repeat(carets))
+    };
+    if first == last {
+        out.push_str(&quote(first, start, Some(end)));
+    } else {
+        out.push_str(&quote(first, start, None));
+        if last > first + 1 {
+            out.push_str(&format!("{gutter} ...\n"));
+        }
+        out.push_str(&quote(last, index.offset(last, 0), Some(end)));
+    }
+    out
+}
+
+/// Environments whose content is not parsed, so which swallow the rest of the document
+/// when they are not closed.
+const VERBATIM_ENVIRONMENTS: &[&str] = &[
+    "verbatim", "verbatim*", "Verbatim", "BVerbatim", "lstlisting", "minted", "comment", "filecontents",
+];
+
+/// Macros whose arguments may hold unbalanced delimiters, with the number of their
+/// leading mandatory arguments which are not checked, along with their optional ones:
+/// definitions, whose bodies are only balanced in braces, URLs and column specifications
+/// such as `>{$}c<{$}`.
+const UNCHECKED_ARGUMENTS: &[(&str, usize)] = &[
+    ("newcommand", 2), ("renewcommand", 2), ("providecommand", 2), ("DeclareRobustCommand", 2),
+    ("newenvironment", 3), ("renewenvironment", 3),
+    ("NewDocumentCommand", 3), ("RenewDocumentCommand", 3), ("ProvideDocumentCommand", 3), ("DeclareDocumentCommand", 3),
+    ("NewDocumentEnvironment", 4), ("RenewDocumentEnvironment", 4),
+    ("url", 1), ("href", 2), ("path", 1), ("nolinkurl", 1),
+    ("newcolumntype", 2), ("NewColumnType", 2), ("multicolumn", 2),
+];
+
+/// Tabular-like environments, with the number of their mandatory arguments which are not
+/// checked as they end with the column specification.
+const TABULAR_ENVIRONMENTS: &[(&str, usize)] = &[
+    ("tabular", 1), ("tabular*", 2), ("array", 1), ("tabularx", 2), ("tabulary", 2),
+    ("longtable", 1), ("supertabular", 1), ("xtabular", 1), ("NiceTabular", 1), ("NiceArray", 1),
+];
+
+/// Number of leading mandatory arguments of `node` which are not checked.
+fn unchecked_arguments(node: &Node) -> usize {
+    let (name, table) = match node {
+        Node::Macro { content, .. } => (content, UNCHECKED_ARGUMENTS),
+        Node::Environment { env, .. } | Node::MathEnv { env, .. } => (env, TABULAR_ENVIRONMENTS),
+        _ => return 0,
+    };
+    table.iter().find(|(n, _)| n == name).map_or(0, |&(_, count)| count)
+}
+
+/// Indices of the `siblings` making up primitive definitions, whose arguments the parser
+/// does not attach: `\def\name#1{body}` and `\let\name\other`, and the arguments of
+/// [`UNCHECKED_ARGUMENTS`] which it did not attach.
+fn definitions(siblings: &[Node]) -> Vec<usize> {
+    let mut indices = vec![];
+    let mut i = 0;
+    while i < siblings.len() {
+        match &siblings[i] {
+            Node::Macro { content, .. } if matches!(content.as_str(), "def" | "gdef" | "edef" | "xdef") => {
+                // the name and parameters, up to the body
+                while i + 1 < siblings.len() {
+                    i += 1;
+                    indices.push(i);
+                    if matches!(siblings[i], Node::Group { .. }) {
+                        break;
+                    }
+                }
+            }
+            node @ Node::Macro { .. } if unchecked_arguments(node) > 0 => {
+                if let Some(call) = macro_call(siblings, i, unchecked_arguments(node)) {
+                    indices.extend(i + 1..=i + call.consumed);
+                    i += call.consumed;
+                }
+            }
+            Node::Macro { content, .. } if content == "let" => {
+                let mut tokens = 0;
+                while tokens < 2 && i + 1 < siblings.len() {
+                    i += 1;
+                    indices.push(i);
+                    if !is_equals(&siblings[i]) {
+                        tokens += 1;
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    indices
+}
+
+/// Whether `node` may separate the names of `\let`.
+fn is_equals(node: &Node) -> bool {
+    match node {
+        Node::WhiteSpace { .. } => true,
+        Node::String { content, .. } => content == "=",
+        _ => false,
+    }
+}
+
+/// Indices of the first `count` groups of `content`, with the optional arguments before
+/// them: the arguments of an environment the parser did not attach.
+fn leading_groups(content: &[Node], count: usize) -> Vec<usize> {
+    let mut indices = vec![];
+    let mut optional = false;
+    for (i, node) in content.iter().enumerate() {
+        if indices.len() == count {
+            break;
+        }
+        match node {
+            Node::String { content, .. } if content == "[" => optional = true,
+            Node::String { content, .. } if content == "]" => optional = false,
+            Node::Group { .. } if !optional => indices.push(i),
+            Node::WhiteSpace { .. } | Node::Comment { .. } => {}
+            _ if optional => {}
+            _ => break,
+        }
+    }
+    indices
+}
+
+/// Call `f` on every list of sibling nodes under `node`, with the indices of the nodes
+/// not to check, skipping the contents of definitions, URLs and column specifications.
+fn visit_checked<'a>(node: &'a Node, f: &mut impl FnMut(&'a [Node], &[usize])) {
+    let unchecked = unchecked_arguments(node);
+    let attached = node.args().iter().filter(|arg| matches!(arg, Node::Argument { open_mark, .. } if open_mark == "{")).count();
+    let content = node.content();
+    let mut skipped = definitions(content);
+    if matches!(node, Node::Environment { .. } | Node::MathEnv { .. }) {
+        skipped.extend(leading_groups(content, unchecked.saturating_sub(attached)));
+    }
+    if !content.is_empty() {
+        f(content, &skipped);
+    }
+    for (i, child) in content.iter().enumerate() {
+        if !skipped.contains(&i) {
+            visit_checked(child, f);
+        }
+    }
+    let mut mandatory = 0;
+    for arg in node.args() {
+        if let Node::Argument { open_mark, .. } = arg {
+            match open_mark.as_str() {
+                "[" if unchecked > 0 => continue,
+                "{" => {
+                    mandatory += 1;
+                    if mandatory <= unchecked {
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+        }
+        visit_checked(arg, f);
+    }
+}
+
+/// Find the delimiters left unmatched in `root`, parsed from `src`, sorted by position.
+///
+/// The bodies of macro definitions, URLs and column specifications are not checked.
+pub fn check(root: &Node, src: &str) -> Vec<Diagnostic> {
+    let map = OffsetMap::new(src);
+    let range = |node: &Node| node_range(node, src, &map);
+    let mut diagnostics = vec![];
+
+    visit_checked(root, &mut |siblings, skipped| {
+        // `\begin{name}` not matched by the parser, with the range of the name
+        let mut open: Vec<(String, Range<usize>)> = vec![];
+        let mut i = 0;
+        while i < siblings.len() {
+            let node = &siblings[i];
+            let Some(span) = range(node).filter(|_| !skipped.contains(&i)) else {
+                i += 1;
+                continue;
+            };
+            match node {
+                Node::String { content, .. } if content == "{" => {
+                    diagnostics.push(Diagnostic::error("`{` is never closed", span));
+                }
+                Node::String { content, .. } if content == "}" => {
+                    diagnostics.push(Diagnostic::error("`}` closes no group", span));
+                }
+                Node::String { content, .. } if content == "$" => {
+                    let next = siblings.get(i + 1).and_then(|next| Some((next, range(next)?)));
+                    match next {
+                        Some((Node::String { content, .. }, next)) if content == "$" && next.start == span.end => {
+                            diagnostics.push(Diagnostic::error("display math `$$` is never closed", span.start..next.end));
+                            i += 1;
+                        }
+                        _ => diagnostics.push(Diagnostic::error("inline math `$` is never closed", span)),
+                    }
+                }
+                Node::Macro { content, .. } => match content.as_str() {
+                    "[" => diagnostics.push(Diagnostic::error("display math `\\[` is never closed", span)),
+                    "(" => diagnostics.push(Diagnostic::error("inline math `\\(` is never closed", span)),
+                    "]" => diagnostics.push(Diagnostic::error("`\\]` closes no display math", span)),
+                    ")" => diagnostics.push(Diagnostic::error("`\\)` closes no inline math", span)),
+                    "verb" => diagnostics.push(Diagnostic::error("`\\verb` is never closed", span)),
+                    "begin" | "end" => {
+                        let name = match siblings.get(i + 1) {
+                            Some(group @ Node::Group { content, .. }) => {
+                                i += 1;
+                                Some((text(content), range(group).map_or(span.end, |r| r.end)))
+                            }
+                            _ => None,
+                        };
+                        let Some((name, end)) = name else {
+                            diagnostics.push(Diagnostic::error(format!("`\\{content}` without an environment name"), span));
+                            i += 1;
+                            continue;
+                        };
+                        if content == "begin" {
+                            open.push((name, span.start..end));
+                        } else {
+                            match open.pop() {
+                                Some((expected, _)) => diagnostics.push(Diagnostic::error(
+                                    format!("`\\end{{{name}}}` does not match `\\begin{{{expected}}}`"),
+                                    span.start..end,
+                                )),
+                                None => diagnostics.push(Diagnostic::error(
+                                    format!("`\\end{{{name}}}` closes no environment"),
+                                    span.start..end,
+                                )),
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                _ => {}
+            }
+            i += 1;
+        }
+        for (name, span) in open {
+            let message = if VERBATIM_ENVIRONMENTS.contains(&name.as_str()) {
+                format!("verbatim environment `{name}` is never closed")
+            } else {
+                format!("environment `{name}` is never closed")
+            };
+            diagnostics.push(Diagnostic::error(message, span));
+        }
+    });
+    diagnostics.sort_by_key(|d| d.span.start);
+    diagnostics
+}
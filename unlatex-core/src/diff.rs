@@ -0,0 +1,521 @@
+//! Structural differences between revisions of a document.
+//!
+//! [`diff`] matches the trees of two revisions and lists the [edits](Edit) turning the
+//! first into the second: nodes inserted, deleted, updated in place or moved. Layout is
+//! ignored as by [`compare`](crate::compare), and so are comments.
+//!
+//! Matching descends into the document, groups, environments and items of lists, so an
+//! edited paragraph of a section is an edit of that paragraph. Macros, math and
+//! environments whose arguments changed are updated as a whole, e.g. an edited equation or
+//! citation. Text between matched nodes is matched word by word, consecutive changed
+//! words making one edit.
+//!
+//! ```
+//! use unlatex::diff::{diff, Edit};
+//!
+//! let old = "\\section{Intro}\nAs shown \\cite{knuth}, $a = b$.\n";
+//! let new = "\\section{Introduction}\nAs shown \\cite{knuth}, $a = c$.\n\n\\section{More}\n";
+//! let edits = diff(old, new).unwrap();
+//! let summary: Vec<_> = edits.iter().map(|edit| (edit.kind(), &new[edit.new_range().unwrap()])).collect();
+//! assert_eq!(summary, [("update", "\\section{Introduction}"), ("update", "$a = c$"), ("insert", "\\section{More}")]);
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+use serde::Serialize;
+
+use crate::ast::Node;
+use crate::compare::{describe, meaningful, same_node, Source};
+use crate::span::LineIndex;
+use crate::{parse, Result};
+
+/// Commands ending rows of tables or drawing rules between them.
+pub(crate) const ALIGNMENT: &[&str] = &["\\", "tabularnewline", "cr", "hline", "cline", "toprule", "midrule", "bottomrule", "cmidrule"];
+
+/// Largest table [`lcs`] fills, beyond which it only matches common ends.
+const LCS_CELLS: usize = 1 << 24;
+
+/// Score of pairing unchanged nodes when aligning revisions, above that of nodes of the
+/// same kind so that an unchanged node is preferred, but not over two nodes of the same
+/// kind, which is more likely a moved node.
+const UNCHANGED_SCORE: u32 = 3;
+
+/// Score of pairing nodes of the same kind when aligning revisions.
+const SAME_KIND_SCORE: u32 = 2;
+
+/// Lines of a node quoted by [`report`] before the rest is elided.
+const QUOTED_LINES: usize = 4;
+
+/// An edit turning a revision of a document into the next, with byte ranges in both.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum Edit {
+    /// A node only in the new revision.
+    Insert {
+        /// Description of the node, such as `` `\section` `` or `text`.
+        node: String,
+        /// Byte range of the node in the new revision.
+        new: Range<usize>,
+    },
+    /// A node only in the old revision.
+    Delete {
+        /// Description of the node.
+        node: String,
+        /// Byte range of the node in the old revision.
+        old: Range<usize>,
+    },
+    /// A node in both revisions at the same place, with a different content.
+    Update {
+        /// Description of the node in the new revision.
+        node: String,
+        /// Byte range of the node in the old revision.
+        old: Range<usize>,
+        /// Byte range of the node in the new revision.
+        new: Range<usize>,
+    },
+    /// A node in both revisions unchanged, at different places.
+    Move {
+        /// Description of the node.
+        node: String,
+        /// Byte range of the node in the old revision.
+        old: Range<usize>,
+        /// Byte range of the node in the new revision.
+        new: Range<usize>,
+    },
+}
+
+impl Edit {
+    /// Name of the operation, as in the JSON output.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Edit::Insert { .. } => "insert",
+            Edit::Delete { .. } => "delete",
+            Edit::Update { .. } => "update",
+            Edit::Move { .. } => "move",
+        }
+    }
+
+    /// Description of the edited node, such as `` `\section` `` or `text`.
+    pub fn node(&self) -> &str {
+        match self {
+            Edit::Insert { node, .. } | Edit::Delete { node, .. } | Edit::Update { node, .. } | Edit::Move { node, .. } => node,
+        }
+    }
+
+    /// Byte range of the node in the old revision.
+    pub fn old_range(&self) -> Option<Range<usize>> {
+        match self {
+            Edit::Insert { .. } => None,
+            Edit::Delete { old, .. } | Edit::Update { old, .. } | Edit::Move { old, .. } => Some(old.clone()),
+        }
+    }
+
+    /// Byte range of the node in the new revision.
+    pub fn new_range(&self) -> Option<Range<usize>> {
+        match self {
+            Edit::Delete { .. } => None,
+            Edit::Insert { new, .. } | Edit::Update { new, .. } | Edit::Move { new, .. } => Some(new.clone()),
+        }
+    }
+}
+
+/// The edits turning the document `old` into `new`, in document order.
+pub fn diff(old: &str, new: &str) -> Result<Vec<Edit>> {
+    let (old, new) = (Source::new(old), Source::new(new));
+    let (old_root, new_root) = (parse(&old.text)?, parse(&new.text)?);
+    Ok(changes(&old, &new, &old_root, &new_root, |_| false).into_iter().map(|change| change.edit).collect())
+}
+
+/// Render `edits` between the revisions `old` and `new` as a report quoting the edited
+/// nodes:
+///
+/// ```text
+/// update `\section` at line 1
+///   - \section{Intro}
+///   + \section{Introduction}
+/// ```
+pub fn report(edits: &[Edit], old: &str, new: &str) -> String {
+    let (old_index, new_index) = (LineIndex::new(old), LineIndex::new(new));
+    let line = |index: &LineIndex, range: &Range<usize>| index.line_col(range.start).0 + 1;
+    let quote = |out: &mut String, sign: char, text: &str| {
+        let lines: Vec<&str> = text.trim_end().split('\n').map(|line| line.trim_end_matches('\r')).collect();
+        for line in lines.iter().take(QUOTED_LINES) {
+            out.push_str(&format!("  {sign} {line}\n"));
+        }
+        if lines.len() > QUOTED_LINES {
+            out.push_str(&format!("  {sign} ...\n"));
+        }
+    };
+    let mut out = String::new();
+    for edit in edits {
+        match edit {
+            Edit::Insert { node, new: range } => {
+                out.push_str(&format!("insert {node} at line {}\n", line(&new_index, range)));
+                quote(&mut out, '+', &new[range.clone()]);
+            }
+            Edit::Delete { node, old: range } => {
+                out.push_str(&format!("delete {node} at line {}\n", line(&old_index, range)));
+                quote(&mut out, '-', &old[range.clone()]);
+            }
+            Edit::Update { node, old: old_range, new: new_range } => {
+                let (from, to) = (line(&old_index, old_range), line(&new_index, new_range));
+                match from == to {
+                    true => out.push_str(&format!("update {node} at line {from}\n")),
+                    false => out.push_str(&format!("update {node} at line {from}, now line {to}\n")),
+                }
+                quote(&mut out, '-', &old[old_range.clone()]);
+                quote(&mut out, '+', &new[new_range.clone()]);
+            }
+            Edit::Move { node, old: old_range, new: new_range } => {
+                let (from, to) = (line(&old_index, old_range), line(&new_index, new_range));
+                out.push_str(&format!("move {node} from line {from} to line {to}\n"));
+                quote(&mut out, ' ', &new[new_range.clone()]);
+            }
+        }
+    }
+    out
+}
+
+/// Pairs of indices of a longest common subsequence of `a` and `b`, whose items are
+/// equal according to `eq`.
+pub(crate) fn lcs<A, B>(a: &[A], b: &[B], eq: impl Fn(&A, &B) -> bool) -> Vec<(usize, usize)> {
+    align(a, b, 1, |x, y| eq(x, y) as u32)
+}
+
+/// Pairs of indices of items of `a` and `b`, in order, with the largest total `score`,
+/// items scoring 0 never being paired and `best` being the largest score.
+pub(crate) fn align<A, B>(a: &[A], b: &[B], best: u32, score: impl Fn(&A, &B) -> u32) -> Vec<(usize, usize)> {
+    // pairing common ends of the best score is always part of an optimal alignment
+    let prefix = a.iter().zip(b).take_while(|(x, y)| score(x, y) == best).count();
+    let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(x, y)| score(x, y) == best).count();
+    let (middle_a, middle_b) = (&a[prefix..a.len() - suffix], &b[prefix..b.len() - suffix]);
+    let mut pairs: Vec<(usize, usize)> = (0..prefix).map(|i| (i, i)).collect();
+    if middle_a.len().saturating_mul(middle_b.len()) > LCS_CELLS {
+        // too large to match, the middles are taken as entirely different
+        pairs.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+        return pairs;
+    }
+
+    let mut total = vec![vec![0u32; middle_b.len() + 1]; middle_a.len() + 1];
+    for i in (0..middle_a.len()).rev() {
+        for j in (0..middle_b.len()).rev() {
+            let paired = match score(&middle_a[i], &middle_b[j]) {
+                0 => 0,
+                score => score + total[i + 1][j + 1],
+            };
+            total[i][j] = paired.max(total[i + 1][j]).max(total[i][j + 1]);
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < middle_a.len() && j < middle_b.len() {
+        let score = score(&middle_a[i], &middle_b[j]);
+        if score > 0 && total[i][j] == score + total[i + 1][j + 1] {
+            pairs.push((prefix + i, prefix + j));
+            (i, j) = (i + 1, j + 1);
+        } else if total[i + 1][j] >= total[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs.extend((0..suffix).map(|k| (a.len() - suffix + k, b.len() - suffix + k)));
+    pairs
+}
+
+/// Hash of a node and its children, equal for nodes [`compare`](crate::compare) finds equal.
+pub(crate) fn fingerprint(node: &Node) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_node(node, &mut hasher);
+    hasher.finish()
+}
+
+fn hash_node(node: &Node, hasher: &mut DefaultHasher) {
+    std::mem::discriminant(node).hash(hasher);
+    match node {
+        Node::String { content, .. } => content.hash(hasher),
+        Node::Macro { content, escape_token, .. } => (content, escape_token).hash(hasher),
+        Node::Environment { env, .. } | Node::MathEnv { env, .. } => env.hash(hasher),
+        Node::VerbatimEnvironment { env, content, .. } => (env, content).hash(hasher),
+        Node::Verb { env, escape, content, .. } => (env, escape, content).hash(hasher),
+        Node::Argument { open_mark, close_mark, .. } => (open_mark, close_mark).hash(hasher),
+        _ => {}
+    }
+    for children in [node.args(), node.content()] {
+        let children = meaningful(children);
+        children.len().hash(hasher);
+        for child in children {
+            hash_node(child, hasher);
+        }
+    }
+}
+
+/// Whether `node` counts as running text, edited by runs rather than node by node.
+pub(crate) fn is_text(node: &Node) -> bool {
+    matches!(node, Node::String { .. } | Node::Parbreak { .. })
+}
+
+/// Whether the content of `old` and `new`, nodes of the same kind, is matched node by
+/// node: that of macros being their arguments which changed, when those are text taken
+/// from after the macro like that of `\item` rather than delimited like `{...}`.
+fn is_container(old: &Node, new: &Node) -> bool {
+    let same_args = || {
+        let (old_args, new_args) = (meaningful(old.args()), meaningful(new.args()));
+        old_args.len() == new_args.len() && old_args.iter().zip(&new_args).all(|(a, b)| fingerprint(a) == fingerprint(b))
+    };
+    match old {
+        Node::Root { .. } | Node::Group { .. } => true,
+        Node::Environment { .. } => same_args(),
+        Node::Macro { .. } => {
+            old.args().len() == new.args().len()
+                && old.args().iter().zip(new.args()).all(|(a, b)| fingerprint(a) == fingerprint(b) || (is_undelimited(a) && is_undelimited(b)))
+        }
+        _ => false,
+    }
+}
+
+/// An edit with where deleted nodes were in the new revision, for
+/// [marking up](crate::markup) the changes.
+pub(crate) struct Change {
+    pub(crate) edit: Edit,
+    /// Whether the edited nodes are [text](is_text).
+    pub(crate) text: bool,
+    /// Byte offset in the new revision of a deleted node: after the node before it, or
+    /// before the node after it.
+    pub(crate) at: Place,
+    /// Fingerprint of the inserted or deleted node, to find moves.
+    print: Option<u64>,
+}
+
+/// A byte offset in a revision, with the side of the node it is next to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Place {
+    pub(crate) offset: usize,
+    /// Whether the offset follows a node rather than precedes one.
+    pub(crate) after: bool,
+}
+
+/// The changes turning the tree `old_node` of the document `old` into `new_node` of
+/// `new`, in document order.
+pub(crate) fn changes(old: &Source, new: &Source, old_node: &Node, new_node: &Node, opaque: fn(&Node) -> bool) -> Vec<Change> {
+    let mut differ = Differ { old, new, opaque, changes: vec![] };
+    differ.node(old_node, new_node);
+    differ.finish()
+}
+
+/// Whether `arg` is an argument of a macro without delimiters, text taken from after the
+/// macro like that of `\item`, whose nodes have positions.
+fn is_undelimited(arg: &Node) -> bool {
+    let positioned = |node: &&Node| node.position().is_some_and(|p| p.end.offset > p.start.offset);
+    matches!(arg, Node::Argument { open_mark, .. } if open_mark.is_empty()) && meaningful(arg.content()).iter().all(positioned)
+}
+
+/// Whether `node` ends a row of a table or is a rule between rows, next to which nothing
+/// can be put.
+pub(crate) fn is_alignment(node: &Node) -> bool {
+    matches!(node, Node::Macro { content, .. } if ALIGNMENT.contains(&content.as_str()))
+}
+
+/// Byte range of `node` in `source`, including the text macros like `\item` take as
+/// arguments.
+fn span(source: &Source, node: &Node) -> Range<usize> {
+    let mut range = source.range(Some(node), 0);
+    let taken = node.args().iter().filter(|arg| is_undelimited(arg)).flat_map(|arg| meaningful(arg.content())).last();
+    if let (Node::Macro { .. }, Some(last)) = (node, taken) {
+        range.end = range.end.max(source.range(Some(last), 0).end);
+    }
+    range
+}
+
+/// Collects the changes between two trees.
+struct Differ<'s> {
+    old: &'s Source,
+    new: &'s Source,
+    /// Whether the content of a node is left out of matching, the node being updated as a whole.
+    opaque: fn(&Node) -> bool,
+    changes: Vec<Change>,
+}
+
+impl Differ<'_> {
+    fn node(&mut self, old: &Node, new: &Node) {
+        if fingerprint(old) == fingerprint(new) {
+            return;
+        }
+        if !is_container(old, new) || (self.opaque)(new) {
+            let node = describe(Some(new));
+            let (old, new) = (span(self.old, old), span(self.new, new));
+            self.push(Edit::Update { node, old, new }, false, None, None);
+        } else if let Node::Macro { .. } = old {
+            let end = span(self.new, new).end;
+            for (old, new) in old.args().iter().zip(new.args()) {
+                if fingerprint(old) != fingerprint(new) {
+                    self.nodes(&meaningful(old.content()), &meaningful(new.content()), end);
+                }
+            }
+        } else {
+            let end = self.content_end(new);
+            self.nodes(&meaningful(old.content()), &meaningful(new.content()), end);
+        }
+    }
+
+    /// Match the `old` nodes with the `new` ones: nodes other than text are paired,
+    /// preferably with unchanged ones, then with ones of the same kind, and the text between
+    /// those is matched word by word. `end` is the offset where the nodes end in the new
+    /// revision.
+    fn nodes(&mut self, old: &[&Node], new: &[&Node], end: usize) {
+        let (old_structural, new_structural) = (structural(old), structural(new));
+        let old_prints: Vec<(u64, &Node)> = old_structural.iter().map(|&i| (fingerprint(old[i]), old[i])).collect();
+        let new_prints: Vec<(u64, &Node)> = new_structural.iter().map(|&i| (fingerprint(new[i]), new[i])).collect();
+        let score = |(a_print, a): &(u64, &Node), (b_print, b): &(u64, &Node)| match (a_print == b_print, same_node(a, b)) {
+            (true, _) => UNCHANGED_SCORE,
+            (false, true) => SAME_KIND_SCORE,
+            (false, false) => 0,
+        };
+        let paired = align(&old_prints, &new_prints, UNCHANGED_SCORE, score);
+        let (mut i, mut j) = (0, 0);
+        for (next_i, next_j) in paired.into_iter().map(|(a, b)| (old_structural[a], new_structural[b])).chain([(old.len(), new.len())]) {
+            let at = self.place(new, j, Place { offset: end, after: false });
+            self.unpaired(&old[i..next_i], &new[j..next_j], at);
+            if next_i < old.len() {
+                self.node(old[next_i], new[next_j]);
+            }
+            (i, j) = (next_i + 1, next_j + 1);
+        }
+    }
+
+    /// Changes for nodes of `old` and `new` with no counterpart, matching their text word
+    /// by word: the nodes between common words are updated if they are text, and deleted
+    /// and inserted otherwise.
+    fn unpaired(&mut self, old: &[&Node], new: &[&Node], at: Place) {
+        let (old, new) = (trim_breaks(old), trim_breaks(new));
+        let common = lcs(old, new, |a, b| is_text(a) && same_node(a, b));
+        let (mut i, mut j) = (0, 0);
+        for (next_i, next_j) in common.into_iter().chain([(old.len(), new.len())]) {
+            let (old_part, new_part) = (&old[i..next_i], &new[j..next_j]);
+            let place = self.place(new, j, at);
+            let all_text = |part: &[&Node]| !part.is_empty() && part.iter().all(|node| is_text(node));
+            if all_text(old_part) && all_text(new_part) {
+                let (old, new) = (self.run(self.old, old_part), self.run(self.new, new_part));
+                self.push(Edit::Update { node: describe_run(new_part), old, new }, true, None, None);
+            } else {
+                for piece in pieces(old_part) {
+                    let (node, old) = (describe_run(piece), self.run(self.old, piece));
+                    let print = (!is_text(piece[0])).then(|| fingerprint(piece[0]));
+                    self.push(Edit::Delete { node, old }, print.is_none(), Some(place), print);
+                }
+                for piece in pieces(new_part) {
+                    let (node, new) = (describe_run(piece), self.run(self.new, piece));
+                    let print = (!is_text(piece[0])).then(|| fingerprint(piece[0]));
+                    self.push(Edit::Insert { node, new }, print.is_none(), None, print);
+                }
+            }
+            (i, j) = (next_i + 1, next_j + 1);
+        }
+    }
+
+    fn push(&mut self, edit: Edit, text: bool, at: Option<Place>, print: Option<u64>) {
+        let at = at.unwrap_or_else(|| {
+            let start = edit.new_range().map_or(0, |range| range.start);
+            Place { offset: start, after: false }
+        });
+        self.changes.push(Change { edit, text, at, print });
+    }
+
+    /// Where nodes deleted before `new[j]` go: after `new[j - 1]`, before `new[j]`, or `at`
+    /// if there are neither, rows of tables being kept together with the rules after them.
+    fn place(&self, new: &[&Node], j: usize, at: Place) -> Place {
+        let next = (j..new.len()).find(|&k| !is_alignment(new[k]));
+        match (j.checked_sub(1).map(|previous| new[previous]), next) {
+            (Some(previous), _) if !is_alignment(previous) => Place { offset: span(self.new, previous).end, after: true },
+            (_, Some(next)) => Place { offset: span(self.new, new[next]).start, after: false },
+            (Some(_), None) => Place { offset: span(self.new, new[new.len() - 1]).end, after: true },
+            (None, None) => at,
+        }
+    }
+
+    /// Byte offset in the new revision of the end of the content of `node`, before the
+    /// end of its environment or group.
+    fn content_end(&self, node: &Node) -> usize {
+        let end = span(self.new, node).end;
+        match node {
+            Node::Environment { env, .. } => end.saturating_sub(format!("\\end{{{env}}}").len()),
+            Node::Group { .. } => end.saturating_sub(1),
+            _ => end,
+        }
+    }
+
+    /// Byte range of a run of nodes in `source`.
+    fn run(&self, source: &Source, run: &[&Node]) -> Range<usize> {
+        let (Some(first), Some(last)) = (run.first(), run.last()) else {
+            return 0..0;
+        };
+        let (first, last) = (span(source, first), span(source, last));
+        first.start..last.end.max(first.start)
+    }
+
+    /// Pair deleted and inserted nodes left unchanged as moves.
+    fn finish(self) -> Vec<Change> {
+        let mut changes: Vec<Option<Change>> = self.changes.into_iter().map(Some).collect();
+        for i in 0..changes.len() {
+            let Some(Change { edit: Edit::Delete { .. }, print: Some(print), .. }) = changes[i] else { continue };
+            let inserted = changes.iter().position(|change| {
+                matches!(change, Some(Change { edit: Edit::Insert { .. }, print: other, .. }) if *other == Some(print))
+            });
+            if let Some(j) = inserted {
+                let Some(Change { edit: Edit::Insert { new, .. }, .. }) = changes[j].take() else { unreachable!() };
+                let Some(Change { edit: Edit::Delete { node, old }, at, .. }) = changes[i].take() else { unreachable!() };
+                changes[i] = Some(Change { edit: Edit::Move { node, old, new }, text: false, at, print: None });
+            }
+        }
+        changes.into_iter().flatten().collect()
+    }
+}
+
+/// Indices of the nodes of `nodes` which are not [text](is_text).
+fn structural(nodes: &[&Node]) -> Vec<usize> {
+    (0..nodes.len()).filter(|&i| !is_text(nodes[i])).collect()
+}
+
+/// `nodes` without the paragraph breaks at either end, which go along with the nodes
+/// next to them.
+fn trim_breaks<'a, 'n>(mut nodes: &'a [&'n Node]) -> &'a [&'n Node] {
+    while let [Node::Parbreak { .. }, rest @ ..] = nodes {
+        nodes = rest;
+    }
+    while let [rest @ .., Node::Parbreak { .. }] = nodes {
+        nodes = rest;
+    }
+    nodes
+}
+
+/// `nodes` split into the maximal runs of [text](is_text) and the other nodes, one by one,
+/// the paragraph breaks next to other nodes going along with them.
+fn pieces<'a, 'n>(nodes: &'a [&'n Node]) -> Vec<&'a [&'n Node]> {
+    if nodes.iter().all(|node| is_text(node)) {
+        return match nodes.is_empty() {
+            true => vec![],
+            false => vec![nodes],
+        };
+    }
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    for (i, node) in nodes.iter().enumerate() {
+        if !is_text(node) {
+            pieces.push(trim_breaks(&nodes[start..i]));
+            pieces.push(&nodes[i..i + 1]);
+            start = i + 1;
+        }
+    }
+    pieces.push(trim_breaks(&nodes[start..]));
+    pieces.retain(|piece| !piece.is_empty());
+    pieces
+}
+
+fn describe_run(run: &[&Node]) -> String {
+    match run {
+        [node] if !is_text(node) => describe(Some(node)),
+        _ if run.iter().all(|node| matches!(node, Node::Parbreak { .. })) => describe(run.first().copied()),
+        _ => "text".to_string(),
+    }
+}
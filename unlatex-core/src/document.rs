@@ -0,0 +1,342 @@
+//! Documents kept parsed while they are edited.
+//!
+//! [`Document::apply_edit`] reparses only the paragraphs around an edit, delimited by
+//! paragraph breaks at the top level of the document or within the environment around
+//! the edit, such as `document`, and splices them into the tree.
+//! Edits whose effect may reach further, e.g. in documents with unbalanced braces or
+//! `\makeatletter`, reparse the whole document. Either way the tree is the one
+//! [`parse`](crate::parse) gives for the new text.
+//!
+//! ```
+//! use unlatex::document::Document;
+//!
+//! let mut doc = Document::new("First paragraph.\n\nSecond \\emph{one}.\n\nThird.").unwrap();
+//! let reparsed = doc.apply_edit(19..24, "ome").unwrap();
+//! assert_eq!(doc.text(), "First paragraph.\n\nSome \\emph{one}.\n\nThird.");
+//! // the second paragraph along with the paragraph breaks around it
+//! assert_eq!(&doc.text()[reparsed], "\n\nSome \\emph{one}.\n\n");
+//! ```
+
+use std::ops::Range;
+
+use crate::ast::Node;
+use crate::info::{Position, PositionInfo};
+use crate::span::OffsetMap;
+use crate::{parse, Error, Result};
+
+/// A LaTeX document along with its syntax tree.
+#[derive(Debug, Clone)]
+pub struct Document {
+    text: String,
+    root: Node,
+    /// Whether an edit may change the parse beyond the paragraphs around it.
+    fragile: bool,
+}
+
+impl Document {
+    /// Parse `text`.
+    pub fn new(text: impl Into<String>) -> Result<Document> {
+        let text = text.into();
+        let root = parse(&text)?;
+        let fragile = is_fragile(&text, &root);
+        Ok(Document { text, root, fragile })
+    }
+
+    /// Text of the document.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Syntax tree of the document.
+    pub fn root(&self) -> &Node {
+        &self.root
+    }
+
+    /// Replace the byte `range` of the text with `text`, returning the byte range of the
+    /// new text which was parsed again.
+    ///
+    /// The document is left unchanged if the range is invalid or the parser fails.
+    pub fn apply_edit(&mut self, range: Range<usize>, text: &str) -> Result<Range<usize>> {
+        let old = &self.text;
+        if range.start > range.end || !old.is_char_boundary(range.start) || !old.is_char_boundary(range.end) {
+            return Err(Error::InvalidRange { start: range.start, end: range.end });
+        }
+        let mut new = String::with_capacity(old.len() - range.len() + text.len());
+        new.push_str(&old[..range.start]);
+        new.push_str(text);
+        new.push_str(&old[range.end..]);
+
+        let edit = Edit {
+            start: utf16_len(&old[..range.start]),
+            old_end: utf16_len(&old[..range.end]),
+            new_end: utf16_len(&old[..range.start]) + utf16_len(text),
+            lines: line_breaks(text) as isize - line_breaks(&old[range.clone()]) as isize,
+        };
+        let spliced = match self.fragile {
+            true => None,
+            false => self.splice(&new, &edit)?,
+        };
+        let reparsed = match spliced {
+            Some(reparsed) => reparsed,
+            None => {
+                self.root = parse(&new)?;
+                self.fragile = is_fragile(&new, &self.root);
+                0..new.len()
+            }
+        };
+        self.text = new;
+        Ok(reparsed)
+    }
+
+    /// Update the tree for the `new` text, parsing again the paragraphs around the `edit`;
+    /// `None` if the whole document has to be parsed again.
+    fn splice(&mut self, new: &str, edit: &Edit) -> Result<Option<Range<usize>>> {
+        let Node::Root { content, position, .. } = &mut self.root else {
+            return Ok(None);
+        };
+        let reparsed = splice_content(content, new, edit, true)?;
+        if reparsed.is_some() {
+            position.end = end_position(new);
+        }
+        Ok(reparsed)
+    }
+}
+
+/// Update the `content` of the root, or of an environment if not at the `top`, for the
+/// `new` text, parsing again the paragraphs around the `edit` in the innermost environment
+/// where it is between paragraph breaks; the byte range of the new text parsed again.
+fn splice_content(content: &mut Vec<Node>, new: &str, edit: &Edit, top: bool) -> Result<Option<Range<usize>>> {
+    let around = content.iter().position(|node| {
+        is_splittable(node) && node.position().is_some_and(|position| position.start.offset < edit.start && position.end.offset > edit.old_end)
+    });
+    if let Some(i) = around {
+        let Node::Environment { content: inner, position, .. } = &mut content[i] else {
+            unreachable!("only environments are split");
+        };
+        if let Some(reparsed) = splice_content(inner, new, edit, false)? {
+            position.end.offset = edit.shift(position.end.offset);
+            position.end.line = (position.end.line as isize + edit.lines) as usize;
+            shift_following(&mut content[i + 1..], edit);
+            return Ok(Some(reparsed));
+        }
+    }
+
+    let parbreak = |node: &Node| match node {
+        Node::Parbreak { position, .. } if position.start.line > 0 => Some(position.clone()),
+        _ => None,
+    };
+    // the closest paragraph breaks the edit and what parsing looks ahead at leave alone
+    let before = content.iter().enumerate().rev().find_map(|(i, node)| {
+        parbreak(node).filter(|position| position.end.offset < edit.start).map(|position| (i, position))
+    });
+    let after = content.iter().enumerate().find_map(|(i, node)| {
+        parbreak(node).filter(|position| position.start.offset > edit.old_end).map(|position| (i, position))
+    });
+    // the content of an environment is only parsed again between paragraph breaks
+    if before.is_none() && after.is_none() || !top && (before.is_none() || after.is_none()) {
+        return Ok(None);
+    }
+    let start = before.as_ref().map_or(0, |(_, position)| position.start.offset);
+    let end = after.as_ref().map_or(utf16_len(new), |(_, position)| edit.shift(position.end.offset));
+    let offsets = OffsetMap::new(new);
+    let (start_byte, end_byte) = (offsets.to_byte(start), offsets.to_byte(end));
+    let window = &new[start_byte..end_byte];
+    if is_fragile_text(window) {
+        return Ok(None);
+    }
+
+    // sentinels keep the paragraph breaks from being trimmed as the ends of a document
+    let mut source = String::with_capacity(window.len() + 2);
+    if before.is_some() {
+        source.push('x');
+    }
+    source.push_str(window);
+    if after.is_some() {
+        source.push('x');
+    }
+    let Ok(Node::Root { content: mut nodes, .. }) = parse(&source) else {
+        return Ok(None);
+    };
+    let sentinel = |node: Option<&Node>| matches!(node, Some(Node::String { content, .. }) if content == "x");
+    if after.is_some() {
+        if !sentinel(nodes.last()) || !matches!(nodes.iter().rev().nth(1), Some(Node::Parbreak { .. })) {
+            return Ok(None);
+        }
+        nodes.pop();
+    }
+    if before.is_some() {
+        if !sentinel(nodes.first()) || !matches!(nodes.get(1), Some(Node::Parbreak { .. })) {
+            return Ok(None);
+        }
+        nodes.remove(0);
+    }
+    // lists split their content at items, which the top level does not
+    if nodes.iter().any(has_marker) || !top && nodes.iter().any(is_item) {
+        return Ok(None);
+    }
+
+    if before.is_some() {
+        let line = 1 + line_breaks(&new[..start_byte]);
+        let column = 1 + utf16_len(&new[new[..start_byte].rfind('\n').map_or(0, |i| i + 1)..start_byte]);
+        let first_line_end = source.find('\n').map_or(usize::MAX, |i| utf16_len(&source[..i]));
+        for node in &mut nodes {
+            shift(node, &|position: &mut Position| {
+                if position.offset <= first_line_end {
+                    position.column = column + position.column - 2;
+                }
+                position.offset = position.offset + start - 1;
+                position.line = line + position.line - 1;
+            });
+        }
+    }
+    let first = before.map_or(0, |(i, _)| i);
+    let last = after.map_or(content.len(), |(i, _)| i + 1);
+    shift_following(&mut content[last..], edit);
+    content.splice(first..last, nodes);
+    Ok(Some(start_byte..end_byte))
+}
+
+/// Move the positions of the `nodes` following the `edit`.
+fn shift_following(nodes: &mut [Node], edit: &Edit) {
+    for node in nodes {
+        shift(node, &|position: &mut Position| {
+            position.offset = edit.shift(position.offset);
+            position.line = (position.line as isize + edit.lines) as usize;
+        });
+    }
+}
+
+/// Whether the content of `node` is parsed like the top level of a document, so that its
+/// paragraphs can be parsed again on their own: an environment which is not math or a list.
+fn is_splittable(node: &Node) -> bool {
+    let Node::Environment { content, position, render_info, .. } = node else {
+        return false;
+    };
+    let owner = (position.start.line > 0).then_some(position.start.offset);
+    owner.is_some()
+        && !render_info.as_ref().is_some_and(|info| info.in_math_mode)
+        && !is_relative(content, owner, 9)
+        && !content.iter().any(is_item)
+}
+
+/// Whether `node` is an item of a list, which holds what follows it as an argument.
+fn is_item(node: &Node) -> bool {
+    matches!(node, Node::Macro { content, .. } if content == "item" || content == "choice")
+}
+
+/// An edit, in UTF-16 offsets.
+struct Edit {
+    start: usize,
+    old_end: usize,
+    new_end: usize,
+    /// Change in the number of lines.
+    lines: isize,
+}
+
+impl Edit {
+    /// The new offset of an old offset following the edit.
+    fn shift(&self, offset: usize) -> usize {
+        offset - self.old_end + self.new_end
+    }
+}
+
+fn utf16_len(text: &str) -> usize {
+    text.encode_utf16().count()
+}
+
+/// Number of lines breaks, which only line feeds are for the parser.
+fn line_breaks(text: &str) -> usize {
+    text.matches('\n').count()
+}
+
+fn end_position(text: &str) -> Position {
+    Position {
+        line: line_breaks(text) + 1,
+        offset: utf16_len(text),
+        column: utf16_len(&text[text.rfind('\n').map_or(0, |i| i + 1)..]) + 1,
+    }
+}
+
+/// Whether `text` contains macros changing the parse up to their counterpart.
+pub(crate) fn is_fragile_text(text: &str) -> bool {
+    text.contains("\\makeat") || text.contains("\\ExplSyntax")
+}
+
+pub(crate) fn is_fragile(text: &str, root: &Node) -> bool {
+    is_fragile_text(text) || has_marker(root)
+}
+
+/// Whether `node` contains what is left of groups, environments or math the parser could
+/// not close, which looked ahead to the end of the document.
+fn has_marker(node: &Node) -> bool {
+    match node {
+        Node::String { content, .. } => matches!(content.as_str(), "{" | "}" | "$"),
+        Node::Macro { content, escape_token, .. } if escape_token.as_deref().unwrap_or("\\") == "\\" => {
+            matches!(content.as_str(), "begin" | "end" | "[" | "]" | "(" | ")" | "verb") || node.args().iter().any(has_marker)
+        }
+        node => node.args().iter().chain(node.content()).any(has_marker),
+    }
+}
+
+/// Whether the `content` of a macro argument or an environment was parsed again in math
+/// mode from its printed source, with positions relative to it: they start before the
+/// content could in the source, `skip` units after the `owner` node.
+fn is_relative(content: &[Node], owner: Option<usize>, skip: usize) -> bool {
+    fn first(nodes: &[Node]) -> Option<usize> {
+        nodes.iter().find_map(|node| match node.position() {
+            Some(position) => Some(position.start.offset),
+            None => first(node.args()).or_else(|| first(node.content())),
+        })
+    }
+    matches!((owner, first(content)), (Some(owner), Some(first)) if first < owner + skip)
+}
+
+/// Move the positions under `node` with `map`.
+fn shift(node: &mut Node, map: &impl Fn(&mut Position)) {
+    let owner = node.position().map(|position| position.start.offset);
+    let relative: Vec<bool> = node.args().iter().map(|arg| is_relative(arg.content(), owner, 1)).collect();
+    // `\begin{x}` at least precedes the content of an environment
+    let relative_content = matches!(node, Node::Environment { .. } | Node::MathEnv { .. }) && is_relative(node.content(), owner, 9);
+    match node {
+        Node::Root { content, position, .. }
+        | Node::DisplayMath { content, position, .. }
+        | Node::Group { content, position, .. }
+        | Node::InlineMath { content, position, .. }
+        | Node::Argument { content, position, .. } => {
+            map_position(position, map);
+            content.iter_mut().for_each(|node| shift(node, map));
+        }
+        Node::Environment { args, content, position, .. } | Node::MathEnv { args, content, position, .. } => {
+            map_position(position, map);
+            shift_args(args, &relative, map);
+            if !relative_content {
+                content.iter_mut().for_each(|node| shift(node, map));
+            }
+        }
+        Node::Macro { args, position, .. } | Node::VerbatimEnvironment { args, position, .. } => {
+            map_position(position, map);
+            shift_args(args, &relative, map);
+        }
+        Node::String { position, .. }
+        | Node::WhiteSpace { position, .. }
+        | Node::Parbreak { position, .. }
+        | Node::Comment { position, .. }
+        | Node::Verb { position, .. } => map_position(position, map),
+        Node::Error => {}
+    }
+}
+
+fn shift_args(args: &mut [Node], relative: &[bool], map: &impl Fn(&mut Position)) {
+    for (arg, &relative) in args.iter_mut().zip(relative) {
+        if !relative {
+            shift(arg, map);
+        }
+    }
+}
+
+fn map_position(position: &mut PositionInfo, map: &impl Fn(&mut Position)) {
+    if position.start.line > 0 {
+        map(&mut position.start);
+        map(&mut position.end);
+    }
+}
@@ -21,7 +21,7 @@ pub enum Error {
     #[error("string from rquickjs was not UTF-8")]
     Utf8(Utf8Error),
     /// An io error
-    #[error("an io error")]
+    #[error("an io error (details: {0})")]
     Io(String),
     /// An exception raised by quickjs itself.
     #[error("an exception raised by quickjs itself (details: {message} at {file}:{line})")]
@@ -53,7 +53,7 @@ pub enum Error {
     },
     #[cfg(feature = "loader")]
     /// Error when resolving js module
-    #[error("error when resolving js module (details: {message} from {base} to {name})")]
+    #[error("error when resolving js module (details: {message:?} from {base} to {name})")]
     Resolving {
         base: String,
         name: String,
@@ -61,11 +61,65 @@ pub enum Error {
     },
     #[cfg(feature = "loader")]
     /// Error when loading js module
-    #[error("error when loading js module (details: {message} from {name})")]
+    #[error("error when loading js module (details: {message:?} from {name})")]
     Loading {
         name: String,
         message: Option<String>,
     },
+    /// A configuration file could not be parsed.
+    #[error("invalid configuration file (details: {message} in {path})")]
+    Config {
+        path: String,
+        message: String,
+    },
+    /// A BibTeX database could not be parsed.
+    #[error("invalid BibTeX (details: {message} at line {line})")]
+    Bib {
+        line: usize,
+        message: String,
+    },
+    /// An included file is missing or includes itself.
+    #[error("cannot include file (details: {message} in {path})")]
+    Include {
+        path: String,
+        message: String,
+    },
+    /// A range does not lie within the input or does not fall on character boundaries.
+    #[error("invalid range {start}..{end}")]
+    InvalidRange {
+        start: usize,
+        end: usize,
+    },
+    /// A region excluded from formatting, starting at `line` of the input, could not be
+    /// put back in the formatted output.
+    #[error("region excluded from formatting at line {line} is missing from the formatted output")]
+    IgnoredRegionLost {
+        line: usize,
+    },
+    /// A document could not be formatted in chunks, as the printer laid them out unlike
+    /// the rest of the document.
+    #[error("cannot format document in chunks (details: {message})")]
+    Chunking {
+        message: String,
+    },
+    /// A document nests groups, environments or math deeper than the native parser accepts.
+    #[error("document nested deeper than {depth} levels")]
+    TooDeep {
+        depth: usize,
+    },
+    /// Formatting the output of the formatter again changes it, from `line` on as `diff` shows.
+    #[error("formatting is not idempotent, formatting again changes line {line}:\n{diff}")]
+    NotIdempotent {
+        line: usize,
+        diff: String,
+    },
+    /// The output of the formatter does not parse to the tree of its input.
+    #[error("formatting changed the document at line {line} (line {formatted_line} once formatted): {message}")]
+    NotEquivalent {
+        line: usize,
+        formatted_line: usize,
+        message: String,
+    },
     /// Error when restoring a Persistent in a runtime other than the original runtime.
     #[error("error when restoring a Persistent in a runtime other than the original runtime")]
     UnrelatedRuntime,
@@ -79,6 +133,12 @@ pub enum Error {
 pub type Result<T, E = Error> = core::result::Result<T, E>;
 
 
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e.to_string())
+    }
+}
+
 impl From<rquickjs::Error> for Error {
     fn from(e: rquickjs::Error) -> Self {
         match e {
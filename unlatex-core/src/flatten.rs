@@ -0,0 +1,122 @@
+//! Inlining the files of a [`Project`] into a single document.
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use std::path::PathBuf;
+//! use unlatex::flatten::{flatten_with, FlattenOptions};
+//!
+//! let files = HashMap::from([
+//!     (PathBuf::from("main.tex"), "A\n\\input{b}\n% \\input{c}\nD\n".to_string()),
+//!     (PathBuf::from("b.tex"), "B\n".to_string()),
+//! ]);
+//! let flat = flatten_with("main.tex", &FlattenOptions::default(), &files).unwrap();
+//! assert_eq!(flat, "A\nB\n% \\input{c}\nD\n");
+//! ```
+
+use std::ops::Range;
+use std::path::Path;
+
+use crate::ast::Node;
+use crate::cleanup::{cleanup_edits, CleanupOptions};
+use crate::error::{Error, Result};
+use crate::macros::visit_lists;
+use crate::project::{FsLoader, Loader, Project, ProjectFile, ProjectIssue};
+use crate::span::{apply_edits, node_range, OffsetMap};
+
+/// Options of [`flatten_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct FlattenOptions {
+    /// Remove comments, keeping a `%` where it joins two lines.
+    pub strip_comments: bool,
+    /// Replace `\bibliography` with the content of the `.bbl` file named after the root file.
+    pub inline_bbl: bool,
+}
+
+/// Inline the files included by the document at `root`, read from the file system.
+pub fn flatten(root: impl AsRef<Path>) -> Result<String> {
+    flatten_with(root, &FlattenOptions::default(), &FsLoader)
+}
+
+/// Inline the files included by the document at `root`, reading files with `loader`.
+///
+/// `\input`, `\subfile` and `\import`-like macros are replaced by the content of the
+/// included file, `\include` also by the `\clearpage` it implies. Only the body of the
+/// `document` environment of a `\subfile` is kept. Fails with [`Error::Include`] if an
+/// included file is missing or includes itself.
+pub fn flatten_with(root: impl AsRef<Path>, opts: &FlattenOptions, loader: &impl Loader) -> Result<String> {
+    let project = Project::load_with(root, loader)?;
+    if let Some(issue) = project.issues.first() {
+        let (ProjectIssue::MissingFile { file, .. } | ProjectIssue::Cycle { file, .. }) = issue;
+        return Err(Error::Include {
+            path: project.files[*file].path.display().to_string(),
+            message: issue.to_string(),
+        });
+    }
+
+    let bbl = if opts.inline_bbl {
+        let path = project.root().path.with_extension("bbl");
+        let bbl = loader.load(&path).map_err(|e| Error::Include {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        Some(bbl)
+    } else {
+        None
+    };
+    Ok(flatten_file(&project, 0, opts, bbl.as_deref()))
+}
+
+/// Content of the file `index` of `project` with its includes inlined.
+fn flatten_file(project: &Project, index: usize, opts: &FlattenOptions, bbl: Option<&str>) -> String {
+    let file = &project.files[index];
+    let map = OffsetMap::new(&file.source);
+    let mut edits: Vec<(Range<usize>, String)> = vec![];
+
+    for include in &file.includes {
+        let Some(included) = include.file else {
+            continue;
+        };
+        let content = flatten_file(project, included, opts, bbl);
+        let content = if include.macro_name == "subfile" {
+            document_body(&project.files[included], &content)
+        } else {
+            content.strip_suffix('\n').unwrap_or(&content).to_string()
+        };
+        let content = match include.macro_name.as_str() {
+            "include" | "includefrom" | "subincludefrom" => format!("\\clearpage\n{content}\n\\clearpage"),
+            _ => content,
+        };
+        edits.push((include.range.clone(), content));
+    }
+
+    visit_lists(&file.ast, &mut |siblings| {
+        for node in siblings {
+            if !matches!(node, Node::Macro { content, .. } if content == "bibliography") {
+                continue;
+            }
+            if let (Some(bbl), Some(range)) = (bbl, node_range(node, &file.source, &map)) {
+                edits.push((range, bbl.strip_suffix('\n').unwrap_or(bbl).to_string()));
+            }
+        }
+    });
+    if opts.strip_comments {
+        edits.extend(cleanup_edits(&file.ast, &file.source, &CleanupOptions::comments_only()));
+    }
+    apply_edits(&file.source, edits)
+}
+
+/// The body of the `document` environment of `file`, taken from its flattened `content`.
+fn document_body(file: &ProjectFile, content: &str) -> String {
+    let has_document = file.ast.content().iter().any(|n| matches!(n, Node::Environment { env, .. } if env == "document"));
+    let body = has_document
+        .then(|| {
+            let start = content.find("\\begin{document}")? + "\\begin{document}".len();
+            let end = content.rfind("\\end{document}")?;
+            (start <= end).then(|| &content[start..end])
+        })
+        .flatten();
+    match body {
+        Some(body) => body.trim_matches('\n').to_string(),
+        None => content.trim_end_matches('\n').to_string(),
+    }
+}
@@ -0,0 +1,204 @@
+//! Formatter suppression comments.
+//!
+//! Parts of a document can be excluded from formatting with comments:
+//!
+//! ```latex
+//! % latexformat-ignore
+//! \begin{tabular}{ll}
+//!   hand & aligned \\
+//! \end{tabular}
+//!
+//! % latexformat: off
+//! \begin{tikzpicture} ... \end{tikzpicture}
+//! % latexformat: on
+//! ```
+//!
+//! `% latexformat-ignore` excludes the next node, `% latexformat: off` excludes everything
+//! up to the matching `% latexformat: on` (or the end of the enclosing group), and
+//! `% latexformat-ignore-file` before any content excludes the whole file.
+
+use std::ops::Range;
+
+use crate::ast::Node;
+use crate::span::{node_range, OffsetMap};
+use crate::{Error, Result};
+
+/// Comment excluding the next node from formatting.
+pub const IGNORE: &str = "latexformat-ignore";
+/// Comment excluding the whole file from formatting, when placed before any content.
+pub const IGNORE_FILE: &str = "latexformat-ignore-file";
+/// Comment starting a region excluded from formatting.
+pub const OFF: &str = "latexformat: off";
+/// Comment ending a region excluded from formatting.
+pub const ON: &str = "latexformat: on";
+
+/// Name given to the placeholders standing for ignored regions while formatting.
+const PLACEHOLDER: &str = "latexformatignored";
+
+pub(crate) fn is_comment(node: &Node, pragma: &str) -> bool {
+    matches!(node, Node::Comment { content, .. } if content.trim() == pragma)
+}
+
+/// Whether the document starts with a `% latexformat-ignore-file` comment.
+pub fn has_ignore_file_pragma(root: &Node) -> bool {
+    root.content()
+        .iter()
+        .take_while(|n| matches!(n, Node::Comment { .. } | Node::WhiteSpace { .. } | Node::Parbreak { .. }))
+        .any(|n| is_comment(n, IGNORE_FILE))
+}
+
+/// Byte ranges of `src` excluded from formatting by suppression comments, sorted and
+/// non-overlapping.
+pub fn ignored_ranges(root: &Node, src: &str) -> Vec<Range<usize>> {
+    let map = OffsetMap::new(src);
+    let mut ranges = vec![];
+    collect_ranges(root.content(), src, &map, &mut ranges);
+
+    ranges.sort_by_key(|r| r.start);
+    let mut merged: Vec<Range<usize>> = vec![];
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start < last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+fn collect_ranges(content: &[Node], src: &str, map: &OffsetMap, ranges: &mut Vec<Range<usize>>) {
+    let mut i = 0;
+    while i < content.len() {
+        let node = &content[i];
+        if is_comment(node, IGNORE) {
+            let next = content[i + 1..]
+                .iter()
+                .position(|n| !matches!(n, Node::WhiteSpace { .. } | Node::Parbreak { .. } | Node::Comment { .. }));
+            if let Some(next) = next {
+                i += 1 + next;
+                ranges.extend(node_range(&content[i], src, map));
+                i += 1;
+                continue;
+            }
+        } else if is_comment(node, OFF) {
+            let end = content[i + 1..].iter().position(|n| is_comment(n, ON)).map(|j| i + 1 + j);
+            let start = node_range(node, src, map).map(|r| r.end);
+            let stop = match end {
+                Some(end) => node_range(&content[end], src, map).map(|r| r.start),
+                None => content.iter().rev().find_map(|n| node_range(n, src, map)).map(|r| r.end),
+            };
+            if let (Some(start), Some(stop)) = (start, stop) {
+                let region = &src[start..stop.max(start)];
+                let start = start + (region.len() - region.trim_start().len());
+                let stop = start + region.trim().len();
+                if start < stop {
+                    ranges.push(start..stop);
+                }
+            }
+            i = end.map_or(content.len(), |end| end + 1);
+            continue;
+        }
+
+        collect_ranges(node.content(), src, map, ranges);
+        for arg in node.args() {
+            collect_ranges(arg.content(), src, map, ranges);
+        }
+        i += 1;
+    }
+}
+
+/// Name of the `i`-th placeholder, using letters only so it reads as a single macro name.
+///
+/// Macro placeholders are followed by `{}` so that letters after them are not read as part
+/// of their name.
+fn placeholder_name(mut i: usize) -> String {
+    let mut name = String::from(PLACEHOLDER);
+    let mut suffix = vec![];
+    loop {
+        suffix.push(b'a' + (i % 26) as u8);
+        i /= 26;
+        if i == 0 {
+            break;
+        }
+        i -= 1;
+    }
+    name.extend(suffix.iter().rev().map(|&c| c as char));
+    name
+}
+
+/// An ignored region replaced by a placeholder.
+struct Masked {
+    /// Text the placeholder was put in place of.
+    original: String,
+    /// Line of the input the region starts at, 1-based.
+    line: usize,
+    /// Placeholder text as it is expected to be found in the formatted output.
+    open: String,
+    /// End of a placeholder environment.
+    close: Option<String>,
+}
+
+/// Replace the `ranges` of `src` by placeholders the formatter leaves intact.
+///
+/// Regions spanning whole lines are replaced by an empty environment so that they stay
+/// on their own lines, other regions by a macro.
+fn mask(src: &str, ranges: &[Range<usize>]) -> (String, Vec<Masked>) {
+    let mut masked = String::with_capacity(src.len());
+    let mut regions = vec![];
+    let mut last = 0;
+    for (i, range) in ranges.iter().enumerate() {
+        let name = placeholder_name(i);
+        let before = src[..range.start].trim_end_matches([' ', '\t']);
+        let after = src[range.end..].trim_start_matches([' ', '\t']);
+        let block = (before.is_empty() || before.ends_with('\n')) && (after.is_empty() || after.starts_with(['\n', '\r']));
+
+        masked.push_str(&src[last..range.start]);
+        let original = src[range.clone()].to_string();
+        let line = src[..range.start].matches('\n').count() + 1;
+        let region = if block {
+            let open = format!("\\begin{{{name}}}");
+            let close = format!("\\end{{{name}}}");
+            masked.push_str(&open);
+            masked.push_str(&close);
+            Masked { original, line, open, close: Some(close) }
+        } else {
+            let open = format!("\\{name}{{}}");
+            masked.push_str(&open);
+            Masked { original, line, open, close: None }
+        };
+        regions.push(region);
+        last = range.end;
+    }
+    masked.push_str(&src[last..]);
+    (masked, regions)
+}
+
+/// Put the original text back in place of the placeholders of `formatted`.
+fn unmask(formatted: &str, regions: &[Masked]) -> Result<String> {
+    let mut output = formatted.to_string();
+    for region in regions {
+        let start = output.find(&region.open).ok_or(Error::IgnoredRegionLost { line: region.line })?;
+        let end = start + region.open.len();
+        let end = match &region.close {
+            Some(close) => end + output[end..].find(close.as_str()).ok_or(Error::IgnoredRegionLost { line: region.line })? + close.len(),
+            None => end,
+        };
+        output.replace_range(start..end, &region.original);
+    }
+    Ok(output)
+}
+
+/// Format `src` with `format`, keeping the regions excluded by suppression comments untouched.
+pub(crate) fn format_preserving<F>(src: &str, root: &Node, format: F) -> Result<String>
+    where F: FnOnce(&str) -> Result<String>
+{
+    if has_ignore_file_pragma(root) {
+        return Ok(src.to_string());
+    }
+    let ranges = ignored_ranges(root, src);
+    if ranges.is_empty() {
+        return format(src);
+    }
+    let (masked, regions) = mask(src, &ranges);
+    let formatted = format(&masked)?;
+    unmask(&formatted, &regions)
+}
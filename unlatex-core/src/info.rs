@@ -1,16 +1,17 @@
 #![allow(missing_docs)]
 
 use rquickjs::{Ctx, FromJs, Object, Value};
+use serde::Serialize;
 use crate::ast::{Node, get_undefined};
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
 pub struct Position {
     pub line: usize,
     pub offset: usize,
     pub column: usize,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
 pub struct PositionInfo {
     pub start: Position,
     pub end: Position,
@@ -22,8 +22,38 @@
 pub mod info;
 pub mod ast;
 pub mod error;
+pub mod options;
+pub mod config;
+pub mod span;
+pub mod diagnostics;
+pub mod ignore;
+pub mod outline;
+pub mod macros;
+pub mod bib;
+pub mod project;
+pub mod flatten;
+pub mod cleanup;
+pub mod lint;
+pub mod analysis;
+pub mod document;
+pub mod chunked;
+pub mod cache;
+pub mod compare;
+pub mod diff;
+pub mod markup;
+pub mod stats;
+pub mod verify;
+#[cfg(feature = "native-parser")]
+pub mod native;
 
 pub use error::{Error, Result};
+pub use options::FormatOptions;
+pub use config::Config;
+pub use outline::{outline, Outline};
+pub use flatten::flatten;
+pub use compare::semantic_eq;
+pub use diff::diff;
+pub use stats::{stats, Stats};
 
 /// JS source code.
 const JS_SRC: &str = concat!(
@@ -40,6 +70,7 @@ include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/js/entry.js")),
 );
 
 
+use std::ops::Range;
 use rquickjs::{Runtime, Context, Function, intrinsic};
 
 thread_local! {
@@ -90,19 +121,81 @@ fn format_inner(engine: &Context, input: &str, print_width: i32, use_tabs: bool,
 }
 
 /// Format LaTeX document using the default [engine](`JsEngine`).
+///
+/// Regions excluded by [suppression comments](ignore) are left untouched.
 pub fn format_with_opts(input: &str, print_width: i32, use_tabs: bool, tab_width: i32, document_only: bool) -> Result<String> {
     UNLATEX.with(|engine| {
         engine
             .as_ref()
             .map_err(|e| e.clone())
-            .and_then(|(_, contex)| format_inner(contex, input, print_width, use_tabs, tab_width, document_only))
+            .and_then(|(_, contex)| {
+                // skip parsing documents which cannot have suppression comments
+                if !input.contains("latexformat") {
+                    return format_inner(contex, input, print_width, use_tabs, tab_width, document_only);
+                }
+                let ast = parse_inner(contex, input)?;
+                ignore::format_preserving(input, &ast, |input| {
+                    format_inner(contex, input, print_width, use_tabs, tab_width, document_only)
+                })
+            })
     })
 }
 
+/// Format LaTeX document with the given [options](`FormatOptions`) using the default [engine](`JsEngine`).
+pub fn format_with_options(input: &str, opts: &FormatOptions) -> Result<String> {
+    format_with_opts(input, opts.print_width, opts.use_tabs, opts.tab_width, opts.document_only)
+}
+
+/// Format the part of LaTeX document in the byte `range` using the default [engine](`JsEngine`),
+/// returning the whole document.
+///
+/// The range is extended to the paragraphs it touches, text outside of them is left untouched.
+/// The document is returned unchanged when the range lies within the body of an environment
+/// aligning its content, such as `tabular`, whose rows are only formatted along with each other.
+/// [`FormatOptions::document_only`] is ignored.
+pub fn format_range(input: &str, range: Range<usize>, opts: &FormatOptions) -> Result<String> {
+    if range.start > range.end || !input.is_char_boundary(range.start) || !input.is_char_boundary(range.end) {
+        return Err(Error::InvalidRange { start: range.start, end: range.end });
+    }
+    let ast = parse(input)?;
+    let map = span::OffsetMap::new(input);
+    let Some(range) = span::paragraphs_range(&ast, input, &map, &range) else {
+        return Ok(input.to_string());
+    };
+    // suppression comments outside of the selection still apply to it
+    let ignored = ignore::ignored_ranges(&ast, input);
+    let straddles = |r: &Range<usize>| r.start < range.end && range.start < r.end && !(range.start <= r.start && r.end <= range.end);
+    if ignore::has_ignore_file_pragma(&ast) || ignored.iter().any(straddles) {
+        return Ok(input.to_string());
+    }
+
+    // the selected paragraphs are formatted on their own, so they are re-indented
+    // to the column they start at
+    let line_start = input[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let prefix = &input[line_start..range.start];
+    let indent = if prefix.trim().is_empty() { prefix } else { "" };
+    let indent_width: i32 = indent.chars().map(|c| if c == '\t' { opts.tab_width } else { 1 }).sum();
+
+    let formatted = format_with_opts(&input[range.clone()], opts.print_width - indent_width, opts.use_tabs, opts.tab_width, false)?;
+    let mut output = String::with_capacity(input.len());
+    output.push_str(&input[..range.start]);
+    for (i, line) in formatted.split('\n').enumerate() {
+        if i > 0 {
+            output.push('\n');
+            if !line.is_empty() {
+                output.push_str(indent);
+            }
+        }
+        output.push_str(line);
+    }
+    output.push_str(&input[range.end..]);
+    Ok(output)
+}
+
 /// Format LaTeX document using the default [engine](`JsEngine`).
 #[inline]
 pub fn format(input: &str) -> Result<String> {
-    format_with_opts(input, 80, false, 2, true)
+    format_with_options(input, &FormatOptions::default())
 }
 
 
@@ -119,7 +212,21 @@ fn parse_inner(engine: &Context, input: &str) -> Result<ast::Node> {
 }
 
 /// Parse LaTeX document using the default [engine](`JsEngine`).
+///
+/// With the `native-parser` feature, the document is parsed by the [native parser](native)
+/// instead, which produces the same tree without the JavaScript engine.
 pub fn parse(input: &str) -> Result<ast::Node> {
+    #[cfg(feature = "native-parser")]
+    let parse = native::parse;
+    #[cfg(not(feature = "native-parser"))]
+    let parse = js_parse;
+    parse(input)
+}
+
+/// Parse LaTeX document using the default [engine](`JsEngine`), even with the
+/// `native-parser` feature.
+#[cfg_attr(all(feature = "native-parser", not(test)), allow(dead_code))]
+fn js_parse(input: &str) -> Result<ast::Node> {
     UNLATEX.with(|engine| {
         engine
             .as_ref()
@@ -128,6 +235,51 @@ pub fn parse(input: &str) -> Result<ast::Node> {
     })
 }
 
+/// Parse LaTeX document into a tree [borrowing its text](ast::borrowed) from `input`.
+///
+/// With the `native-parser` feature, the tree is built by the [native parser](native)
+/// without going through an owned tree.
+pub fn parse_borrowed(input: &str) -> Result<ast::borrowed::Node<'_>> {
+    #[cfg(feature = "native-parser")]
+    return native::parse_borrowed(input);
+    #[cfg(not(feature = "native-parser"))]
+    parse(input).map(|ast| ast::borrowed::Node::from_owned(ast, input))
+}
+
+/// Parse LaTeX document using the default [engine](`JsEngine`), reporting the
+/// [problems](diagnostics::check) of malformed documents along with the best-effort AST.
+///
+/// If the parser fails, e.g. on too deeply nested groups, an empty document is returned
+/// with the failure as diagnostic.
+pub fn parse_with_diagnostics(input: &str) -> Result<(ast::Node, Vec<diagnostics::Diagnostic>)> {
+    match parse(input) {
+        Ok(ast) => {
+            let diagnostics = diagnostics::check(&ast, input);
+            Ok((ast, diagnostics))
+        }
+        Err(Error::Exception { message, .. }) => Ok(failed_parse(input, &message)),
+        Err(err @ Error::TooDeep { .. }) => Ok(failed_parse(input, &err.to_string())),
+        Err(err) => Err(err),
+    }
+}
+
+/// An empty document spanning `input`, with the failure of the parser as diagnostic.
+fn failed_parse(input: &str, message: &str) -> (ast::Node, Vec<diagnostics::Diagnostic>) {
+    let end = info::Position {
+        line: input.matches('\n').count() + 1,
+        offset: input.encode_utf16().count(),
+        column: input[input.rfind('\n').map_or(0, |i| i + 1)..].encode_utf16().count() + 1,
+    };
+    let position = info::PositionInfo { start: info::Position { line: 1, offset: 0, column: 1 }, end };
+    let root = ast::Node::Root { content: vec![], position, render_info: None };
+    let diagnostic = diagnostics::Diagnostic::new(
+        diagnostics::Severity::Error,
+        format!("the parser failed: {message}"),
+        0..input.len(),
+    );
+    (root, vec![diagnostic])
+}
+
 /// Parse LaTeX document using specified [engine](`JsEngine`).
 #[inline]
 fn jparse_inner(engine: &Context, input: &str) -> Result<String> {
@@ -0,0 +1,250 @@
+//! Style checks over the AST, in the spirit of chktex and lacheck.
+//!
+//! ```
+//! use unlatex::lint::{lint, LintOptions, Severity};
+//!
+//! let diagnostics = lint(r"Wait... see \ref{fig}", &LintOptions::default()).unwrap();
+//! let rules: Vec<_> = diagnostics.iter().map(|d| d.rule.as_str()).collect();
+//! assert_eq!(rules, ["ellipsis", "ref-tie"]);
+//! assert_eq!(diagnostics[0].severity, Severity::Warning);
+//! ```
+//!
+//! Some diagnostics carry a [`Fix`], which [`fix`] applies:
+//!
+//! ```
+//! use unlatex::lint::{fix, LintOptions};
+//!
+//! let fixed = fix(r"Wait... see \cite{knuth} $$x$$", &LintOptions::default()).unwrap();
+//! assert_eq!(fixed, r"Wait\dots{} see~\cite{knuth} \[x\]");
+//! ```
+//!
+//! Rules are enabled and disabled by ID, e.g. in the `[lint]` table of a configuration file:
+//!
+//! ```toml
+//! [lint]
+//! disable = ["quotes"]
+//! severity = { left-right = "warning" }
+//! ```
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+pub use crate::diagnostics::Severity;
+
+use crate::diagnostics::Diagnostic;
+
+use crate::ast::Node;
+use crate::error::Result;
+use crate::info::PositionInfo;
+use crate::span::{apply_edits, OffsetMap};
+
+pub mod rules;
+
+/// A problem found by a [`Rule`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LintDiagnostic {
+    /// ID of the rule which found the problem.
+    pub rule: String,
+    /// Severity of the problem.
+    pub severity: Severity,
+    /// Description of the problem.
+    pub message: String,
+    /// Position of the offending source.
+    pub position: PositionInfo,
+    /// Edits fixing the problem, if the rule knows how.
+    pub fix: Option<Fix>,
+}
+
+impl LintDiagnostic {
+    /// This diagnostic as a [`Diagnostic`] with the rule ID as code, given the
+    /// [`OffsetMap`] of the linted source.
+    pub fn to_diagnostic(&self, map: &OffsetMap) -> Diagnostic {
+        let span = map.to_byte(self.position.start.offset)..map.to_byte(self.position.end.offset);
+        Diagnostic {
+            code: Some(self.rule.clone()),
+            ..Diagnostic::new(self.severity, self.message.clone(), span)
+        }
+    }
+}
+
+/// Edits fixing a [`LintDiagnostic`], applied together or not at all.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Fix {
+    /// Byte ranges of the linted source and their replacements, not overlapping.
+    pub edits: Vec<(Range<usize>, String)>,
+}
+
+impl Fix {
+    /// A fix replacing `range` with `replacement`.
+    pub fn replace(range: Range<usize>, replacement: impl Into<String>) -> Fix {
+        Fix { edits: vec![(range, replacement.into())] }
+    }
+}
+
+/// Which rules run, and how serious their diagnostics are.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct LintOptions {
+    /// IDs of rules to run even if they are disabled by default.
+    pub enable: Vec<String>,
+    /// IDs of rules not to run.
+    pub disable: Vec<String>,
+    /// Severity of the diagnostics of a rule, by rule ID.
+    pub severity: BTreeMap<String, Severity>,
+}
+
+/// What a rule sees of the document, and where it reports problems.
+pub struct Context<'a> {
+    /// The document.
+    pub root: &'a Node,
+    /// Source the document was parsed from.
+    pub src: &'a str,
+    map: OffsetMap,
+    findings: Vec<(PositionInfo, String, Option<Fix>)>,
+}
+
+impl<'a> Context<'a> {
+    /// Context for checking `root`, parsed from `src`.
+    pub fn new(root: &'a Node, src: &'a str) -> Context<'a> {
+        Context { root, src, map: OffsetMap::new(src), findings: vec![] }
+    }
+
+    /// Report a problem at `position`.
+    pub fn report(&mut self, position: &PositionInfo, message: impl Into<String>) {
+        self.findings.push((position.clone(), message.into(), None));
+    }
+
+    /// Report a problem at `position`, which `fix` fixes.
+    pub fn report_fix(&mut self, position: &PositionInfo, message: impl Into<String>, fix: Fix) {
+        self.findings.push((position.clone(), message.into(), Some(fix)));
+    }
+
+    /// Byte range of `position` in the source.
+    pub fn range(&self, position: &PositionInfo) -> Range<usize> {
+        self.map.to_byte(position.start.offset)..self.map.to_byte(position.end.offset)
+    }
+
+    /// Source text at `position`.
+    pub fn text(&self, position: &PositionInfo) -> &'a str {
+        &self.src[self.range(position)]
+    }
+}
+
+/// A check run over a whole document.
+pub trait Rule {
+    /// Unique kebab-case ID of the rule, used in configuration and output.
+    fn id(&self) -> &'static str;
+
+    /// One-line description of what the rule checks.
+    fn description(&self) -> &'static str;
+
+    /// Severity of the rule's diagnostics, unless configured otherwise.
+    fn severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    /// Whether the rule runs without being enabled in the configuration.
+    fn enabled_by_default(&self) -> bool {
+        true
+    }
+
+    /// Check the document of `cx`, reporting problems to it.
+    fn check(&self, cx: &mut Context<'_>);
+}
+
+/// A set of rules with their configuration.
+pub struct Linter {
+    rules: Vec<Box<dyn Rule>>,
+    options: LintOptions,
+}
+
+impl Linter {
+    /// A linter running the [built-in rules](rules::builtin) configured by `options`.
+    pub fn new(options: LintOptions) -> Linter {
+        Linter { rules: rules::builtin(), options }
+    }
+
+    /// Add a rule, which is subject to the configuration like the built-in ones.
+    pub fn add_rule(&mut self, rule: Box<dyn Rule>) {
+        self.rules.push(rule);
+    }
+
+    /// All the rules, enabled or not.
+    pub fn rules(&self) -> impl Iterator<Item = &dyn Rule> {
+        self.rules.iter().map(|rule| rule.as_ref())
+    }
+
+    /// Whether `rule` runs with the configuration of this linter.
+    pub fn is_enabled(&self, rule: &dyn Rule) -> bool {
+        let listed = |ids: &[String]| ids.iter().any(|id| id == rule.id());
+        !listed(&self.options.disable) && (rule.enabled_by_default() || listed(&self.options.enable))
+    }
+
+    /// Run the enabled rules over `root`, parsed from `src`. Diagnostics are sorted by position.
+    pub fn check(&self, root: &Node, src: &str) -> Vec<LintDiagnostic> {
+        let mut diagnostics = vec![];
+        for rule in self.rules().filter(|rule| self.is_enabled(*rule)) {
+            let mut cx = Context::new(root, src);
+            rule.check(&mut cx);
+            let severity = self.options.severity.get(rule.id()).copied().unwrap_or(rule.severity());
+            diagnostics.extend(cx.findings.into_iter().map(|(position, message, fix)| LintDiagnostic {
+                rule: rule.id().to_string(),
+                severity,
+                message,
+                position,
+                fix,
+            }));
+        }
+        diagnostics.sort_by_key(|d| d.position.start.offset);
+        diagnostics
+    }
+
+    /// Apply the fixes of the diagnostics of `input` until none is left, or an
+    /// iteration limit is hit.
+    pub fn fix(&self, input: &str) -> Result<String> {
+        let mut src = input.to_string();
+        for _ in 0..MAX_FIX_PASSES {
+            let root = crate::parse(&src)?;
+            let diagnostics = self.check(&root, &src);
+            let (fixed, applied) = apply_fixes(&src, &diagnostics);
+            if applied == 0 {
+                break;
+            }
+            src = fixed;
+        }
+        Ok(src)
+    }
+}
+
+/// How many times [`Linter::fix`] re-runs the rules over the fixed source.
+const MAX_FIX_PASSES: usize = 10;
+
+/// Apply the fixes of `diagnostics` to `src`, the source they were found in, returning
+/// the fixed source and the number of fixes applied. A fix overlapping one of an earlier
+/// diagnostic is skipped, it may be found again on the fixed source.
+pub fn apply_fixes(src: &str, diagnostics: &[LintDiagnostic]) -> (String, usize) {
+    let mut edits: Vec<(Range<usize>, String)> = vec![];
+    let mut applied = 0;
+    for fix in diagnostics.iter().filter_map(|d| d.fix.as_ref()) {
+        let overlaps = |a: &Range<usize>, b: &Range<usize>| (a.start < b.end && b.start < a.end) || a.start == b.start;
+        if fix.edits.iter().any(|(range, _)| edits.iter().any(|(other, _)| overlaps(range, other))) {
+            continue;
+        }
+        edits.extend(fix.edits.iter().cloned());
+        applied += 1;
+    }
+    (apply_edits(src, edits), applied)
+}
+
+/// Run the built-in rules configured by `options` over `input`.
+pub fn lint(input: &str, options: &LintOptions) -> Result<Vec<LintDiagnostic>> {
+    let root = crate::parse(input)?;
+    Ok(Linter::new(options.clone()).check(&root, input))
+}
+
+/// Apply the fixes of the built-in rules configured by `options` to `input`.
+pub fn fix(input: &str, options: &LintOptions) -> Result<String> {
+    Linter::new(options.clone()).fix(input)
+}
@@ -0,0 +1,452 @@
+//! The built-in lint rules.
+
+use std::collections::HashSet;
+
+use crate::ast::Node;
+use crate::info::PositionInfo;
+use crate::macros::visit_lists;
+
+use super::{Context, Fix, Rule, Severity};
+
+/// All the built-in rules.
+pub fn builtin() -> Vec<Box<dyn Rule>> {
+    vec![
+        Box::new(DollarDisplayMath),
+        Box::new(RefTie),
+        Box::new(Over),
+        Box::new(Ellipsis),
+        Box::new(Quotes),
+        Box::new(DefInBody),
+        Box::new(LeftRight),
+        Box::new(ObsoleteFont),
+    ]
+}
+
+/// Call `f` on every list of sibling nodes under `node` with whether it is in math mode.
+fn visit_mode_lists<'a>(node: &'a Node, math: bool, f: &mut impl FnMut(&'a [Node], bool)) {
+    let math = math || matches!(node, Node::InlineMath { .. } | Node::DisplayMath { .. } | Node::MathEnv { .. });
+    let content = node.content();
+    if !content.is_empty() {
+        f(content, math);
+    }
+    for child in content {
+        visit_mode_lists(child, math, f);
+    }
+    for arg in node.args() {
+        visit_mode_lists(arg, math, f);
+    }
+}
+
+fn is_macro(node: Option<&Node>, names: &[&str]) -> bool {
+    matches!(node, Some(Node::Macro { content, .. }) if names.contains(&content.as_str()))
+}
+
+fn is_string(node: Option<&Node>, s: &str) -> bool {
+    matches!(node, Some(Node::String { content, .. }) if content == s)
+}
+
+/// `$$ ... $$` is plain TeX and breaks the spacing of LaTeX display math.
+pub struct DollarDisplayMath;
+
+impl Rule for DollarDisplayMath {
+    fn id(&self) -> &'static str {
+        "dollar-display-math"
+    }
+
+    fn description(&self) -> &'static str {
+        "use \\[ ... \\] instead of $$ ... $$"
+    }
+
+    fn check(&self, cx: &mut Context<'_>) {
+        let root = cx.root;
+        visit_lists(root, &mut |siblings| {
+            for node in siblings {
+                if let Node::DisplayMath { position, .. } = node {
+                    let text = cx.text(position);
+                    if text.starts_with("$$") {
+                        let range = cx.range(position);
+                        let message = "use `\\[ ... \\]` instead of `$$ ... $$`";
+                        if text.len() >= 4 && text.ends_with("$$") {
+                            let fix = Fix {
+                                edits: vec![
+                                    (range.start..range.start + 2, "\\[".to_string()),
+                                    (range.end - 2..range.end, "\\]".to_string()),
+                                ],
+                            };
+                            cx.report_fix(position, message, fix);
+                        } else {
+                            cx.report(position, message);
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Macros which should be tied to the preceding word.
+const TIE_MACROS: &[&str] = &["ref", "eqref", "pageref", "cite"];
+
+/// A line break between a word and the reference following it looks wrong.
+pub struct RefTie;
+
+impl Rule for RefTie {
+    fn id(&self) -> &'static str {
+        "ref-tie"
+    }
+
+    fn description(&self) -> &'static str {
+        "use ~ between a word and \\ref or \\cite"
+    }
+
+    fn check(&self, cx: &mut Context<'_>) {
+        let root = cx.root;
+        visit_mode_lists(root, false, &mut |siblings, math| {
+            if math {
+                return;
+            }
+            for i in 2..siblings.len() {
+                let Node::Macro { content, position, .. } = &siblings[i] else {
+                    continue;
+                };
+                let word = matches!(&siblings[i - 2], Node::String { content, .. } if content.chars().all(char::is_alphanumeric));
+                let Node::WhiteSpace { position: space, .. } = &siblings[i - 1] else {
+                    continue;
+                };
+                if TIE_MACROS.contains(&content.as_str()) && word {
+                    let fix = Fix::replace(cx.range(space), "~");
+                    cx.report_fix(position, format!("use `~` instead of a space before `\\{content}`"), fix);
+                }
+            }
+        });
+    }
+}
+
+/// Plain TeX fraction primitives, with their LaTeX replacement.
+const FRACTIONS: &[(&str, &str)] = &[("over", "\\frac"), ("atop", "\\genfrac"), ("choose", "\\binom")];
+
+/// `\over` and friends are plain TeX primitives which do not mix with LaTeX.
+pub struct Over;
+
+impl Rule for Over {
+    fn id(&self) -> &'static str {
+        "over"
+    }
+
+    fn description(&self) -> &'static str {
+        "use \\frac instead of \\over"
+    }
+
+    fn check(&self, cx: &mut Context<'_>) {
+        let root = cx.root;
+        visit_lists(root, &mut |siblings| {
+            for node in siblings {
+                let Node::Macro { content, position, .. } = node else {
+                    continue;
+                };
+                if let Some((name, replacement)) = FRACTIONS.iter().find(|(name, _)| name == content) {
+                    cx.report(position, format!("use `{replacement}` instead of `\\{name}`"));
+                }
+            }
+        });
+    }
+}
+
+/// Three periods are spaced too tightly, `\dots` spaces them properly.
+pub struct Ellipsis;
+
+impl Rule for Ellipsis {
+    fn id(&self) -> &'static str {
+        "ellipsis"
+    }
+
+    fn description(&self) -> &'static str {
+        "use \\dots instead of ..."
+    }
+
+    fn check(&self, cx: &mut Context<'_>) {
+        let root = cx.root;
+        visit_lists(root, &mut |siblings| {
+            let mut i = 0;
+            while i + 2 < siblings.len() {
+                let dots = &siblings[i..i + 3];
+                let adjacent = dots.windows(2).all(|pair| match (pair[0].position(), pair[1].position()) {
+                    (Some(a), Some(b)) => a.end.offset == b.start.offset,
+                    _ => false,
+                });
+                if dots.iter().all(|node| is_string(Some(node), ".")) && adjacent {
+                    let (Some(first), Some(last)) = (dots[0].position(), dots[2].position()) else {
+                        break;
+                    };
+                    let position = PositionInfo { start: first.start.clone(), end: last.end.clone() };
+                    // `\dots` would swallow a following space or merge with a following letter
+                    let range = cx.range(&position);
+                    let delimit = cx.src[range.end..].starts_with(|c: char| c.is_alphabetic() || c.is_whitespace());
+                    let fix = Fix::replace(range, if delimit { "\\dots{}" } else { "\\dots" });
+                    cx.report_fix(&position, "use `\\dots` instead of `...`", fix);
+                    i += 3;
+                } else {
+                    i += 1;
+                }
+            }
+        });
+    }
+}
+
+/// `"` typesets as a closing quote; LaTeX quotes are written ``` `` ``` and `''`.
+pub struct Quotes;
+
+impl Rule for Quotes {
+    fn id(&self) -> &'static str {
+        "quotes"
+    }
+
+    fn description(&self) -> &'static str {
+        "use `` and '' instead of \""
+    }
+
+    fn check(&self, cx: &mut Context<'_>) {
+        let root = cx.root;
+        visit_mode_lists(root, false, &mut |siblings, math| {
+            if math {
+                return;
+            }
+            for node in siblings {
+                if let Node::String { content, position, .. } = node {
+                    let message = "use ``` `` ``` and `''` instead of `\"`";
+                    if content == "\"" {
+                        // a quote opens after a space or bracket, and closes after anything else
+                        let range = cx.range(position);
+                        let opening = cx.src[..range.start].ends_with(|c: char| c.is_whitespace() || "([{".contains(c))
+                            || range.start == 0;
+                        cx.report_fix(position, message, Fix::replace(range, if opening { "``" } else { "''" }));
+                    } else if content.contains('"') {
+                        cx.report(position, message);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Macros defining macros without checking for existing ones.
+const DEFS: &[&str] = &["def", "gdef", "edef", "xdef"];
+
+/// Definitions belong in the preamble, and `\def` silently overwrites existing macros.
+pub struct DefInBody;
+
+impl Rule for DefInBody {
+    fn id(&self) -> &'static str {
+        "def-in-body"
+    }
+
+    fn description(&self) -> &'static str {
+        "do not use \\def in the document body"
+    }
+
+    fn check(&self, cx: &mut Context<'_>) {
+        let root = cx.root;
+        visit_lists(root, &mut |siblings| {
+            for node in siblings {
+                if !matches!(node, Node::Environment { env, .. } if env == "document") {
+                    continue;
+                }
+                visit_lists(node, &mut |body| {
+                    for node in body {
+                        if let Node::Macro { content, position, .. } = node {
+                            if DEFS.contains(&content.as_str()) {
+                                cx.report(position, format!("`\\{content}` in the document body, define macros in the preamble"));
+                            }
+                        }
+                    }
+                });
+            }
+        });
+    }
+}
+
+/// Every `\left` needs a `\right` in the same formula, and conversely.
+pub struct LeftRight;
+
+impl Rule for LeftRight {
+    fn id(&self) -> &'static str {
+        "left-right"
+    }
+
+    fn description(&self) -> &'static str {
+        "\\left and \\right must be paired"
+    }
+
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, cx: &mut Context<'_>) {
+        let root = cx.root;
+        visit_mode_lists(root, false, &mut |siblings, math| {
+            if !math {
+                return;
+            }
+            let mut open = vec![];
+            for node in siblings {
+                let Node::Macro { position, .. } = node else {
+                    continue;
+                };
+                if is_macro(Some(node), &["left"]) {
+                    open.push(position);
+                } else if is_macro(Some(node), &["right"]) && open.pop().is_none() {
+                    cx.report(position, "`\\right` without a matching `\\left`");
+                }
+            }
+            for position in open {
+                cx.report(position, "`\\left` without a matching `\\right`");
+            }
+        });
+    }
+}
+
+/// A LaTeX 2.09 font switch with its LaTeX 2e replacements.
+struct Font {
+    name: &'static str,
+    /// Command taking the text as argument.
+    command: Option<&'static str>,
+    /// Declaration switching the font until the end of the group.
+    declaration: Option<&'static str>,
+    /// Command taking a formula as argument.
+    math: Option<&'static str>,
+}
+
+const fn font(name: &'static str, command: &'static str, declaration: &'static str, math: Option<&'static str>) -> Font {
+    Font { name, command: Some(command), declaration: Some(declaration), math }
+}
+
+const OBSOLETE_FONTS: &[Font] = &[
+    font("bf", "textbf", "bfseries", Some("mathbf")),
+    font("it", "textit", "itshape", Some("mathit")),
+    font("rm", "textrm", "rmfamily", Some("mathrm")),
+    font("sf", "textsf", "sffamily", Some("mathsf")),
+    font("tt", "texttt", "ttfamily", Some("mathtt")),
+    font("sl", "textsl", "slshape", None),
+    font("sc", "textsc", "scshape", None),
+    Font { name: "cal", command: None, declaration: None, math: Some("mathcal") },
+];
+
+/// The two-letter font switches do not combine, e.g. `{\bf\it x}` is not bold.
+///
+/// `{\bf x}` is fixed to `\textbf{x}`, or `\mathbf{x}` in math, unless a macro before the
+/// group may take it as argument, and a switch elsewhere to its declaration, e.g. `\bfseries`.
+pub struct ObsoleteFont;
+
+impl ObsoleteFont {
+    fn lookup(node: &Node) -> Option<&'static Font> {
+        match node {
+            Node::Macro { content, .. } => OBSOLETE_FONTS.iter().find(|font| font.name == content),
+            _ => None,
+        }
+    }
+}
+
+impl Rule for ObsoleteFont {
+    fn id(&self) -> &'static str {
+        "obsolete-font"
+    }
+
+    fn description(&self) -> &'static str {
+        "use \\textbf, \\bfseries... instead of \\bf, \\it..."
+    }
+
+    fn check(&self, cx: &mut Context<'_>) {
+        let root = cx.root;
+        // switches opening a group, which is visited before its content
+        let mut grouped = HashSet::new();
+        visit_mode_lists(root, false, &mut |siblings, math| {
+            for (i, node) in siblings.iter().enumerate() {
+                let Some(position) = node.position() else {
+                    continue;
+                };
+                let font = match node {
+                    Node::Group { content, .. } => {
+                        let Some((font, switch)) = content.first().and_then(|n| Some((ObsoleteFont::lookup(n)?, n))) else {
+                            continue;
+                        };
+                        let Some(switch_position) = switch.position() else {
+                            continue;
+                        };
+                        grouped.insert(switch_position.start.offset);
+                        let command = if math { font.math } else { font.command };
+                        let message = obsolete_message(font, math);
+                        let Some(command) = command else {
+                            cx.report(switch_position, message);
+                            continue;
+                        };
+                        if may_take_argument(&siblings[..i]) {
+                            // `\mytitle{\bf x}` → `\mytitle{\bfseries x}`, as `\mytitle\textbf{x}` would
+                            // give `\textbf` to `\mytitle`
+                            match font.declaration {
+                                Some(declaration) if !math => {
+                                    let fix = Fix::replace(cx.range(switch_position), format!("\\{declaration}"));
+                                    cx.report_fix(switch_position, message, fix);
+                                }
+                                _ => cx.report(switch_position, message),
+                            }
+                            continue;
+                        }
+                        // `{\bf x}` → `\textbf{x}`, dropping the spaces ending the switch
+                        let group = cx.range(position);
+                        let mut end = cx.range(switch_position).end;
+                        if let Some(Node::WhiteSpace { position: space, .. }) = content.get(1) {
+                            end = cx.range(space).end;
+                        }
+                        let fix = Fix {
+                            edits: vec![
+                                (group.start..group.start + 1, format!("\\{command}{{")),
+                                (group.start + 1..end, String::new()),
+                            ],
+                        };
+                        cx.report_fix(switch_position, message, fix);
+                        continue;
+                    }
+                    _ => ObsoleteFont::lookup(node),
+                };
+                let Some(font) = font else {
+                    continue;
+                };
+                if grouped.contains(&position.start.offset) {
+                    continue;
+                }
+                let message = obsolete_message(font, math);
+                match font.declaration {
+                    Some(declaration) if !math => {
+                        let fix = Fix::replace(cx.range(position), format!("\\{declaration}"));
+                        cx.report_fix(position, message, fix);
+                    }
+                    _ => cx.report(position, message),
+                }
+            }
+        });
+    }
+}
+
+/// Whether the node following `before` may be read as the argument of a macro before it:
+/// the last of `before`, whitespace aside, is a macro other than a font switch, or what
+/// may be one of its arguments.
+fn may_take_argument(before: &[Node]) -> bool {
+    let previous = before.iter().rev().find(|node| !matches!(node, Node::WhiteSpace { .. } | Node::Comment { .. }));
+    match previous {
+        Some(node @ Node::Macro { .. }) => ObsoleteFont::lookup(node).is_none(),
+        Some(Node::Group { .. }) => true,
+        previous => is_string(previous, "]") || is_string(previous, "*"),
+    }
+}
+
+fn obsolete_message(font: &Font, math: bool) -> String {
+    let replacement = match (math, font.command, font.declaration) {
+        (true, ..) | (_, None, _) => font.math.map(|math| format!("`\\{math}`")),
+        (false, Some(command), Some(declaration)) => Some(format!("`\\{command}` or `\\{declaration}`")),
+        (false, Some(command), None) => Some(format!("`\\{command}`")),
+    };
+    match replacement {
+        Some(replacement) => format!("`\\{}` is obsolete, use {replacement}", font.name),
+        None => format!("`\\{}` is obsolete", font.name),
+    }
+}
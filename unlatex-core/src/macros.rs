@@ -0,0 +1,135 @@
+//! Reading the arguments of macro calls.
+//!
+//! The parser only attaches arguments to the macros it knows the signature of. For the
+//! other ones, the arguments are left as the nodes following the macro: a [`Group`](Node::Group)
+//! for a mandatory argument, and `[`, ..., `]` strings for an optional one.
+//! [`macro_call`] reads them in both cases, in the owned and the
+//! [borrowed](crate::ast::borrowed) trees alike.
+
+use crate::ast::{to_latex, Node, Tree};
+
+/// A macro call with its arguments.
+#[derive(Debug, Clone)]
+pub struct MacroCall<'a, N = Node> {
+    /// The macro node.
+    pub node: &'a N,
+    /// Name of the macro, without escape token.
+    pub name: &'a str,
+    /// Whether the macro is followed by a `*`.
+    pub star: bool,
+    /// Content of the optional `[` `]` arguments, in order.
+    pub optional: Vec<&'a [N]>,
+    /// Content of the mandatory `{` `}` arguments, in order.
+    pub mandatory: Vec<&'a [N]>,
+    /// Number of nodes following the macro which hold its arguments, when the parser
+    /// did not attach them.
+    pub consumed: usize,
+}
+
+impl<N: Tree> MacroCall<'_, N> {
+    /// Text of the last mandatory argument.
+    pub fn last_text(&self) -> Option<String> {
+        self.mandatory.last().map(|content| text(content))
+    }
+}
+
+/// Text of `nodes`, as trimmed LaTeX source.
+pub fn text<N: Tree>(nodes: &[N]) -> String {
+    to_latex(nodes).trim().to_string()
+}
+
+/// Split a comma separated list of keys, such as the argument of `\cite` or `\cref`.
+pub fn keys<N: Tree>(nodes: &[N]) -> Vec<String> {
+    text(nodes)
+        .split(',')
+        .map(str::trim)
+        .filter(|key| !key.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn is_string<N: Tree>(node: Option<&N>, s: &str) -> bool {
+    node.and_then(N::string) == Some(s)
+}
+
+/// Read the call of the macro `siblings[i]` expecting `mandatory` mandatory arguments.
+///
+/// Returns `None` if `siblings[i]` is not a macro. Missing mandatory arguments are left
+/// out of [`MacroCall::mandatory`].
+pub fn macro_call<N: Tree>(siblings: &[N], i: usize, mandatory: usize) -> Option<MacroCall<'_, N>> {
+    let node = siblings.get(i)?;
+    let (name, args) = (node.macro_name()?, node.args());
+    let mut call = MacroCall {
+        node,
+        name,
+        star: false,
+        optional: vec![],
+        mandatory: vec![],
+        consumed: 0,
+    };
+
+    if args.iter().any(|arg| !arg.content().is_empty() || arg.open_mark() != Some("")) {
+        for arg in args {
+            match arg.open_mark() {
+                Some("[") => call.optional.push(arg.content()),
+                Some("{") => call.mandatory.push(arg.content()),
+                Some("") if is_string(arg.content().first(), "*") => call.star = true,
+                _ => {}
+            }
+        }
+        return Some(call);
+    }
+
+    let mut j = i + 1;
+    let skip_whitespace = |mut j: usize| {
+        while siblings.get(j).is_some_and(N::is_whitespace) {
+            j += 1;
+        }
+        j
+    };
+    if is_string(siblings.get(j), "*") {
+        call.star = true;
+        j += 1;
+        call.consumed = j - i - 1;
+    }
+    loop {
+        let start = skip_whitespace(j);
+        if call.mandatory.len() < mandatory && is_string(siblings.get(start), "[") {
+            let Some(end) = (start + 1..siblings.len()).find(|&k| is_string(siblings.get(k), "]")) else {
+                break;
+            };
+            call.optional.push(&siblings[start + 1..end]);
+            j = end + 1;
+            call.consumed = j - i - 1;
+        } else if call.mandatory.len() < mandatory {
+            match siblings.get(start) {
+                Some(group) if group.is_group() => {
+                    call.mandatory.push(group.content());
+                    j = start + 1;
+                    call.consumed = j - i - 1;
+                }
+                _ => break,
+            }
+        } else {
+            break;
+        }
+    }
+    Some(call)
+}
+
+/// Call `f` on every list of sibling nodes of the tree rooted at `node`: the content
+/// of each node and of each argument.
+///
+/// A list is visited before the lists nested in its nodes.
+pub fn visit_lists<'a, N: Tree>(node: &'a N, f: &mut impl FnMut(&'a [N])) {
+    let content = node.content();
+    if !content.is_empty() {
+        f(content);
+    }
+    for child in content {
+        visit_lists(child, f);
+    }
+    for arg in node.args() {
+        visit_lists(arg, f);
+    }
+}
@@ -0,0 +1,250 @@
+//! Marking up the changes between revisions of a document, like latexdiff.
+//!
+//! [`mark_changes`] returns the new revision of a document with the [edits](crate::diff)
+//! from the old one marked up: inserted words are wrapped in `\DIFadd{...}` and deleted
+//! words put back in `\DIFdel{...}`. Other nodes, such as commands, math and environments
+//! whose content is not text, are marked as a whole between `\DIFaddbegin` and
+//! `\DIFaddend`, or `\DIFdelbegin` and `\DIFdelend`, so they are never broken apart.
+//!
+//! Only the body of the document is marked up, the preamble being that of the new
+//! revision with the definitions of [`PREAMBLE`] before `\begin{document}`. Deleted labels,
+//! alignment tabs and row breaks are left out so that references and tables keep working.
+//! Deleted commands stepping a counter, such as `\section` or `\item`, and deleted floats
+//! with a `\caption` are commented out on `%DIFDELCMD` lines, and deleted numbered equations
+//! are typeset unnumbered, so sections, items, floats and equations keep the numbers of the
+//! new revision.
+//!
+//! ```
+//! use unlatex::markup::mark_changes;
+//!
+//! let old = "\\begin{document}\nThe old result is $a = b$.\n\\end{document}\n";
+//! let new = "\\begin{document}\nThe new result is $a = c$.\n\\end{document}\n";
+//! let marked = mark_changes(old, new).unwrap();
+//! assert!(marked.ends_with(
+//!     "The \\DIFdel{old} \\DIFadd{new} result is \\DIFdelbegin $a = b$\\DIFdelend{} \\DIFaddbegin $a = c$\\DIFaddend{}.\n\\end{document}\n"
+//! ));
+//! ```
+
+use std::ops::Range;
+
+use crate::ast::Node;
+use crate::compare::Source;
+use crate::diff::{changes, Edit, Place, ALIGNMENT};
+use crate::{parse, Result};
+
+/// Definitions of the markup commands, added to the preamble of the new revision.
+pub const PREAMBLE: &str = r"%DIF PREAMBLE
+\makeatletter
+\@ifpackageloaded{ulem}{}{\RequirePackage[normalem]{ulem}}
+\makeatother
+\RequirePackage{color}
+\providecommand{\DIFadd}[1]{{\protect\color{blue}\uwave{#1}}}
+\providecommand{\DIFdel}[1]{{\protect\color{red}\sout{#1}}}
+\providecommand{\DIFaddbegin}{\begingroup\protect\color{blue}}
+\providecommand{\DIFaddend}{\endgroup}
+\providecommand{\DIFdelbegin}{\begingroup\protect\color{red}}
+\providecommand{\DIFdelend}{\endgroup}
+%DIF END PREAMBLE
+";
+
+/// Commands stepping a counter, commented out when deleted.
+const COUNTER_COMMANDS: &[&str] = &[
+    "part", "chapter", "section", "subsection", "subsubsection", "paragraph", "subparagraph",
+    "item", "caption", "footnote",
+];
+
+/// Math environments numbering their lines, typeset with their starred variant when deleted.
+const NUMBERED_MATH: &[&str] = &["equation", "align", "gather", "multline", "flalign", "alignat", "eqnarray"];
+
+/// Environments whose content is code rather than text, marked up as a whole.
+const CODE_ENVIRONMENTS: &[&str] = &["tikzpicture", "pgfpicture", "axis", "circuitikz", "forest"];
+
+/// Order of the marks inserted at the same offset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Rank {
+    Close,
+    Deleted,
+    Open,
+}
+
+/// The new revision `new` of a document with its changes from `old` marked up.
+pub fn mark_changes(old: &str, new: &str) -> Result<String> {
+    let (old_source, new_source) = (Source::new(old), Source::new(new));
+    let (old_root, new_root) = (parse(&old_source.text)?, parse(&new_source.text)?);
+    let (old_node, new_node, preamble) = match (document(&old_root), document(&new_root)) {
+        (Some(old_body), Some(new_body)) => (old_body, new_body, new_source.range(Some(new_body), 0).start),
+        _ => (&old_root, &new_root, 0),
+    };
+    let mut marker = Marker { old, new, marks: vec![(preamble, Rank::Close, PREAMBLE.to_string())] };
+    for change in changes(&old_source, &new_source, old_node, new_node, is_code) {
+        match change.edit {
+            Edit::Insert { new, .. } => marker.insert(new, change.text),
+            Edit::Delete { old, .. } => marker.delete(old, change.text, change.at),
+            Edit::Update { old, new, .. } => {
+                marker.delete(old, change.text, Place { offset: new.start, after: false });
+                marker.insert(new, change.text);
+            }
+            Edit::Move { old, new, .. } => {
+                marker.delete(old, false, change.at);
+                marker.insert(new, false);
+            }
+        }
+    }
+
+    let mut marks = marker.marks;
+    marks.sort_by_key(|(offset, rank, _)| (*offset, *rank));
+    let mut marked = String::with_capacity(new.len() + marks.iter().map(|(_, _, mark)| mark.len()).sum::<usize>());
+    let mut last = 0;
+    for (offset, _, mark) in marks {
+        marked.push_str(&new[last..offset]);
+        marked.push_str(&mark);
+        last = offset;
+    }
+    marked.push_str(&new[last..]);
+    Ok(marked)
+}
+
+fn document(root: &Node) -> Option<&Node> {
+    root.content().iter().find(|node| matches!(node, Node::Environment { env, .. } if env == "document"))
+}
+
+fn is_code(node: &Node) -> bool {
+    matches!(node, Node::Environment { env, .. } if CODE_ENVIRONMENTS.contains(&env.as_str()))
+}
+
+/// Collects the marks to insert in the new revision, with their offset.
+struct Marker<'a> {
+    old: &'a str,
+    new: &'a str,
+    marks: Vec<(usize, Rank, String)>,
+}
+
+impl Marker<'_> {
+    /// Mark the nodes at `range` of the new revision as inserted.
+    fn insert(&mut self, range: Range<usize>, text: bool) {
+        let content = &self.new[range.clone()];
+        if text {
+            for words in words(content) {
+                self.marks.push((range.start + words.start, Rank::Open, "\\DIFadd{".to_string()));
+                self.marks.push((range.start + words.end, Rank::Close, "}".to_string()));
+            }
+        } else if !is_alignment(content) {
+            self.marks.push((range.start, Rank::Open, "\\DIFaddbegin ".to_string()));
+            self.marks.push((range.end, Rank::Close, "\\DIFaddend{}".to_string()));
+        }
+    }
+
+    /// Put back the nodes at `range` of the old revision, marked as deleted, `at` the new one.
+    fn delete(&mut self, range: Range<usize>, text: bool, at: Place) {
+        let content = &self.old[range];
+        if !text && steps_counter(content) {
+            return self.comment_out(content, at);
+        }
+        let deleted = if text {
+            let words: Vec<String> = words(content).into_iter().map(|words| format!("\\DIFdel{{{}}}", &content[words])).collect();
+            words.join(" ")
+        } else if is_alignment(content) || command(content) == Some("label") {
+            String::new()
+        } else {
+            format!("\\DIFdelbegin {}\\DIFdelend{{}}", unnumbered(content))
+        };
+        if deleted.is_empty() {
+            return;
+        }
+        let deleted = match self.new.contains("\r\n") {
+            true => deleted,
+            false => deleted.replace("\r\n", "\n"),
+        };
+        let mark = match at.after {
+            true => format!(" {deleted}"),
+            false => format!("{deleted} "),
+        };
+        self.marks.push((at.offset, Rank::Deleted, mark));
+    }
+
+    /// Put back the deleted `content` of the old revision `at` the new one on `%DIFDELCMD`
+    /// comment lines, ending the line it follows with a comment and reusing the end of the
+    /// line it is put before, so no paragraph break is added.
+    fn comment_out(&mut self, content: &str, at: Place) {
+        let newline = if self.new.contains("\r\n") { "\r\n" } else { "\n" };
+        // the lines are indented like the one the content is put in
+        let before = &self.new[self.new[..at.offset].rfind('\n').map_or(0, |i| i + 1)..at.offset];
+        let indent = &before[..before.len() - before.trim_start_matches([' ', '\t']).len()];
+        let lines: Vec<String> = content.lines().map(|line| format!("%DIFDELCMD < {line}")).collect();
+        let commented = lines.join(&format!("{newline}{indent}"));
+        let mark = match at.after {
+            true if self.new[at.offset..].starts_with(['\r', '\n']) || at.offset == self.new.len() => format!(" %{newline}{indent}{commented}"),
+            true => format!(" %{newline}{indent}{commented}{newline}{indent}"),
+            false if indent == before => format!("{commented}{newline}{indent}"),
+            false => format!("{commented}{newline}"),
+        };
+        self.marks.push((at.offset, Rank::Deleted, mark));
+    }
+}
+
+/// Name of the command starting `content`, such as `section` for `\section{Intro}`.
+fn command(content: &str) -> Option<&str> {
+    let name = content.strip_prefix('\\')?;
+    let end = name.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(name.len());
+    match end {
+        0 => name.get(..name.chars().next()?.len_utf8()),
+        end => Some(&name[..end]),
+    }
+}
+
+/// Whether the deleted `content` would step a counter of the new revision: a command of
+/// [`COUNTER_COMMANDS`], or a float with a caption.
+fn steps_counter(content: &str) -> bool {
+    command(content).is_some_and(|name| COUNTER_COMMANDS.contains(&name))
+        || (command(content) == Some("begin") && content.contains("\\caption"))
+}
+
+/// `content` with its numbered math environments starred and its labels left out.
+fn unnumbered(content: &str) -> String {
+    let mut content = content.to_string();
+    for env in NUMBERED_MATH {
+        for (numbered, starred) in [(format!("\\begin{{{env}}}"), format!("\\begin{{{env}*}}")), (format!("\\end{{{env}}}"), format!("\\end{{{env}*}}"))] {
+            content = content.replace(&numbered, &starred);
+        }
+    }
+    while let Some(start) = content.find("\\label{") {
+        let end = content[start..].find('}').map_or(content.len(), |end| start + end + 1);
+        content.replace_range(start..end, "");
+    }
+    content
+}
+
+/// Whether `content` is a command ending a row of a table or drawing a rule, never marked
+/// up as a group around it breaks the table.
+fn is_alignment(content: &str) -> bool {
+    command(content).is_some_and(|name| ALIGNMENT.contains(&name))
+}
+
+/// Byte ranges of the runs of words of `text`, a run of text nodes, which can be marked
+/// up: those between paragraph breaks and alignment tabs, without comments at their end.
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut current: Option<Range<usize>> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '%' => {
+                while chars.next_if(|&(_, c)| c != '\n').is_some() {}
+            }
+            '&' => runs.extend(current.take()),
+            '\n' => {
+                let next_line = text[i + 1..].trim_start_matches([' ', '\t', '\r']);
+                if next_line.starts_with('\n') {
+                    runs.extend(current.take());
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let end = i + c.len_utf8();
+                current = Some(current.map_or(i..end, |run| run.start..end));
+            }
+        }
+    }
+    runs.extend(current);
+    runs
+}
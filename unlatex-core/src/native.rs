@@ -0,0 +1,722 @@
+//! A parser written in Rust, producing the same trees as the unified-latex parser
+//! without going through the JavaScript engine.
+//!
+//! [`parse`](crate::parse) uses it when the `native-parser` feature is enabled, and
+//! [`parse_borrowed`](crate::parse_borrowed) builds its [borrowed tree](crate::ast::borrowed)
+//! directly, slicing the text of the nodes from the input as the grammar reads it.
+//!
+//! ```
+//! use unlatex::ast::Node;
+//!
+//! let ast = unlatex::native::parse(r"\textbf{bold} $x^2$").unwrap();
+//! let Node::Macro { content, args, .. } = &ast.content()[0] else { panic!() };
+//! assert_eq!((content.as_str(), args.len()), ("textbf", 1));
+//! assert_eq!(ast.to_latex(), r"\textbf{bold} $x^{2}$");
+//! ```
+//!
+//! The document goes through the same steps as in unified-latex:
+//!
+//! 1. the grammar splits it into tokens, groups, environments and math;
+//! 2. macro names are extended with `@` between `\makeatletter` and `\makeatother`, and
+//!    with `_` and `:` between `\ExplSyntaxOn` and `\ExplSyntaxOff`;
+//! 3. the arguments of the macros and environments of the [known packages](db) are
+//!    attached, math arguments and environments first;
+//! 4. the content of math arguments and environments parsed as text is parsed again in
+//!    math mode, with positions relative to the printed content;
+//! 5. list environments are split into items, and whitespace is trimmed around the content
+//!    of environments and math.
+//!
+//! The trees differ from the JavaScript parser on a few inputs:
+//!
+//! - characters outside of the Basic Multilingual Plane are kept whole where the
+//!   JavaScript parser splits UTF-16 surrogate pairs, e.g. in math mode;
+//! - a null character is parsed as a string;
+//! - letters extending macro names after `\makeatletter` are alphabetic characters
+//!   rather than the Unicode letter category;
+//! - documents nested too deeply are rejected with [`Error::TooDeep`](crate::Error::TooDeep).
+
+mod args;
+mod db;
+mod grammar;
+
+use std::borrow::Cow;
+
+use crate::ast::{self, borrowed::Node};
+use crate::info::{PositionInfo, RenderInfo};
+use crate::Result;
+
+/// Rendering hints of a known macro or environment, see [`RenderInfo`].
+#[derive(Debug, Clone, Copy)]
+struct Render {
+    align_content: bool,
+    in_par_mode: bool,
+    pgfkeys_args: bool,
+    break_around: bool,
+    in_math_mode: bool,
+    hanging_indent: bool,
+    named_arguments: &'static [Option<&'static str>],
+}
+
+impl Render {
+    const NONE: Render = Render {
+        align_content: false,
+        in_par_mode: false,
+        pgfkeys_args: false,
+        break_around: false,
+        in_math_mode: false,
+        hanging_indent: false,
+        named_arguments: &[],
+    };
+
+    /// Add these hints to the `render_info` of a node.
+    fn merge(&self, render_info: &mut Option<RenderInfo>) {
+        let info = render_info.get_or_insert_with(|| RenderInfo {
+            align_content: false,
+            in_par_mode: false,
+            pgfkeys_args: false,
+            break_around: false,
+            in_math_mode: false,
+            hanging_indent: false,
+            named_arguments: vec![],
+        });
+        info.align_content |= self.align_content;
+        info.in_par_mode |= self.in_par_mode;
+        info.pgfkeys_args |= self.pgfkeys_args;
+        info.break_around |= self.break_around;
+        info.in_math_mode |= self.in_math_mode;
+        info.hanging_indent |= self.hanging_indent;
+        if !self.named_arguments.is_empty() {
+            info.named_arguments = self.named_arguments.iter().map(|name| name.map(String::from)).collect();
+        }
+    }
+}
+
+/// A known macro.
+#[derive(Debug, Clone, Copy)]
+struct Macro {
+    /// Arguments as an xparse signature.
+    signature: Option<&'static str>,
+    render: Option<Render>,
+    /// Escape token of the macro, if it is not `\`.
+    escape_token: Option<&'static str>,
+}
+
+/// A known environment.
+#[derive(Debug, Clone, Copy)]
+struct Env {
+    /// Arguments as an xparse signature.
+    signature: Option<&'static str>,
+    render: Option<Render>,
+    process: Process,
+}
+
+/// How the content of an environment is restructured.
+#[derive(Debug, Clone, Copy)]
+enum Process {
+    None,
+    /// Whitespace around the content is trimmed.
+    Trim,
+    /// The content is split into items introduced by the given macro.
+    Items(&'static str),
+}
+
+/// Which known macros and environments are handled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// Only the ones whose content is math.
+    Math,
+    All,
+}
+
+fn lookup<T>(table: &'static [(&str, T)], name: &str) -> Option<&'static T> {
+    let i = table.binary_search_by_key(&name, |(name, _)| name).ok()?;
+    Some(&table[i].1)
+}
+
+fn is_math(render: Option<Render>) -> bool {
+    render.is_some_and(|render| render.in_math_mode)
+}
+
+fn macro_info(node: &Node, mode: Mode) -> Option<&'static Macro> {
+    let Node::Macro { content, escape_token, .. } = node else {
+        return None;
+    };
+    let info = lookup(db::MACROS, content)?;
+    if info.escape_token.is_some_and(|token| escape_token.as_deref() != Some(token)) {
+        return None;
+    }
+    (mode == Mode::All || is_math(info.render)).then_some(info)
+}
+
+fn env_info(node: &Node, mode: Mode) -> Option<&'static Env> {
+    let (Node::Environment { env, .. } | Node::MathEnv { env, .. }) = node else {
+        return None;
+    };
+    let info = lookup(db::ENVIRONMENTS, env)?;
+    (mode == Mode::All || is_math(info.render)).then_some(info)
+}
+
+/// Parse LaTeX document.
+pub fn parse(input: &str) -> Result<ast::Node> {
+    parse_borrowed(input).map(Node::into_owned)
+}
+
+/// Parse LaTeX document into a tree [borrowing its text](crate::ast::borrowed) from `input`.
+///
+/// Only the text of math parsed again from its printed content is owned.
+pub fn parse_borrowed(input: &str) -> Result<Node<'_>> {
+    let mut root = grammar::document(input)?;
+    let Node::Root { content, .. } = &mut root else {
+        unreachable!("the grammar returns a root");
+    };
+    at_letter(content, input);
+    attach(content, Mode::Math);
+    reparse_math(&mut root)?;
+    let Node::Root { content, .. } = &mut root else {
+        unreachable!("the grammar returns a root");
+    };
+    attach(content, Mode::All);
+    trim_contents(&mut root);
+    if let Node::Root { content, .. } = &mut root {
+        trim(content);
+    }
+    Ok(root)
+}
+
+/// The lists of child nodes of `node`, in the order unified-latex visits them.
+fn lists_mut<'a, 'src>(node: &'a mut Node<'src>) -> Vec<&'a mut Vec<Node<'src>>> {
+    match node {
+        Node::Root { content, .. }
+        | Node::DisplayMath { content, .. }
+        | Node::Group { content, .. }
+        | Node::InlineMath { content, .. }
+        | Node::Argument { content, .. } => vec![content],
+        Node::Environment { content, args, .. } | Node::MathEnv { content, args, .. } => vec![content, args],
+        Node::Macro { args, .. } => vec![args],
+        _ => vec![],
+    }
+}
+
+fn is_macro(node: &Node, name: &str) -> bool {
+    matches!(node, Node::Macro { content, .. } if content == name)
+}
+
+/// A range of nodes from a macro switching letters on to the macro switching them off,
+/// or to the end of the list.
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    /// `None` for a macro switching letters off without switching them on first.
+    start: Option<usize>,
+    end: usize,
+    /// Whether `_` and `:` are letters, rather than `@`.
+    expl: bool,
+}
+
+fn regions(nodes: &[Node], start: &str, end: &str, expl: bool) -> Vec<Region> {
+    let mut regions = vec![];
+    let mut open = None;
+    for (i, node) in nodes.iter().enumerate() {
+        if is_macro(node, start) {
+            open = Some(i);
+        }
+        if is_macro(node, end) {
+            regions.push(Region { start: open.take(), end: i + 1, expl });
+        }
+    }
+    if open.is_some() {
+        regions.push(Region { start: open, end: nodes.len(), expl });
+    }
+    regions
+}
+
+/// Sort `regions` by start like V8 does in unified-latex, leaving the regions without
+/// start wherever the sort puts them.
+fn sort_regions(regions: &mut [Region]) {
+    let less = |a: &Region, b: &Region| matches!((a.start, b.start), (Some(a), Some(b)) if a < b);
+    let len = regions.len();
+    if len < 2 {
+        return;
+    }
+    // the leading run, reversed if descending
+    let mut run = 2;
+    if less(&regions[1], &regions[0]) {
+        while run < len && less(&regions[run], &regions[run - 1]) {
+            run += 1;
+        }
+        regions[..run].reverse();
+    } else {
+        while run < len && !less(&regions[run], &regions[run - 1]) {
+            run += 1;
+        }
+    }
+    for i in run..len {
+        let (mut low, mut high) = (0, i);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if less(&regions[i], &regions[mid]) {
+                high = mid;
+            } else {
+                low = mid + 1;
+            }
+        }
+        regions[low..=i].rotate_right(1);
+    }
+}
+
+/// Extend macro names with `@` in `\makeatletter` regions and with `_` and `:` in
+/// `\ExplSyntaxOn` regions, in `nodes` and the lists under them.
+fn at_letter<'src>(nodes: &mut Vec<Node<'src>>, source: &'src str) {
+    for node in nodes.iter_mut() {
+        for list in lists_mut(node) {
+            at_letter(list, source);
+        }
+    }
+    let mut regions = regions(nodes, "ExplSyntaxOn", "ExplSyntaxOff", true);
+    regions.extend(self::regions(nodes, "makeatletter", "makeatother", false));
+    if regions.is_empty() {
+        return;
+    }
+    sort_regions(&mut regions);
+
+    // the letters of each span between the boundaries of the regions
+    let mut bounds: Vec<usize> = regions.iter().flat_map(|region| region.start.into_iter().chain([region.end])).collect();
+    bounds.sort_unstable();
+    bounds.dedup();
+    let mut spans: Vec<(usize, usize, &str)> = vec![];
+    let mut first = 0;
+    for pair in bounds.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let mut within = vec![];
+        let mut reached = false;
+        for (i, region) in regions.iter().enumerate().skip(first) {
+            reached |= region.end >= start;
+            if !reached {
+                first = i + 1;
+                continue;
+            }
+            if region.start.is_some_and(|s| s > end) {
+                break;
+            }
+            if region.start.is_some_and(|s| s <= start) && region.end >= end {
+                within.push(region.expl);
+            }
+        }
+        let letters = match within[..] {
+            [] => continue,
+            [_, _] => "_:@",
+            [true] => "_:",
+            _ => "@",
+        };
+        if end - start > 1 {
+            spans.push((start, end, letters));
+        }
+    }
+    if spans.is_empty() {
+        return;
+    }
+
+    let mut bounds: Vec<usize> = vec![0, nodes.len()];
+    bounds.extend(spans.iter().flat_map(|&(start, end, _)| [start, end]));
+    bounds.sort_unstable();
+    bounds.dedup();
+    let mut rest = std::mem::take(nodes);
+    let mut offset = 0;
+    for pair in bounds.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        let mut segment: Vec<Node> = rest.drain(..end - offset).collect();
+        offset = end;
+        if let Some(&(_, _, letters)) = spans.iter().find(|span| (span.0, span.1) == (start, end)) {
+            extend_macro_names(&mut segment, letters, source);
+        }
+        nodes.append(&mut segment);
+    }
+}
+
+/// Append the `letters` and alphabetic characters of the strings following macros to
+/// their names, in `nodes` and the lists under them.
+fn extend_macro_names<'src>(nodes: &mut Vec<Node<'src>>, letters: &str, source: &'src str) {
+    let is_letter = |c: char| c.is_alphabetic() || letters.contains(c);
+    let mut i = 0;
+    while i + 1 < nodes.len() {
+        let (head, tail) = nodes.split_at_mut(i + 1);
+        let (
+            Node::Macro { content: name, escape_token, position, .. },
+            Node::String { content: text, position: text_position, .. },
+        ) = (&mut head[i], &mut tail[0])
+        else {
+            i += 1;
+            continue;
+        };
+        let joins = name.chars().last().is_some_and(|c| letters.contains(c))
+            || text.chars().next().is_some_and(|c| letters.contains(c));
+        if !joins || escape_token.as_deref().is_some_and(|token| token != "\\") {
+            i += 1;
+            continue;
+        }
+        let len = text.find(|c: char| !is_letter(c)).unwrap_or(text.len());
+        if len == 0 {
+            i += 1;
+        } else if len == text.len() {
+            *name = join(name, text, len, source);
+            position.end = text_position.end.clone();
+            nodes.remove(i + 1);
+        } else {
+            let width = text[..len].encode_utf16().count();
+            *name = join(name, text, len, source);
+            match text {
+                Cow::Borrowed(text) => *text = &text[len..],
+                Cow::Owned(text) => {
+                    text.drain(..len);
+                }
+            }
+            position.end.offset += width;
+            position.end.column += width;
+            text_position.start.offset += width;
+            text_position.start.column += width;
+        }
+    }
+    for node in nodes {
+        for list in lists_mut(node) {
+            extend_macro_names(list, letters, source);
+        }
+    }
+}
+
+/// `name` followed by the first `len` bytes of `text`, still sliced from `source` if
+/// both are and `text` follows `name` there.
+fn join<'src>(name: &Cow<'src, str>, text: &Cow<'src, str>, len: usize, source: &'src str) -> Cow<'src, str> {
+    // the byte offset of a slice of `source`
+    let offset = |text: &str| {
+        let offset = (text.as_ptr() as usize).checked_sub(source.as_ptr() as usize)?;
+        (offset + text.len() <= source.len()).then_some(offset)
+    };
+    if let (Cow::Borrowed(name), Cow::Borrowed(text)) = (name, text) {
+        if let (Some(start), Some(end)) = (offset(name), offset(text)) {
+            if start + name.len() == end {
+                return Cow::Borrowed(&source[start..end + len]);
+            }
+        }
+    }
+    Cow::Owned(format!("{name}{}", &text[..len]))
+}
+
+/// Attach the arguments of the known macros and environments in `nodes` and under them.
+fn attach(nodes: &mut Vec<Node>, mode: Mode) {
+    attach_macro_args(nodes, mode);
+    for node in nodes.iter_mut() {
+        for list in lists_mut(node) {
+            attach(list, mode);
+        }
+        if let Some(info) = env_info(node, mode) {
+            process_environment(node, info);
+        }
+    }
+}
+
+/// Attach the arguments following the known macros of `nodes`, from the last one so that
+/// macros can be arguments of the macros preceding them.
+fn attach_macro_args(nodes: &mut Vec<Node>, mode: Mode) {
+    let mut i = nodes.len();
+    while i > 0 {
+        i -= 1;
+        let Some(info) = macro_info(&nodes[i], mode) else {
+            continue;
+        };
+        let Node::Macro { args, render_info, .. } = &mut nodes[i] else {
+            continue;
+        };
+        if let Some(render) = &info.render {
+            render.merge(render_info);
+        }
+        let Some(signature) = info.signature else {
+            continue;
+        };
+        if !args.is_empty() {
+            continue;
+        }
+        let taken = args::take(nodes, signature, i + 1);
+        if let Node::Macro { args, .. } = &mut nodes[i] {
+            *args = taken;
+        }
+    }
+}
+
+fn process_environment(node: &mut Node, info: &Env) {
+    let (Node::Environment { args, content, render_info, .. } | Node::MathEnv { args, content, render_info, .. }) = node else {
+        return;
+    };
+    if let Some(signature) = info.signature {
+        if args.is_empty() {
+            *args = args::take(content, signature, 0);
+        }
+    }
+    if let Some(render) = &info.render {
+        render.merge(render_info);
+    }
+    match info.process {
+        Process::None => {}
+        Process::Trim => trim(content),
+        Process::Items(name) => *content = items(std::mem::take(content), name),
+    }
+}
+
+/// Whether `nodes` were parsed as math already: text has no words, superscripts or
+/// subscripts.
+fn is_math_ready(nodes: &[Node]) -> bool {
+    !nodes.iter().any(|node| match node {
+        Node::String { content, .. } => content.encode_utf16().count() > 1 || content == "^" || content == "_",
+        _ => false,
+    })
+}
+
+/// Parse again in math mode the content of the math macros and environments parsed as text.
+fn reparse_math(node: &mut Node) -> Result<()> {
+    let is_math_macro = macro_info(node, Mode::Math).is_some();
+    let is_math_env = env_info(node, Mode::Math).is_some();
+    match node {
+        Node::Macro { args, .. } if is_math_macro => {
+            for arg in args {
+                if let Node::Argument { content, .. } = arg {
+                    if !content.is_empty() && !is_math_ready(content) {
+                        *content = math(content)?;
+                    }
+                }
+            }
+        }
+        Node::Environment { content, .. } | Node::MathEnv { content, .. } if is_math_env && !is_math_ready(content) => {
+            *content = math(content)?;
+        }
+        _ => {}
+    }
+    for list in lists_mut(node) {
+        for child in list {
+            reparse_math(child)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parse `nodes` again in math mode from their printed source, which the text of the
+/// new nodes is owned from.
+fn math(nodes: &[Node]) -> Result<Vec<Node<'static>>> {
+    Ok(grammar::math(&print_raw(nodes))?.into_iter().map(Node::into_static).collect())
+}
+
+/// Print `nodes` back to source, the way they are parsed again in math mode.
+fn print_raw(nodes: &[Node]) -> String {
+    let mut out = String::new();
+    nodes.iter().for_each(|node| write_raw(node, &mut out));
+    out
+}
+
+fn write_raw(node: &Node, out: &mut String) {
+    let write_all = |nodes: &[Node], out: &mut String| nodes.iter().for_each(|node| write_raw(node, out));
+    match node {
+        Node::Root { content, .. } => write_all(content, out),
+        Node::Argument { open_mark, close_mark, content, .. } => {
+            out.push_str(open_mark);
+            write_all(content, out);
+            out.push_str(close_mark);
+        }
+        Node::Comment { content, sameline, leading_whitespace, suffix_parbreak, .. } => {
+            if !sameline {
+                out.push('\n');
+            } else if *leading_whitespace {
+                out.push(' ');
+            }
+            out.push('%');
+            out.push_str(content);
+            if !suffix_parbreak {
+                out.push('\n');
+            }
+        }
+        Node::Environment { env, args, content, .. } | Node::MathEnv { env, args, content, .. } => {
+            out.push_str(&format!("\\begin{{{env}}}"));
+            write_all(args, out);
+            write_all(content, out);
+            out.push_str(&format!("\\end{{{env}}}"));
+        }
+        Node::VerbatimEnvironment { env, args, content, .. } => {
+            out.push_str(&format!("\\begin{{{env}}}"));
+            write_all(args, out);
+            out.push_str(content);
+            out.push_str(&format!("\\end{{{env}}}"));
+        }
+        Node::DisplayMath { content, .. } => {
+            out.push_str("\\[");
+            write_all(content, out);
+            out.push_str("\\]");
+        }
+        Node::Group { content, .. } => {
+            out.push('{');
+            write_all(content, out);
+            out.push('}');
+        }
+        Node::InlineMath { content, .. } => {
+            out.push('$');
+            write_all(content, out);
+            out.push('$');
+        }
+        Node::Macro { content, args, escape_token, .. } => {
+            out.push_str(escape_token.as_deref().unwrap_or("\\"));
+            out.push_str(content);
+            write_all(args, out);
+        }
+        Node::Parbreak { .. } => out.push_str("\n\n"),
+        Node::String { content, .. } => out.push_str(content),
+        Node::Verb { env, escape, content, .. } => {
+            out.push('\\');
+            out.push_str(env);
+            out.push_str(escape);
+            out.push_str(content);
+            out.push_str(escape);
+        }
+        Node::WhiteSpace { .. } => out.push(' '),
+        Node::Error => {}
+    }
+}
+
+fn is_blank(node: &Node) -> bool {
+    matches!(node, Node::WhiteSpace { .. } | Node::Parbreak { .. })
+}
+
+/// Remove the whitespace and paragraph breaks starting `nodes`, returning how many.
+///
+/// Comments starting the list lose their leading whitespace, and become comments on their
+/// own line if anything was removed before them.
+fn trim_start(nodes: &mut Vec<Node>) -> usize {
+    let start = nodes.iter().take_while(|node| is_blank(node)).count();
+    nodes.drain(..start);
+    for node in nodes.iter_mut() {
+        let Node::Comment { sameline, leading_whitespace, position, .. } = node else {
+            break;
+        };
+        if *leading_whitespace || *sameline {
+            *leading_whitespace = false;
+            *position = PositionInfo::default();
+        }
+        if start > 0 && *sameline {
+            *sameline = false;
+            *position = PositionInfo::default();
+        }
+    }
+    start
+}
+
+/// Remove the whitespace and paragraph breaks ending `nodes`.
+///
+/// Comments ending the list are not followed by a paragraph break anymore.
+fn trim_end(nodes: &mut Vec<Node>) {
+    if nodes.iter().all(is_blank) {
+        return;
+    }
+    let end = nodes.iter().rev().take_while(|node| is_blank(node)).count();
+    nodes.truncate(nodes.len() - end);
+    for node in nodes.iter_mut().rev() {
+        let Node::Comment { sameline, leading_whitespace, suffix_parbreak, position, .. } = node else {
+            break;
+        };
+        *suffix_parbreak = false;
+        if *leading_whitespace && !*sameline {
+            *leading_whitespace = false;
+            *position = PositionInfo::default();
+        }
+    }
+}
+
+fn trim(nodes: &mut Vec<Node>) {
+    trim_start(nodes);
+    trim_end(nodes);
+}
+
+/// Trim the content of environments and math under `node`, keeping a comment on the line
+/// of `\begin` in place.
+fn trim_contents(node: &mut Node) {
+    if let Node::DisplayMath { content, .. }
+    | Node::InlineMath { content, .. }
+    | Node::Environment { content, .. }
+    | Node::MathEnv { content, .. } = node
+    {
+        if let Some(Node::Comment { sameline: true, suffix_parbreak, .. }) = content.first_mut() {
+            *suffix_parbreak = false;
+            trim_end(content);
+            let mut rest = content.split_off(1);
+            trim_start(&mut rest);
+            content.append(&mut rest);
+        } else {
+            trim(content);
+        }
+    }
+    for list in lists_mut(node) {
+        list.iter_mut().for_each(trim_contents);
+    }
+}
+
+/// Split the content of a list environment into the `name` macros introducing items, with
+/// the content of each item as their last argument.
+fn items<'src>(content: Vec<Node<'src>>, name: &str) -> Vec<Node<'src>> {
+    let mut segments = vec![vec![]];
+    let mut items = vec![];
+    for node in content {
+        if is_macro(&node, name) {
+            items.push(node);
+            segments.push(vec![]);
+        } else {
+            segments.last_mut().expect("segments are not empty").push(node);
+        }
+    }
+    for (i, segment) in segments.iter_mut().enumerate() {
+        if i == 0 {
+            trim_end(segment);
+        } else {
+            trim(segment);
+            if !segment.is_empty() {
+                segment.insert(0, Node::WhiteSpace { position: PositionInfo::default(), render_info: None });
+            }
+        }
+    }
+
+    let mut segments = segments.into_iter();
+    let mut out = segments.next().expect("segments are not empty");
+    let preamble = !out.is_empty();
+    for (i, (mut item, mut body)) in items.into_iter().zip(segments).enumerate() {
+        // comments ending an item follow it instead
+        let trailing = match body.iter().rposition(|node| !matches!(node, Node::WhiteSpace { .. } | Node::Comment { .. } | Node::Parbreak { .. })) {
+            Some(last) => body.split_off(last + 1),
+            None => std::mem::take(&mut body),
+        };
+        if let Node::Macro { args, render_info, .. } = &mut item {
+            args.push(Node::Argument {
+                open_mark: "".into(),
+                close_mark: "".into(),
+                content: body,
+                position: PositionInfo::default(),
+                render_info: None,
+            });
+            Render { in_par_mode: true, ..Render::NONE }.merge(render_info);
+        }
+        if i > 0 || preamble {
+            out.push(Node::Parbreak { position: PositionInfo::default(), render_info: None });
+        }
+        out.push(item);
+        out.extend(trailing);
+    }
+
+    for i in 1..out.len() {
+        if !matches!(out[i], Node::Parbreak { .. }) {
+            continue;
+        }
+        match &mut out[i - 1] {
+            Node::Comment { suffix_parbreak, .. } => *suffix_parbreak = true,
+            Node::Macro { args, .. } => {
+                if let Some(Node::Argument { close_mark, content, .. }) = args.last_mut() {
+                    if let (true, Some(Node::Comment { suffix_parbreak, .. })) = (close_mark.is_empty(), content.last_mut()) {
+                        *suffix_parbreak = true;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    out
+}
@@ -0,0 +1,133 @@
+//! Attaching arguments to macros and environments from their xparse signatures.
+
+use std::borrow::Cow;
+
+use crate::ast::borrowed::Node;
+
+/// How an argument is delimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Mandatory,
+    Optional,
+    /// A `*` following the macro.
+    Star,
+    /// The given token following the macro.
+    Token(char),
+}
+
+/// An argument of a signature, such as `m` or `!d<>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Spec {
+    kind: Kind,
+    open: char,
+    close: char,
+    /// Whether whitespace may not precede the argument.
+    no_leading_whitespace: bool,
+}
+
+/// Parse the signature `m`, `o`, `s`, `d<>`, `r()` and `t+` arguments of the xparse
+/// `signature`, optionally prefixed with `!` or `+`.
+fn parse(signature: &str) -> Vec<Spec> {
+    let mut specs = vec![];
+    let mut chars = signature.chars();
+    let mut no_leading_whitespace = false;
+    while let Some(c) = chars.next() {
+        let (kind, open, close) = match c {
+            '!' => {
+                no_leading_whitespace = true;
+                continue;
+            }
+            'm' => (Kind::Mandatory, '{', '}'),
+            'o' => (Kind::Optional, '[', ']'),
+            's' => (Kind::Star, '*', '*'),
+            'r' | 'd' => {
+                let (Some(open), Some(close)) = (chars.next(), chars.next()) else {
+                    break;
+                };
+                (if c == 'r' { Kind::Mandatory } else { Kind::Optional }, open, close)
+            }
+            't' => {
+                let Some(token) = chars.next() else {
+                    break;
+                };
+                (Kind::Token(token), token, token)
+            }
+            // spaces and `+`, for long arguments
+            _ => continue,
+        };
+        specs.push(Spec { kind, open, close, no_leading_whitespace });
+        no_leading_whitespace = false;
+    }
+    specs
+}
+
+fn argument<'src>(content: Vec<Node<'src>>, open_mark: Cow<'src, str>, close_mark: Cow<'src, str>) -> Node<'src> {
+    Node::Argument {
+        open_mark,
+        close_mark,
+        content,
+        position: Default::default(),
+        render_info: None,
+    }
+}
+
+fn is_string(node: &Node, s: char) -> bool {
+    matches!(node, Node::String { content, .. } if content.len() == s.len_utf8() && content.starts_with(s))
+}
+
+/// Text of the string delimiting an argument.
+fn mark<'src>(node: &Node<'src>) -> Cow<'src, str> {
+    match node {
+        Node::String { content, .. } => content.clone(),
+        _ => "".into(),
+    }
+}
+
+/// Take the argument described by `spec` from the `nodes` starting at `start`, removing
+/// the nodes it spans.
+///
+/// Like xparse, a mandatory argument may be a single token rather than a group, but
+/// optional arguments end at the first closing delimiter even when nested.
+fn gobble<'src>(nodes: &mut Vec<Node<'src>>, spec: Spec, start: usize) -> Option<Node<'src>> {
+    let mut i = start;
+    if !spec.no_leading_whitespace {
+        while matches!(nodes.get(i), Some(Node::WhiteSpace { .. })) {
+            i += 1;
+        }
+    }
+    let next = nodes.get(i)?;
+    if matches!(next, Node::Comment { .. } | Node::Parbreak { .. }) {
+        return None;
+    }
+    let braces = spec.open == '{' && spec.close == '}';
+    let (argument, end) = match spec.kind {
+        Kind::Mandatory if braces => {
+            let content = match next {
+                Node::Group { content, .. } => content.clone(),
+                node => vec![node.clone()],
+            };
+            (argument(content, "{".into(), "}".into()), i + 1)
+        }
+        Kind::Mandatory | Kind::Optional => match next {
+            Node::Group { content, .. } if braces => (argument(content.clone(), "{".into(), "}".into()), i + 1),
+            node if is_string(node, spec.open) => {
+                let end = i + 1 + nodes[i + 1..].iter().position(|node| is_string(node, spec.close))?;
+                (argument(nodes[i + 1..end].to_vec(), mark(node), mark(&nodes[end])), end + 1)
+            }
+            _ => return None,
+        },
+        Kind::Star | Kind::Token(_) if is_string(next, spec.open) => (argument(vec![next.clone()], "".into(), "".into()), i + 1),
+        Kind::Star | Kind::Token(_) => return None,
+    };
+    nodes.drain(start..end);
+    Some(argument)
+}
+
+/// Take the arguments of `signature` from the `nodes` starting at `start`; missing
+/// arguments are empty.
+pub(super) fn take<'src>(nodes: &mut Vec<Node<'src>>, signature: &str, start: usize) -> Vec<Node<'src>> {
+    parse(signature)
+        .into_iter()
+        .map(|spec| gobble(nodes, spec, start).unwrap_or_else(|| argument(vec![], "".into(), "".into())))
+        .collect()
+}
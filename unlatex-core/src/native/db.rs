@@ -0,0 +1,506 @@
+//! Macros and environments of the unified-latex packages, whose arguments the
+//! [native parser](super) attaches.
+//!
+//! Generated from the vendored parser by `node js/native-db.js > src/native/db.rs`.
+
+use super::{Env, Macro, Process, Render};
+
+/// Known macros, sorted by name.
+pub(super) const MACROS: &[(&str, Macro)] = &[
+    ("Alph", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("ArrowBetweenLines", Macro { signature: Some("s o"), render: None, escape_token: None }),
+    ("AtBeginLecture", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("AtBeginPart", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("Bdd", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("CorrectChoiceEmphasis", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("Cpageref", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("Cref", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("Crefrange", Macro { signature: Some("s m m"), render: None, escape_token: None }),
+    ("DeclareDocumentCommand", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("DeclareDocumentEnvironment", Macro { signature: Some("m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("DeclareExpandableDocumentCommand", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("DeclareMathOperator", Macro { signature: Some("s m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("DeclareMathSizes", Macro { signature: Some("m m m m"), render: None, escape_token: None }),
+    ("DeclareOption", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("DeclarePairedDelimiter", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("DeclarePairedDelimiterX", Macro { signature: Some("m o m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("DeclarePairedDelimiterXPP", Macro { signature: Some("m o m m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("DefineNamedColor", Macro { signature: Some("m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("NewDocumentCommand", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("NewDocumentEnvironment", Macro { signature: Some("m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("NewExpandableDocumentCommand", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("NiceMatrixOptions", Macro { signature: Some("m"), render: Some(Render { pgfkeys_args: true, break_around: true, ..Render::NONE }), escape_token: None }),
+    ("ProvideDocumentCommand", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("ProvideDocumentEnvironment", Macro { signature: Some("m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("ProvideExpandableDocumentCommand", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("RenewDocumentCommand", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("RenewDocumentEnvironment", Macro { signature: Some("m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("RenewExpandableDocumentCommand", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("RequirePackage", Macro { signature: Some("o m"), render: Some(Render { pgfkeys_args: true, break_around: true, ..Render::NONE }), escape_token: None }),
+    ("Roman", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("SolutionEmphasis", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("\\", Macro { signature: Some("!s o"), render: None, escape_token: None }),
+    ("^", Macro { signature: Some("m"), render: None, escape_token: Some("") }),
+    ("_", Macro { signature: Some("m"), render: None, escape_token: Some("") }),
+    ("abstract", Macro { signature: Some("m"), render: Some(Render { break_around: true, in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("action", Macro { signature: Some("d<> m"), render: None, escape_token: None }),
+    ("addcontentsline", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("addtocontents", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("addtocounter", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("addtolength", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("againframe", Macro { signature: Some("d<> o o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("alph", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("alsoname", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("alt", Macro { signature: Some("d<> m m d<>"), render: None, escape_token: None }),
+    ("animate", Macro { signature: Some("r<>"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("animatevalue", Macro { signature: Some("r<> m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("answerline", Macro { signature: Some("o"), render: None, escape_token: None }),
+    ("appendix", Macro { signature: None, render: Some(Render { break_around: true, in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("arabic", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("author", Macro { signature: Some("m"), render: Some(Render { break_around: true, in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("autopageref", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("autoref", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("backmatter", Macro { signature: None, render: Some(Render { break_around: true, in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("beamerdefaultoverlayspecification", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("belowpdfbookmark", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("bibitem", Macro { signature: Some("s d<> o m"), render: Some(Render { hanging_indent: true, named_arguments: &[Some("starred"), None, Some("tocTitle"), Some("title")], ..Render::NONE }), escape_token: None }),
+    ("bibliography", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("bibliographystyle", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("bigbreak", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("bigskip", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("blendcolors", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("bold", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("bonuspointformat", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("bonuspointpoints", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("boxframe", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("caption", Macro { signature: Some("o m"), render: Some(Render { in_par_mode: true, break_around: true, ..Render::NONE }), escape_token: None }),
+    ("centering", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("chapter", Macro { signature: Some("s o m"), render: Some(Render { break_around: true, in_par_mode: true, named_arguments: &[Some("starred"), Some("tocTitle"), Some("title")], ..Render::NONE }), escape_token: None }),
+    ("checkboxchar", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("checkedchar", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("cite", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("clap", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("cleardoublepage", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("clearpage", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("color", Macro { signature: Some("o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("colorbox", Macro { signature: Some("o m m"), render: None, escape_token: None }),
+    ("colorlet", Macro { signature: Some("o m o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("column", Macro { signature: Some("d<> o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("columnbreak", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("contentsline", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("convertcolorspec", Macro { signature: Some("m m m m"), render: None, escape_token: None }),
+    ("cpageref", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("cramped", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("crampedclap", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("crampedllap", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("crampedrlap", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("crampedsubstack", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("cref", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("crefalias", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("crefdefaultlabelformat", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("crefname", Macro { signature: Some("m m m"), render: None, escape_token: None }),
+    ("crefrange", Macro { signature: Some("s m m"), render: None, escape_token: None }),
+    ("crefrangeconjunction", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("currentpdfbookmark", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("date", Macro { signature: Some("o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("definecolor", Macro { signature: Some("o m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("definecolors", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("definecolorseries", Macro { signature: Some("m m m o m o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("definecolorset", Macro { signature: Some("o m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("discretionary", Macro { signature: Some("m m m"), render: None, escape_token: None }),
+    ("documentclass", Macro { signature: Some("o m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("doublespacing", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("emph", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("enlargethispage", Macro { signature: Some("s"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("ensuremath", Macro { signature: Some("m"), render: Some(Render { in_math_mode: true, ..Render::NONE }), escape_token: None }),
+    ("extractcolorspec", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("extractcolorspecs", Macro { signature: Some("m m m"), render: None, escape_token: None }),
+    ("extrawidth", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("fbox", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("fcolorbox", Macro { signature: Some("o m o m m"), render: None, escape_token: None }),
+    ("fillin", Macro { signature: Some("o o"), render: None, escape_token: None }),
+    ("fillwidthdottedlines", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("fillwidthgrid", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("fillwidthlines", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("fnsymbol", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("footnote", Macro { signature: Some("o m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("footnotemark", Macro { signature: Some("o"), render: None, escape_token: None }),
+    ("footnotetext", Macro { signature: Some("o m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("frac", Macro { signature: Some("m m"), render: Some(Render { in_math_mode: true, ..Render::NONE }), escape_token: None }),
+    ("frak", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("frame", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("framebox", Macro { signature: Some("o o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("framesubtitle", Macro { signature: Some("d<> m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("frametitle", Macro { signature: Some("d<> o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("framezoom", Macro { signature: Some("r<> r<> o r() r()"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("frontmatter", Macro { signature: None, render: Some(Render { break_around: true, in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("fullwidth", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("geometry", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("hphantom", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("href", Macro { signature: Some("o m m"), render: None, escape_token: None }),
+    ("hspace", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("hyperbaseurl", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("hypercalcbp", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("hyperdef", Macro { signature: Some("m m m"), render: None, escape_token: None }),
+    ("hyperimage", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("hyperlink", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("hyperlinkmute", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("hyperlinksound", Macro { signature: Some("o m m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("hyperref", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("hypersetup", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("hypertarget", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("hyphenation", Macro { signature: Some("m m m"), render: None, escape_token: None }),
+    ("include", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("includegraphics", Macro { signature: Some("s o o m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("includeonly", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("indent", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("index", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("input", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertauthor", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertnavigation", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertsectionnavigation", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertsectionnavigationhorizontal", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertshortauthor", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertshortdate", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertshortinstitute", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertshortpart", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertshorttitle", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertsubsectionnavigation", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertsubsectionnavigationhorizontal", Macro { signature: Some("m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("insertverticalnavigation", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("invisible", Macro { signature: Some("d<> m"), render: None, escape_token: None }),
+    ("item", Macro { signature: Some("d<> o d<>"), render: Some(Render { hanging_indent: true, named_arguments: &[None, Some("label"), None], ..Render::NONE }), escape_token: None }),
+    ("keywords", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("label", Macro { signature: Some("d<> o m"), render: None, escape_token: None }),
+    ("labelcpageref", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("labelcref", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("lcnamecref", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("lcnamecrefs", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("lecture", Macro { signature: Some("o m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("linebreak", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("logo", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("mainmatter", Macro { signature: None, render: Some(Render { break_around: true, in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("makebox", Macro { signature: Some("d() o o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("makeemptybox", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("maketitle", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("marginpar", Macro { signature: Some("o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("marginpointname", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("maskcolors", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("mathbb", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathbf", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathcal", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathclap", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("mathfrak", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathit", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathllap", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("mathmakebox", Macro { signature: Some("o o m"), render: None, escape_token: None }),
+    ("mathmbox", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathnormal", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathrlap", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("mathrm", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathscr", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathsf", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mathtoolsset", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("mathtt", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("mbox", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("medbreak", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("medskip", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("mode", Macro { signature: Some("s d<> d{}"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("multicolumn", Macro { signature: Some("m m m"), render: None, escape_token: None }),
+    ("nameCref", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("nameCrefs", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("namecref", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("namecrefs", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("newcommand", Macro { signature: Some("s d<> +m o +o +m"), render: Some(Render { break_around: true, named_arguments: &[Some("starred"), None, Some("name"), Some("numArgs"), Some("default"), Some("body")], ..Render::NONE }), escape_token: None }),
+    ("newcounter", Macro { signature: Some("m o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("newenvironment", Macro { signature: Some("s d<> m o o m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("newfont", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("newgathered", Macro { signature: Some("m m m m"), render: None, escape_token: None }),
+    ("newlength", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("newline", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("newpage", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("newsavebox", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("newtagform", Macro { signature: Some("m o m m"), render: None, escape_token: None }),
+    ("newtheorem", Macro { signature: Some("s m o m o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("newtheoremstyle", Macro { signature: Some("m m m m m m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("noindent", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("nolinebreak", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("nolinkurl", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("nopagebreak", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("nopagecolor", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("only", Macro { signature: Some("d<> m d<>"), render: None, escape_token: None }),
+    ("onslide", Macro { signature: Some("t+ t* d<> d{}"), render: None, escape_token: None }),
+    ("operatorname", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("overbrace", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("overbracket", Macro { signature: Some("o o m"), render: None, escape_token: None }),
+    ("pagebreak", Macro { signature: Some("o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("pagecolor", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("pagenumbering", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("pageref", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("pagestyle", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("paragraph", Macro { signature: Some("s o m"), render: Some(Render { break_around: true, in_par_mode: true, named_arguments: &[Some("starred"), Some("tocTitle"), Some("title")], ..Render::NONE }), escape_token: None }),
+    ("parbox", Macro { signature: Some("o o o m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("part", Macro { signature: Some("s d<> o m"), render: Some(Render { break_around: true, named_arguments: &[Some("starred"), None, Some("tocTitle"), Some("title")], ..Render::NONE }), escape_token: None }),
+    ("partpage", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("pause", Macro { signature: Some("o"), render: None, escape_token: None }),
+    ("pdfbookmark", Macro { signature: Some("o m m"), render: None, escape_token: None }),
+    ("pdfstringdef", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("pgfkeys", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("pgfplotsset", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("pgfplotstabletypeset", Macro { signature: Some("o m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("phantom", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("pointformat", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("pointname", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("pointpoints", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("preparecolor", Macro { signature: Some("o m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("preparecolorset", Macro { signature: Some("o m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("prescript", Macro { signature: Some("m m m"), render: None, escape_token: None }),
+    ("printbibliography", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("providecolor", Macro { signature: Some("o m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("providecolors", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("providecolorset", Macro { signature: Some("o m m m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("providecommand", Macro { signature: Some("s +m o +o +m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("qformat", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("raisebox", Macro { signature: Some("m o o m"), render: None, escape_token: None }),
+    ("ref", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("reflectbox", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("refstepcounter", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("renewcommand", Macro { signature: Some("s d<> +m o +o +m"), render: Some(Render { break_around: true, named_arguments: &[Some("starred"), None, Some("name"), Some("numArgs"), Some("default"), Some("body")], ..Render::NONE }), escape_token: None }),
+    ("renewenvironment", Macro { signature: Some("s d<> m o o m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("renewgathered", Macro { signature: Some("m m m m"), render: None, escape_token: None }),
+    ("renewtagform", Macro { signature: Some("m o m m"), render: None, escape_token: None }),
+    ("resetcolorseries", Macro { signature: Some("o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("resetcounteronoverlays", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("resetcountonoverlays", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("resizebox", Macro { signature: Some("s m m m"), render: None, escape_token: None }),
+    ("roman", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("rotatebox", Macro { signature: Some("o m m"), render: None, escape_token: None }),
+    ("rowcolors", Macro { signature: Some("s o m m m"), render: None, escape_token: None }),
+    ("rule", Macro { signature: Some("o m m"), render: None, escape_token: None }),
+    ("savebox", Macro { signature: Some("m o o m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("sbox", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("scalebox", Macro { signature: Some("m o m"), render: None, escape_token: None }),
+    ("section", Macro { signature: Some("s d<> o m"), render: Some(Render { break_around: true, named_arguments: &[Some("starred"), None, Some("tocTitle"), Some("title")], ..Render::NONE }), escape_token: None }),
+    ("sectionpage", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("see", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("seealso", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("seename", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("selectcolormodel", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("setbeamercolor", Macro { signature: Some("m m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("setbeamersize", Macro { signature: Some("m o o"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("setbeamertemplate", Macro { signature: Some("m o o d{}"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("setcounter", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("setlength", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("settodepth", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("settoheight", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("settowidth", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("shortdotswithin", Macro { signature: Some("s m"), render: None, escape_token: None }),
+    ("shoveleft", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("shoveright", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("singlespacing", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("smallbreak", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("smallskip", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("smashoperator", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("sound", Macro { signature: Some("o m m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("splitdfrac", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("splitfrac", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("sqrt", Macro { signature: Some("o m"), render: Some(Render { in_math_mode: true, ..Render::NONE }), escape_token: None }),
+    ("stackrel", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("stepcounter", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("stretch", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("subject", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("subparagraph", Macro { signature: Some("s o m"), render: Some(Render { break_around: true, in_par_mode: true, named_arguments: &[Some("starred"), Some("tocTitle"), Some("title")], ..Render::NONE }), escape_token: None }),
+    ("subpdfbookmark", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("subsection", Macro { signature: Some("s d<> o m"), render: Some(Render { break_around: true, named_arguments: &[Some("starred"), None, Some("tocTitle"), Some("title")], ..Render::NONE }), escape_token: None }),
+    ("subsectionpage", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("substitutecolormodel", Macro { signature: Some("m m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("subsubsection", Macro { signature: Some("s d<> o m"), render: Some(Render { break_around: true, named_arguments: &[Some("starred"), None, Some("tocTitle"), Some("title")], ..Render::NONE }), escape_token: None }),
+    ("sysaddeqsign", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("sysalign", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("sysautonum", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("syscodeextracol", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("sysdelim", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("syseqivsign", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("syseqsep", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("syseqspace", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("sysextracolonsign", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("syslineskipcoeff", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("sysremoveeqsign", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("syssignspace", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("syssubstitute", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("systeme", Macro { signature: Some("s o o m"), render: Some(Render { in_math_mode: true, ..Render::NONE }), escape_token: None }),
+    ("tableofcontents", Macro { signature: Some("o"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("temporal", Macro { signature: Some("r<> m m m"), render: None, escape_token: None }),
+    ("testcolor", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("texorpdfstring", Macro { signature: Some("m m"), render: None, escape_token: None }),
+    ("text", Macro { signature: Some("m"), render: Some(Render { ..Render::NONE }), escape_token: None }),
+    ("textbf", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textcolor", Macro { signature: Some("o m m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textit", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textmd", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textnormal", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textrm", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textsc", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textsf", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textsl", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("texttt", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("textup", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("thanks", Macro { signature: Some("m"), render: Some(Render { break_around: true, in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("theoremstyle", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("thispagestyle", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("thispdfpagelabel", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("tikzoption", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("tikzstyle", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("title", Macro { signature: Some("o m"), render: Some(Render { break_around: true, in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("titledquestion", Macro { signature: Some("m o"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("titlegraphic", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("totalformat", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("uncover", Macro { signature: Some("d<> m"), render: None, escape_token: None }),
+    ("underbrace", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("underbracket", Macro { signature: Some("o o m"), render: None, escape_token: None }),
+    ("uplevel", Macro { signature: Some("m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("uppercase", Macro { signature: Some("m"), render: Some(Render { in_par_mode: true, ..Render::NONE }), escape_token: None }),
+    ("url", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("usebeamercolor", Macro { signature: Some("s m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("usebeamertemplate", Macro { signature: Some("s m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("usecounter", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("usepackage", Macro { signature: Some("o m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("usetagform", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("usetikzlibrary", Macro { signature: Some("m"), render: Some(Render { break_around: true, pgfkeys_args: true, ..Render::NONE }), escape_token: None }),
+    ("value", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("vdotswithin", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("vfill", Macro { signature: None, render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("visible", Macro { signature: Some("d<> m"), render: None, escape_token: None }),
+    ("vphantom", Macro { signature: Some("m"), render: None, escape_token: None }),
+    ("vspace", Macro { signature: Some("s m"), render: Some(Render { break_around: true, ..Render::NONE }), escape_token: None }),
+    ("xLeftarrow", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("xLeftrightarrow", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("xRightarrow", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("xhookleftarrow", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("xhookrightarrow", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("xleftrightarrow", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("xmapsto", Macro { signature: Some("o m"), render: None, escape_token: None }),
+    ("xmathstrut", Macro { signature: Some("o m"), render: None, escape_token: None }),
+];
+
+/// Known environments, sorted by name.
+pub(super) const ENVIRONMENTS: &[(&str, Env)] = &[
+    ("BNiceArray", Env { signature: Some("o m !o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("BNiceMatrix", Env { signature: Some("!o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("Bmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("Bmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("Bsmallmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("Bsmallmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("NiceArray", Env { signature: Some("o m !o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("NiceArrayWithDelims", Env { signature: Some("m m o m !o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("NiceMatrix", Env { signature: Some("!o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("NiceMatrixBlock", Env { signature: Some("!o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("NiceTabular", Env { signature: Some("o m !o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("VNiceArray", Env { signature: Some("o m !o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("VNiceMatrix", Env { signature: Some("!o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("Vmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("Vmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("Vsmallmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("Vsmallmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("actionenv", Env { signature: Some("!d<>"), render: None, process: Process::None }),
+    ("alertblock", Env { signature: Some("!d<> !d{} !d<>"), render: None, process: Process::None }),
+    ("align", Env { signature: None, render: Some(Render { in_math_mode: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("align*", Env { signature: None, render: Some(Render { in_math_mode: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("alignat", Env { signature: None, render: Some(Render { in_math_mode: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("alignat*", Env { signature: None, render: Some(Render { in_math_mode: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("aligned", Env { signature: None, render: Some(Render { in_math_mode: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("altenv", Env { signature: Some("!d<> m m m m !d<>"), render: None, process: Process::None }),
+    ("array", Env { signature: Some("o m"), render: Some(Render { align_content: true, ..Render::NONE }), process: Process::None }),
+    ("axis", Env { signature: Some("o"), render: Some(Render { pgfkeys_args: true, ..Render::NONE }), process: Process::None }),
+    ("bNiceArray", Env { signature: Some("o m !o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("bNiceMatrix", Env { signature: Some("!o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("block", Env { signature: Some("!d<> !d{} !d<>"), render: None, process: Process::None }),
+    ("bmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("bmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("bsmallmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("bsmallmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("cases", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("cases*", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("checkboxes", Env { signature: Some("o"), render: None, process: Process::Items("choice") }),
+    ("choices", Env { signature: Some("o"), render: None, process: Process::Items("choice") }),
+    ("column", Env { signature: Some("d<> o m"), render: None, process: Process::None }),
+    ("columns", Env { signature: Some("d<> o"), render: None, process: Process::None }),
+    ("corollary", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("crampedsubarray", Env { signature: Some("m"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("dcases", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("dcases*", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("definition", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("description", Env { signature: Some("o"), render: None, process: Process::Items("item") }),
+    ("displaymath", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("document", Env { signature: None, render: None, process: Process::Trim }),
+    ("drcases", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("drcases*", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("enumerate", Env { signature: Some("o"), render: Some(Render { pgfkeys_args: true, ..Render::NONE }), process: Process::Items("item") }),
+    ("equation", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("equation*", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("example", Env { signature: Some("!o"), render: None, process: Process::None }),
+    ("exampleblock", Env { signature: Some("!d<> !d{} !d<>"), render: None, process: Process::None }),
+    ("figure", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("figure*", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("filecontents", Env { signature: Some("o m"), render: None, process: Process::None }),
+    ("filecontents*", Env { signature: Some("o m"), render: None, process: Process::None }),
+    ("flalign", Env { signature: None, render: Some(Render { in_math_mode: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("flalign*", Env { signature: None, render: Some(Render { in_math_mode: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("frame", Env { signature: Some("!d<> !o !o !d{} !d{}"), render: None, process: Process::None }),
+    ("gather", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("gather*", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("itemize", Env { signature: Some("o"), render: None, process: Process::Items("item") }),
+    ("lemma", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("lgathered", Env { signature: Some("o"), render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("list", Env { signature: Some("m m"), render: None, process: Process::Items("item") }),
+    ("math", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("matrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("matrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("minipage", Env { signature: Some("o o o m"), render: None, process: Process::None }),
+    ("multicols", Env { signature: Some("m o o"), render: None, process: Process::None }),
+    ("multicols*", Env { signature: Some("m o o"), render: None, process: Process::None }),
+    ("multilined", Env { signature: Some("o o"), render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("multline", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("multline*", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("oneparcheckboxes", Env { signature: Some("o"), render: None, process: Process::Items("choice") }),
+    ("oneparchoices", Env { signature: Some("o"), render: None, process: Process::Items("choice") }),
+    ("onlyenv", Env { signature: Some("!d<>"), render: None, process: Process::None }),
+    ("overlayarea", Env { signature: Some("m m"), render: None, process: Process::None }),
+    ("overprint", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("pNiceArray", Env { signature: Some("o m !o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("pNiceMatrix", Env { signature: Some("!o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("parts", Env { signature: Some("o"), render: None, process: Process::Items("part") }),
+    ("picture", Env { signature: Some("r() d()"), render: None, process: Process::None }),
+    ("pmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("pmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("proof", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("proposition", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("psmallmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("psmallmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("questions", Env { signature: Some("o"), render: None, process: Process::Items("question") }),
+    ("rcases", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("rcases*", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("remark", Env { signature: Some("!o"), render: None, process: Process::None }),
+    ("rgathered", Env { signature: Some("o"), render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("smallmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("smallmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("split", Env { signature: None, render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("spreadlines", Env { signature: Some("m"), render: Some(Render { in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("subparts", Env { signature: Some("o"), render: None, process: Process::Items("subpart") }),
+    ("subsubparts", Env { signature: Some("o"), render: None, process: Process::Items("subsubpart") }),
+    ("tabbing", Env { signature: None, render: Some(Render { align_content: true, ..Render::NONE }), process: Process::None }),
+    ("table", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("tabular", Env { signature: Some("o m"), render: Some(Render { align_content: true, ..Render::NONE }), process: Process::None }),
+    ("tabular*", Env { signature: Some("m o m"), render: Some(Render { align_content: true, ..Render::NONE }), process: Process::None }),
+    ("testcolors", Env { signature: Some("o"), render: Some(Render { pgfkeys_args: true, ..Render::NONE }), process: Process::None }),
+    ("thebibliography", Env { signature: Some("m"), render: None, process: Process::Items("bibitem") }),
+    ("theorem", Env { signature: Some("o"), render: None, process: Process::None }),
+    ("tikzpicture", Env { signature: Some("o"), render: Some(Render { pgfkeys_args: true, ..Render::NONE }), process: Process::None }),
+    ("trivlist", Env { signature: Some("o"), render: None, process: Process::Items("item") }),
+    ("vNiceArray", Env { signature: Some("o m !o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("vNiceMatrix", Env { signature: Some("!o"), render: Some(Render { pgfkeys_args: true, align_content: true, ..Render::NONE }), process: Process::None }),
+    ("vmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("vmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("vsmallmatrix", Env { signature: None, render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+    ("vsmallmatrix*", Env { signature: Some("o"), render: Some(Render { align_content: true, in_math_mode: true, ..Render::NONE }), process: Process::None }),
+];
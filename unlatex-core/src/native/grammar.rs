@@ -0,0 +1,591 @@
+//! The PEG grammar of unified-latex, turning source into a tree of tokens.
+//!
+//! Rules are tried in the same order as the original grammar so that ambiguous input,
+//! such as unclosed groups, is split the same way. Failed attempts at the rules spanning
+//! the rest of the document are remembered, which keeps unclosed environments from making
+//! the backtracking exponential.
+
+use std::collections::HashSet;
+
+use crate::ast::borrowed::Node;
+use crate::info::{Position, PositionInfo};
+use crate::{Error, Result};
+
+/// Deepest nesting of groups, environments and math accepted.
+const MAX_DEPTH: usize = 256;
+
+/// Environments whose content is not parsed, in the order they are tried.
+const VERBATIM_ENVIRONMENTS: &[&str] = &["verbatim*", "verbatim", "filecontents*", "filecontents", "comment", "lstlisting"];
+
+/// Environments whose content is parsed in math mode, in the order they are tried.
+///
+/// A name is matched as soon as it is a prefix of the environment, so `alignat` is never
+/// recognized: `align` is tried first and then fails on the missing `}`.
+const MATH_ENVIRONMENTS: &[&str] = &[
+    "equation*", "equation", "align*", "align", "alignat*", "alignat", "gather*", "gather",
+    "multline*", "multline", "flalign*", "flalign", "split", "math", "displaymath",
+];
+
+/// Rules whose failures are remembered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Rule {
+    Group,
+    Environment,
+    MathEnvironment,
+    VerbatimEnvironment,
+    InlineMath,
+    DisplayMath,
+    ParenMath,
+    BracketMath,
+}
+
+/// A node and the index of the character following it.
+type Parsed<'src> = Option<(Node<'src>, usize)>;
+
+/// Parse a whole document.
+pub(super) fn document(input: &str) -> Result<Node<'_>> {
+    let mut grammar = Grammar::new(input);
+    let mut content = vec![];
+    let mut p = 0;
+    while let Some((node, end)) = grammar.token(p) {
+        content.push(node);
+        p = end;
+    }
+    grammar.finish()?;
+    let position = grammar.position(0, p);
+    Ok(Node::Root { content, position, render_info: None })
+}
+
+/// Parse `input` in math mode, with positions relative to `input`.
+pub(super) fn math(input: &str) -> Result<Vec<Node<'_>>> {
+    let mut grammar = Grammar::new(input);
+    let mut content = vec![];
+    let mut p = 0;
+    while let Some((node, end)) = grammar.math_token(p) {
+        content.push(node);
+        p = end;
+    }
+    grammar.finish()?;
+    Ok(content)
+}
+
+struct Grammar<'src> {
+    input: &'src str,
+    chars: Vec<char>,
+    /// Byte index of each character, and of the end of the input.
+    bytes: Vec<usize>,
+    /// UTF-16 offset of each character, and of the end of the input.
+    offsets: Vec<usize>,
+    /// Indices of the characters starting a line.
+    line_starts: Vec<usize>,
+    failed: HashSet<(Rule, usize)>,
+    depth: usize,
+    too_deep: bool,
+}
+
+impl<'src> Grammar<'src> {
+    fn new(input: &'src str) -> Grammar<'src> {
+        let (bytes, chars): (Vec<usize>, Vec<char>) = input.char_indices().unzip();
+        let mut bytes = bytes;
+        bytes.push(input.len());
+        let mut offsets = Vec::with_capacity(chars.len() + 1);
+        let mut line_starts = vec![0];
+        let mut offset = 0;
+        for (i, &c) in chars.iter().enumerate() {
+            offsets.push(offset);
+            offset += c.len_utf16();
+            if c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        offsets.push(offset);
+        Grammar { input, chars, bytes, offsets, line_starts, failed: HashSet::new(), depth: 0, too_deep: false }
+    }
+
+    fn finish(&self) -> Result<()> {
+        if self.too_deep {
+            return Err(Error::TooDeep { depth: MAX_DEPTH });
+        }
+        Ok(())
+    }
+
+    fn position(&self, start: usize, end: usize) -> PositionInfo {
+        PositionInfo { start: self.point(start), end: self.point(end) }
+    }
+
+    /// Position of the character `i`; only `\n` starts a line, like in the original parser.
+    fn point(&self, i: usize) -> Position {
+        let line = self.line_starts.partition_point(|&start| start <= i);
+        let line_start = self.line_starts[line - 1];
+        Position { line, offset: self.offsets[i], column: self.offsets[i] - self.offsets[line_start] + 1 }
+    }
+
+    fn char(&self, p: usize) -> Option<char> {
+        self.chars.get(p).copied()
+    }
+
+    /// The source of the characters `start..end`.
+    fn text(&self, start: usize, end: usize) -> &'src str {
+        &self.input[self.bytes[start]..self.bytes[end]]
+    }
+
+    /// The index following `s` if it is found at `p`.
+    fn literal(&self, p: usize, s: &str) -> Option<usize> {
+        let mut q = p;
+        for c in s.chars() {
+            if self.char(q) != Some(c) {
+                return None;
+            }
+            q += 1;
+        }
+        Some(q)
+    }
+
+    /// The source of the first of `names` found at `p`, with the index following it.
+    fn one_of(&self, p: usize, names: &[&str]) -> Option<(&'src str, usize)> {
+        let q = names.iter().find_map(|&name| self.literal(p, name))?;
+        Some((self.text(p, q), q))
+    }
+
+    fn string(&self, start: usize, end: usize) -> Parsed<'src> {
+        let content = self.text(start, end).into();
+        Some((Node::String { content, position: self.position(start, end), render_info: None }, end))
+    }
+
+    fn memoized(&mut self, rule: Rule, p: usize, parse: impl FnOnce(&mut Grammar<'src>) -> Parsed<'src>) -> Parsed<'src> {
+        if self.failed.contains(&(rule, p)) {
+            return None;
+        }
+        let parsed = parse(self);
+        if parsed.is_none() && !self.too_deep {
+            self.failed.insert((rule, p));
+        }
+        parsed
+    }
+
+    /// Enter a nested token, failing once the input is nested too deeply.
+    fn descend(&mut self) -> bool {
+        if self.too_deep {
+            return false;
+        }
+        if self.depth == MAX_DEPTH {
+            self.too_deep = true;
+            return false;
+        }
+        self.depth += 1;
+        true
+    }
+
+    fn token(&mut self, p: usize) -> Parsed<'src> {
+        if p >= self.chars.len() || !self.descend() {
+            return None;
+        }
+        let parsed = self.regular_token(p);
+        self.depth -= 1;
+        parsed
+    }
+
+    fn regular_token(&mut self, p: usize) -> Parsed<'src> {
+        if let Some(parsed) = self
+            .special_macro(p)
+            .or_else(|| self.macro_(p))
+            .or_else(|| self.full_comment(p))
+            .or_else(|| self.group(p))
+            .or_else(|| self.inline_math(p))
+        {
+            return Some(parsed);
+        }
+        let c = self.char(p)?;
+        if c == '&' {
+            return self.string(p, p + 1);
+        }
+        if let Some(end) = self.parbreak(p) {
+            return Some((Node::Parbreak { position: self.position(p, end), render_info: None }, end));
+        }
+        if c == '#' || c == '\0' {
+            return self.string(p, p + 1);
+        }
+        if let Some(end) = self.number(p) {
+            return self.string(p, end);
+        }
+        if let Some(end) = self.whitespace(p) {
+            return Some((Node::WhiteSpace { position: self.position(p, end), render_info: None }, end));
+        }
+        if is_punctuation(c) {
+            return self.string(p, p + 1);
+        }
+        let end = (p..self.chars.len()).find(|&q| is_nonchar(self.chars[q])).unwrap_or(self.chars.len());
+        if end > p {
+            return self.string(p, end);
+        }
+        // unmatched delimiters, and a lone escape at the end of the input
+        self.string(p, p + 1)
+    }
+
+    fn math_token(&mut self, p: usize) -> Parsed<'src> {
+        if p >= self.chars.len() || !self.descend() {
+            return None;
+        }
+        let parsed = self.math_mode_token(p);
+        self.depth -= 1;
+        parsed
+    }
+
+    fn math_mode_token(&mut self, p: usize) -> Parsed<'src> {
+        if let Some(parsed) = self.special_macro(p).or_else(|| self.macro_(p)).or_else(|| self.full_comment(p)) {
+            return Some(parsed);
+        }
+        // groups, alignment tabs and scripts absorb the whitespace around them
+        let s = self.whitespaces(p);
+        if let Some((group, end)) = self.group(s) {
+            return Some((group, self.whitespaces(end)));
+        }
+        if self.char(s) == Some('&') {
+            let end = self.whitespaces(s + 1);
+            return Some((Node::String { content: self.text(s, s + 1).into(), position: self.position(s, s + 1), render_info: None }, end));
+        }
+        let c = self.char(p)?;
+        if c == '#' {
+            return self.string(p, p + 1);
+        }
+        if let Some('^' | '_') = self.char(s) {
+            // the position of a script covers the whitespace too
+            let end = self.whitespaces(s + 1);
+            let position = self.position(p, end);
+            let content = self.text(s, s + 1).into();
+            let node = Node::Macro { content, args: vec![], escape_token: Some("".into()), position, render_info: None };
+            return Some((node, end));
+        }
+        if c == '\0' {
+            return self.string(p, p + 1);
+        }
+        if let Some(end) = self.whitespace(p) {
+            return Some((Node::WhiteSpace { position: self.position(p, end), render_info: None }, end));
+        }
+        self.string(p, p + 1)
+    }
+
+    fn special_macro(&mut self, p: usize) -> Parsed<'src> {
+        if self.char(p) != Some('\\') && self.char(p) != Some('$') {
+            return None;
+        }
+        self.verb(p)
+            .or_else(|| self.memoized(Rule::VerbatimEnvironment, p, |g| g.verbatim_environment(p)))
+            .or_else(|| self.memoized(Rule::BracketMath, p, |g| g.delimited_math(p, "\\[", "\\]")))
+            .or_else(|| self.memoized(Rule::ParenMath, p, |g| g.delimited_math(p, "\\(", "\\)")))
+            .or_else(|| self.memoized(Rule::DisplayMath, p, |g| g.delimited_math(p, "$$", "$$")))
+            .or_else(|| self.memoized(Rule::MathEnvironment, p, |g| g.math_environment(p)))
+            .or_else(|| self.memoized(Rule::Environment, p, |g| g.environment(p)))
+    }
+
+    fn verb(&mut self, p: usize) -> Parsed<'src> {
+        let q = self.literal(p, "\\")?;
+        let (env, q) = self.one_of(q, &["verb*", "verb"])?;
+        let escape = self.char(q)?;
+        let start = q + 1;
+        let end = (start..self.chars.len()).find(|&i| self.chars[i] == escape)?;
+        let verb = Node::Verb {
+            env: env.into(),
+            escape: self.text(q, start).into(),
+            content: self.text(start, end).into(),
+            position: self.position(p, end + 1),
+            render_info: None,
+        };
+        Some((verb, end + 1))
+    }
+
+    fn verbatim_environment(&mut self, p: usize) -> Parsed<'src> {
+        let q = self.literal(p, "\\begin{")?;
+        let (env, q) = self.one_of(q, VERBATIM_ENVIRONMENTS)?;
+        let start = self.literal(q, "}")?;
+        let mut end = start;
+        while !self.ends(end, |_, group, _, _| group_text(group) == env) {
+            if end >= self.chars.len() {
+                return None;
+            }
+            end += 1;
+        }
+        let q = self.literal(end, "\\end{")?;
+        let (_, q) = self.one_of(q, VERBATIM_ENVIRONMENTS)?;
+        let q = self.literal(q, "}")?;
+        let verbatim = Node::VerbatimEnvironment {
+            env: env.into(),
+            args: vec![],
+            content: self.text(start, end).into(),
+            position: self.position(p, q),
+            render_info: None,
+        };
+        Some((verbatim, q))
+    }
+
+    /// Whether an `\end` followed by a group accepted by `matches` is found at `p`.
+    fn ends(&mut self, p: usize, matches: impl FnOnce(&Grammar, &Node, usize, usize) -> bool) -> bool {
+        let Some(q) = self.literal(p, "\\end") else {
+            return false;
+        };
+        match self.group(q) {
+            Some((group, end)) => matches(self, &group, q, end),
+            None => false,
+        }
+    }
+
+    /// `\[ … \]`, `\( … \)` and `$$ … $$`.
+    fn delimited_math(&mut self, p: usize, open: &str, close: &str) -> Parsed<'src> {
+        let mut q = self.literal(p, open)?;
+        let mut content = vec![];
+        while self.literal(q, close).is_none() {
+            let Some((node, end)) = self.math_token(q) else {
+                break;
+            };
+            content.push(node);
+            q = end;
+        }
+        let end = self.literal(q, close)?;
+        let position = self.position(p, end);
+        let node = if open == "\\(" {
+            Node::InlineMath { content, position, render_info: None }
+        } else {
+            Node::DisplayMath { content, position, render_info: None }
+        };
+        Some((node, end))
+    }
+
+    fn math_environment(&mut self, p: usize) -> Parsed<'src> {
+        let q = self.literal(p, "\\begin{")?;
+        let (env, q) = self.one_of(q, MATH_ENVIRONMENTS)?;
+        let mut q = self.literal(q, "}")?;
+        let mut content = vec![];
+        if let Some((comment, end)) = self.sameline_comment(q) {
+            content.push(comment);
+            q = end;
+        }
+        while !self.ends(q, |_, group, _, _| group_text(group) == env) {
+            let Some((node, end)) = self.math_token(q) else {
+                break;
+            };
+            content.push(node);
+            q = end;
+        }
+        let q = self.literal(q, "\\end{")?;
+        let (_, q) = self.one_of(q, MATH_ENVIRONMENTS)?;
+        let end = self.literal(q, "}")?;
+        let node = Node::MathEnv { env: env.into(), args: vec![], content, position: self.position(p, end), render_info: None };
+        Some((node, end))
+    }
+
+    fn environment(&mut self, p: usize) -> Parsed<'src> {
+        let q = self.literal(p, "\\begin")?;
+        let (_, mut q) = self.group(q)?;
+        let env = self.text(p + "\\begin{".len(), q - 1);
+        let mut content = vec![];
+        if let Some((comment, end)) = self.sameline_comment(q) {
+            content.push(comment);
+            q = end;
+        }
+        // the name of the environment is compared as written
+        while !self.ends(q, |g, _, start, end| g.chars[start + 1..end - 1].iter().copied().eq(env.chars())) {
+            let Some((node, end)) = self.token(q) else {
+                break;
+            };
+            content.push(node);
+            q = end;
+        }
+        let q = self.literal(q, "\\end")?;
+        let (_, end) = self.group(q)?;
+        let node = Node::Environment { env: env.into(), args: vec![], content, position: self.position(p, end), render_info: None };
+        Some((node, end))
+    }
+
+    fn macro_(&mut self, p: usize) -> Parsed<'src> {
+        let q = self.literal(p, "\\")?;
+        let letters = (q..self.chars.len()).find(|&i| !self.chars[i].is_ascii_alphabetic()).unwrap_or(self.chars.len());
+        let end = if letters > q { letters } else { self.char(q).map(|_| q + 1)? };
+        let node = Node::Macro { content: self.text(q, end).into(), args: vec![], escape_token: None, position: self.position(p, end), render_info: None };
+        Some((node, end))
+    }
+
+    fn full_comment(&mut self, p: usize) -> Parsed<'src> {
+        self.ownline_comment(p).or_else(|| self.sameline_comment(p))
+    }
+
+    /// A comment on its own line, along with the line break preceding it.
+    fn ownline_comment(&mut self, p: usize) -> Parsed<'src> {
+        let q = self.newline(self.spaces(p)).unwrap_or(p);
+        if q > 0 && self.chars[q - 1] != '\n' {
+            return None;
+        }
+        let start = self.spaces(q);
+        let (content, suffix_parbreak, end) = self.comment(start)?;
+        let comment = Node::Comment {
+            content: content.into(),
+            sameline: false,
+            suffix_parbreak,
+            leading_whitespace: start > q,
+            position: self.position(p, end),
+            render_info: None,
+        };
+        Some((comment, end))
+    }
+
+    fn sameline_comment(&mut self, p: usize) -> Parsed<'src> {
+        let start = self.spaces(p);
+        let (content, suffix_parbreak, end) = self.comment(start)?;
+        let comment = Node::Comment {
+            content: content.into(),
+            sameline: true,
+            suffix_parbreak,
+            leading_whitespace: start > p,
+            position: self.position(p, end),
+            render_info: None,
+        };
+        Some((comment, end))
+    }
+
+    /// The content of a comment, whether a paragraph break follows it, and its end.
+    ///
+    /// The line break ending the comment is part of it, along with the indentation of the
+    /// next line unless another comment starts there; a following paragraph break is not.
+    fn comment(&mut self, p: usize) -> Option<(&'src str, bool, usize)> {
+        let start = self.literal(p, "%")?;
+        let end = (start..self.chars.len()).find(|&i| matches!(self.chars[i], '\n' | '\r')).unwrap_or(self.chars.len());
+        let content = self.text(start, end);
+        if self.parbreak(end).is_some() {
+            return Some((content, true, end));
+        }
+        let Some(line) = self.newline(end) else {
+            return Some((content, false, end));
+        };
+        let indent = self.spaces(line);
+        let end = if self.char(indent) == Some('%') { line } else { indent };
+        Some((content, false, end))
+    }
+
+    fn group(&mut self, p: usize) -> Parsed<'src> {
+        if self.char(p) != Some('{') {
+            return None;
+        }
+        self.memoized(Rule::Group, p, |g| {
+            let mut q = p + 1;
+            let mut content = vec![];
+            while g.char(q) != Some('}') {
+                let Some((node, end)) = g.token(q) else {
+                    break;
+                };
+                content.push(node);
+                q = end;
+            }
+            let end = g.literal(q, "}")?;
+            Some((Node::Group { content, position: g.position(p, end), render_info: None }, end))
+        })
+    }
+
+    fn inline_math(&mut self, p: usize) -> Parsed<'src> {
+        if self.char(p) != Some('$') {
+            return None;
+        }
+        self.memoized(Rule::InlineMath, p, |g| {
+            let mut q = p + 1;
+            let mut content = vec![];
+            while g.char(q) != Some('$') {
+                let Some((node, end)) = g.math_token(q) else {
+                    break;
+                };
+                content.push(node);
+                q = end;
+            }
+            if content.is_empty() {
+                return None;
+            }
+            let end = g.literal(q, "$")?;
+            Some((Node::InlineMath { content, position: g.position(p, end), render_info: None }, end))
+        })
+    }
+
+    /// Two line breaks or more, with the spaces around them.
+    fn parbreak(&self, p: usize) -> Option<usize> {
+        let mut end = self.newline(self.spaces(p))?;
+        let mut breaks = 1;
+        while let Some(line) = self.newline(self.spaces(end)) {
+            end = line;
+            breaks += 1;
+        }
+        if breaks < 2 {
+            return None;
+        }
+        // spaces before a comment belong to it
+        let indent = self.spaces(end);
+        Some(if self.char(indent) == Some('%') { end } else { indent })
+    }
+
+    /// Whitespace containing at most one line break.
+    fn whitespace(&self, p: usize) -> Option<usize> {
+        if let Some(line) = self.newline(p) {
+            return Some(self.spaces(line));
+        }
+        let spaces = self.spaces(p);
+        if spaces == p {
+            return None;
+        }
+        if let Some(line) = self.newline(spaces) {
+            let indent = self.spaces(line);
+            if self.char(line) != Some('%') && self.newline(indent).is_none() {
+                return Some(indent);
+            }
+        }
+        Some(spaces)
+    }
+
+    /// The end of the whitespace tokens starting at `p`.
+    fn whitespaces(&self, mut p: usize) -> usize {
+        while let Some(end) = self.whitespace(p) {
+            p = end;
+        }
+        p
+    }
+
+    /// Decimal numbers: `1.5`, `.5` and `5.`.
+    fn number(&self, p: usize) -> Option<usize> {
+        let digits = |q: usize| (q..self.chars.len()).find(|&i| !self.chars[i].is_ascii_digit()).unwrap_or(self.chars.len());
+        let integer = digits(p);
+        let dot = self.literal(integer, ".")?;
+        let fraction = digits(dot);
+        (integer > p || fraction > dot).then_some(fraction)
+    }
+
+    /// The end of the spaces and tabs starting at `p`.
+    fn spaces(&self, p: usize) -> usize {
+        (p..self.chars.len()).find(|&i| !matches!(self.chars[i], ' ' | '\t')).unwrap_or(self.chars.len())
+    }
+
+    /// `\r\n` counts as two line breaks, like in the original grammar.
+    fn newline(&self, p: usize) -> Option<usize> {
+        matches!(self.char(p), Some('\n' | '\r')).then_some(p + 1)
+    }
+}
+
+/// Text of the name of an environment in an `\end` group: strings and macro names,
+/// whitespace as a space.
+fn group_text(group: &Node) -> String {
+    group
+        .content()
+        .iter()
+        .map(|node| match node {
+            Node::String { content, .. }
+            | Node::Comment { content, .. }
+            | Node::Macro { content, .. }
+            | Node::Verb { content, .. }
+            | Node::VerbatimEnvironment { content, .. } => content.clone(),
+            Node::WhiteSpace { .. } => " ".into(),
+            _ => "[object Object]".into(),
+        })
+        .collect()
+}
+
+fn is_punctuation(c: char) -> bool {
+    matches!(c, '.' | ',' | ';' | ':' | '-' | '*' | '/' | '(' | ')' | '!' | '?' | '=' | '+' | '<' | '>' | '[' | ']' | '`' | '\'' | '"' | '~')
+}
+
+/// Characters ending a run of text.
+fn is_nonchar(c: char) -> bool {
+    matches!(c, '\\' | '%' | '{' | '}' | '$' | '&' | '\n' | '\r' | '#' | '\0' | ' ' | '\t') || is_punctuation(c)
+}
@@ -0,0 +1,26 @@
+//! Options controlling how documents are formatted.
+
+/// Formatting options passed to the underlying Prettier printer.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FormatOptions {
+    /// Specify the line length that the printer will wrap on.
+    pub print_width: i32,
+    /// Indent lines with tabs instead of spaces.
+    pub use_tabs: bool,
+    /// Specify the number of spaces per indentation-level.
+    pub tab_width: i32,
+    /// Only format the document environment.
+    pub document_only: bool,
+}
+
+impl Default for FormatOptions {
+    /// The options used by [`format`](crate::format).
+    fn default() -> Self {
+        FormatOptions {
+            print_width: 80,
+            use_tabs: false,
+            tab_width: 2,
+            document_only: true,
+        }
+    }
+}
@@ -0,0 +1,239 @@
+//! Sectioning structure of a document.
+//!
+//! ```
+//! let ast = unlatex::parse(r"\section{Intro}\label{sec:intro} \subsection*{Details}").unwrap();
+//! let outline = unlatex::outline(&ast);
+//! assert_eq!(outline.sections[0].title, "Intro");
+//! assert_eq!(outline.sections[0].labels[0].name, "sec:intro");
+//! assert!(outline.sections[0].children[0].starred);
+//! ```
+//!
+//! The [borrowed tree](crate::ast::borrowed) of a document has the same outline.
+
+use serde::Serialize;
+
+use crate::ast::{to_latex, Tree};
+use crate::info::PositionInfo;
+
+/// Sectioning commands, from the outermost to the innermost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SectionKind {
+    /// `\part`
+    Part,
+    /// `\chapter`
+    Chapter,
+    /// `\section`
+    Section,
+    /// `\subsection`
+    Subsection,
+    /// `\subsubsection`
+    Subsubsection,
+    /// `\paragraph`
+    Paragraph,
+    /// `\subparagraph`
+    Subparagraph,
+}
+
+impl SectionKind {
+    /// All the sectioning commands, from the outermost to the innermost.
+    pub const ALL: [SectionKind; 7] = [
+        SectionKind::Part,
+        SectionKind::Chapter,
+        SectionKind::Section,
+        SectionKind::Subsection,
+        SectionKind::Subsubsection,
+        SectionKind::Paragraph,
+        SectionKind::Subparagraph,
+    ];
+
+    /// Name of the macro starting a section of this kind.
+    pub fn macro_name(self) -> &'static str {
+        match self {
+            SectionKind::Part => "part",
+            SectionKind::Chapter => "chapter",
+            SectionKind::Section => "section",
+            SectionKind::Subsection => "subsection",
+            SectionKind::Subsubsection => "subsubsection",
+            SectionKind::Paragraph => "paragraph",
+            SectionKind::Subparagraph => "subparagraph",
+        }
+    }
+
+    /// The kind of section started by the macro `name`.
+    pub fn from_macro(name: &str) -> Option<SectionKind> {
+        SectionKind::ALL.into_iter().find(|kind| kind.macro_name() == name)
+    }
+}
+
+/// A `\label` attached to a section.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Label {
+    /// The label key.
+    pub name: String,
+    /// Position of the `\label` macro.
+    pub position: PositionInfo,
+    /// Index of the file the label is in, see [`Project`](crate::project::Project).
+    pub file: usize,
+}
+
+/// An environment, such as a figure or an equation, with the labels defined in it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Environment {
+    /// Name of the environment.
+    pub name: String,
+    /// Labels defined in the environment, outside of the environments nested in it.
+    pub labels: Vec<Label>,
+    /// Position of the environment, from `\begin` to `\end`.
+    pub position: PositionInfo,
+    /// Index of the file the environment is in, see [`Project`](crate::project::Project).
+    pub file: usize,
+    /// Environments nested in it.
+    pub children: Vec<Environment>,
+}
+
+/// A section of the document and its subsections.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Section {
+    /// The sectioning command starting the section.
+    pub kind: SectionKind,
+    /// Whether the starred variant (e.g. `\section*`) was used.
+    pub starred: bool,
+    /// Title of the section, as LaTeX source.
+    pub title: String,
+    /// Title for the table of contents given in `[` `]`, as LaTeX source.
+    pub short_title: Option<String>,
+    /// Labels defined in the section, before its first subsection.
+    pub labels: Vec<Label>,
+    /// Environments of the section, before its first subsection.
+    pub environments: Vec<Environment>,
+    /// Position of the sectioning macro.
+    pub position: PositionInfo,
+    /// Index of the file the section starts in, see [`Project`](crate::project::Project).
+    pub file: usize,
+    /// Subsections.
+    pub children: Vec<Section>,
+}
+
+/// The sectioning structure of a document.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Outline {
+    /// Labels defined before the first section.
+    pub labels: Vec<Label>,
+    /// Environments before the first section.
+    pub environments: Vec<Environment>,
+    /// Top-level sections.
+    pub sections: Vec<Section>,
+}
+
+/// Text of the argument of `args` delimited by `open`.
+fn argument<N: Tree>(args: &[N], open: &str) -> Option<String> {
+    args.iter()
+        .rev()
+        .find(|arg| arg.open_mark() == Some(open))
+        .map(|arg| to_latex(arg.content()).trim().to_string())
+}
+
+/// Finds the file included by a macro node of a file, with its index and AST.
+pub(crate) type IncludeResolver<'a, N> = dyn Fn(usize, &N) -> Option<(usize, &'a N)> + 'a;
+
+struct Builder<'a, N> {
+    outline: Outline,
+    /// Sections whose end has not been seen yet.
+    open: Vec<Section>,
+    /// Environments whose end has not been seen yet.
+    environments: Vec<Environment>,
+    /// Index of the file being visited.
+    file: usize,
+    include: &'a IncludeResolver<'a, N>,
+}
+
+impl<N: Tree> Builder<'_, N> {
+    fn close(&mut self, kind: Option<SectionKind>) {
+        while self.open.last().is_some_and(|s| kind.is_none_or(|kind| s.kind >= kind)) {
+            let section = self.open.pop().unwrap();
+            match self.open.last_mut() {
+                Some(parent) => parent.children.push(section),
+                None => self.outline.sections.push(section),
+            }
+        }
+    }
+
+    fn visit(&mut self, content: &[N]) {
+        for node in content {
+            if let Some(name) = node.macro_name() {
+                let (args, position) = (node.args(), node.position().cloned().unwrap_or_default());
+                if let Some((file, ast)) = (self.include)(self.file, node) {
+                    let parent = std::mem::replace(&mut self.file, file);
+                    self.visit(ast.content());
+                    self.file = parent;
+                } else if let Some(kind) = SectionKind::from_macro(name) {
+                    self.close(Some(kind));
+                    let starred = args.first().is_some_and(|arg| matches!(arg.content(), [star] if star.string() == Some("*")));
+                    self.open.push(Section {
+                        kind,
+                        starred,
+                        title: argument(args, "{").unwrap_or_default(),
+                        short_title: argument(args, "["),
+                        labels: vec![],
+                        environments: vec![],
+                        position,
+                        file: self.file,
+                        children: vec![],
+                    });
+                } else if name == "label" {
+                    let label = Label {
+                        name: argument(args, "{").unwrap_or_default(),
+                        position,
+                        file: self.file,
+                    };
+                    match (self.environments.last_mut(), self.open.last_mut()) {
+                        (Some(environment), _) => environment.labels.push(label),
+                        (None, Some(section)) => section.labels.push(label),
+                        (None, None) => self.outline.labels.push(label),
+                    }
+                }
+            } else if node.environment() == Some("document") {
+                self.visit(node.content());
+            } else if let Some(env) = node.environment().or_else(|| node.math_environment()) {
+                // labels of figures, equations... belong to them rather than to the section
+                self.environments.push(Environment {
+                    name: env.to_string(),
+                    labels: vec![],
+                    position: node.position().cloned().unwrap_or_default(),
+                    file: self.file,
+                    children: vec![],
+                });
+                self.visit(node.content());
+                let environment = self.environments.pop().unwrap();
+                match (self.environments.last_mut(), self.open.last_mut()) {
+                    (Some(parent), _) => parent.children.push(environment),
+                    (None, Some(section)) => section.environments.push(environment),
+                    (None, None) => self.outline.environments.push(environment),
+                }
+            } else if node.is_group() {
+                self.visit(node.content());
+            }
+        }
+    }
+}
+
+/// Extract the sectioning structure of the document `root`.
+pub fn outline<N: Tree>(root: &N) -> Outline {
+    outline_with(root, &|_, _| None)
+}
+
+/// Extract the sectioning structure of the document `root`, visiting the files returned
+/// by `include` in place of the macros including them.
+pub(crate) fn outline_with<'a, N: Tree>(root: &N, include: &'a IncludeResolver<'a, N>) -> Outline {
+    let mut builder = Builder {
+        outline: Outline::default(),
+        open: vec![],
+        environments: vec![],
+        file: 0,
+        include,
+    };
+    builder.visit(root.content());
+    builder.close(None);
+    builder.outline
+}
@@ -0,0 +1,372 @@
+//! Documents spanning several files.
+//!
+//! A [`Project`] starts from a root file and follows `\input`, `\include`, `\subfile`,
+//! `\import` and `\subimport`, parsing every file it reaches.
+//!
+//! ```
+//! use std::collections::HashMap;
+//! use std::path::PathBuf;
+//! use unlatex::project::Project;
+//!
+//! let files = HashMap::from([
+//!     (PathBuf::from("main.tex"), r"\input{intro} \ref{sec:intro}".to_string()),
+//!     (PathBuf::from("intro.tex"), r"\section{Intro}\label{sec:intro}".to_string()),
+//! ]);
+//! let project = Project::load_with("main.tex", &files).unwrap();
+//! assert_eq!(project.files[1].path, PathBuf::from("intro.tex"));
+//! assert!(project.references().check().is_empty());
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use crate::analysis::citations::Citations;
+use crate::analysis::refs::References;
+use crate::ast::Node;
+use crate::error::{Error, Result};
+use crate::info::PositionInfo;
+use crate::macros::{macro_call, text, visit_lists};
+use crate::outline::{self, Outline};
+use crate::stats::{self, CountOptions, Stats};
+use crate::span::{node_range, OffsetMap};
+
+/// Macros including another file, with the number of mandatory arguments they take.
+pub const INCLUDE_MACROS: &[(&str, usize)] = &[
+    ("input", 1),
+    ("include", 1),
+    ("subfile", 1),
+    ("import", 2),
+    ("subimport", 2),
+    ("inputfrom", 2),
+    ("subinputfrom", 2),
+    ("includefrom", 2),
+    ("subincludefrom", 2),
+];
+
+/// Source of the files of a project.
+pub trait Loader {
+    /// Read the file at `path`.
+    fn load(&self, path: &Path) -> io::Result<String>;
+}
+
+/// Loads files from the file system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FsLoader;
+
+impl Loader for FsLoader {
+    fn load(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+}
+
+/// Loads files from memory, e.g. for tests or unsaved editor buffers.
+impl Loader for HashMap<PathBuf, String> {
+    fn load(&self, path: &Path) -> io::Result<String> {
+        self.get(path)
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())))
+    }
+}
+
+/// An inclusion of a file by another one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Include {
+    /// Name of the including macro.
+    pub macro_name: String,
+    /// The included file as written, joined to the directory argument of `\import`-like macros.
+    pub target: String,
+    /// The resolved path of the included file.
+    pub path: PathBuf,
+    /// Index of the included file in [`Project::files`], `None` if it could not be loaded.
+    pub file: Option<usize>,
+    /// Position of the including macro.
+    pub position: PositionInfo,
+    /// Byte range of the including macro and its arguments in the including file.
+    pub range: Range<usize>,
+}
+
+/// A file of a project.
+#[derive(Debug, Clone)]
+pub struct ProjectFile {
+    /// Path of the file, as resolved from the root.
+    pub path: PathBuf,
+    /// Content of the file.
+    pub source: String,
+    /// The parsed file.
+    pub ast: Node,
+    /// The files this file includes, in document order.
+    pub includes: Vec<Include>,
+    /// Directory the includes of this file are resolved against.
+    base: PathBuf,
+}
+
+/// A problem found while following includes.
+#[derive(Debug, Clone)]
+pub enum ProjectIssue {
+    /// An included file could not be loaded.
+    MissingFile {
+        /// Index of the including file in [`Project::files`].
+        file: usize,
+        /// The failed inclusion.
+        include: Include,
+        /// Why the file could not be loaded.
+        message: String,
+    },
+    /// A file including itself, directly or through other files.
+    Cycle {
+        /// Index of the including file in [`Project::files`].
+        file: usize,
+        /// The inclusion closing the cycle.
+        include: Include,
+    },
+}
+
+impl std::fmt::Display for ProjectIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProjectIssue::MissingFile { include, message, .. } => {
+                write!(f, "cannot include `{}`: {message}", include.target)
+            }
+            ProjectIssue::Cycle { include, .. } => {
+                write!(f, "`{}` is already being included", include.path.display())
+            }
+        }
+    }
+}
+
+/// A document made of several files.
+#[derive(Debug, Clone)]
+pub struct Project {
+    /// The files of the project, the root first and then in inclusion order. Each file is
+    /// listed once even if it is included several times.
+    pub files: Vec<ProjectFile>,
+    /// Problems found while following includes.
+    pub issues: Vec<ProjectIssue>,
+}
+
+impl Project {
+    /// Load the project whose root file is at `root`, from the file system.
+    pub fn load(root: impl AsRef<Path>) -> Result<Project> {
+        Project::load_with(root, &FsLoader)
+    }
+
+    /// Load the project whose root file is at `root`, reading files with `loader`.
+    ///
+    /// Fails with [`Error::Include`] if the root file cannot be read, or if any file cannot
+    /// be parsed. Included files which cannot be read are reported in [`Project::issues`].
+    pub fn load_with(root: impl AsRef<Path>, loader: &impl Loader) -> Result<Project> {
+        let root = normalize(root.as_ref());
+        let source = loader.load(&root).map_err(|e| Error::Include {
+            path: root.display().to_string(),
+            message: e.to_string(),
+        })?;
+        let base = root.parent().unwrap_or(Path::new("")).to_path_buf();
+        let mut project = Project { files: vec![], issues: vec![] };
+        project.add(root, source, base)?;
+        project.follow(0, loader, &mut vec![0])?;
+        Ok(project)
+    }
+
+    fn add(&mut self, path: PathBuf, source: String, base: PathBuf) -> Result<usize> {
+        let ast = crate::parse(&source)?;
+        let includes = includes(&ast, &source, &base, &path);
+        self.files.push(ProjectFile { path, source, ast, includes, base });
+        Ok(self.files.len() - 1)
+    }
+
+    /// Load the files included by `file`, `stack` holding the files being included.
+    fn follow(&mut self, file: usize, loader: &impl Loader, stack: &mut Vec<usize>) -> Result<()> {
+        for i in 0..self.files[file].includes.len() {
+            let include = &self.files[file].includes[i];
+            let mut error = None;
+            let mut found = None;
+            for path in candidates(include) {
+                if let Some(existing) = self.files.iter().position(|f| f.path == path) {
+                    found = Some((path, Err(existing)));
+                    break;
+                }
+                match loader.load(&path) {
+                    Ok(source) => {
+                        found = Some((path, Ok(source)));
+                        break;
+                    }
+                    Err(e) => error = error.or(Some(e)),
+                }
+            }
+            let Some((path, loaded)) = found else {
+                let include = include.clone();
+                let message = error.map(|e| e.to_string()).unwrap_or_default();
+                self.issues.push(ProjectIssue::MissingFile { file, include, message });
+                continue;
+            };
+
+            let (index, new) = match loaded {
+                Err(existing) => (existing, false),
+                Ok(source) => {
+                    // files included with a directory argument resolve their own includes from it
+                    let base = if takes_directory(&include.macro_name) {
+                        path.parent().unwrap_or(Path::new("")).to_path_buf()
+                    } else {
+                        self.files[file].base.clone()
+                    };
+                    (self.add(path.clone(), source, base)?, true)
+                }
+            };
+            let include = &mut self.files[file].includes[i];
+            include.path = path;
+            include.file = Some(index);
+            if stack.contains(&index) {
+                let include = include.clone();
+                self.issues.push(ProjectIssue::Cycle { file, include });
+            } else if new {
+                stack.push(index);
+                self.follow(index, loader, stack)?;
+                stack.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// The root file.
+    pub fn root(&self) -> &ProjectFile {
+        &self.files[0]
+    }
+
+    /// The file at `path`, as resolved from the root.
+    pub fn file(&self, path: impl AsRef<Path>) -> Option<&ProjectFile> {
+        let path = normalize(path.as_ref());
+        self.files.iter().find(|f| f.path == path)
+    }
+
+    /// The labels and references of all the files, each tagged with its file index.
+    pub fn references(&self) -> References {
+        let mut refs = References::default();
+        for (i, file) in self.files.iter().enumerate() {
+            let mut file_refs = References::collect(&file.ast);
+            file_refs.labels.iter_mut().for_each(|l| l.file = i);
+            file_refs.references.iter_mut().for_each(|r| r.file = i);
+            refs.extend(file_refs);
+        }
+        refs
+    }
+
+    /// The citations of all the files, each tagged with its file index.
+    pub fn citations(&self) -> Citations {
+        let mut citations = Citations::default();
+        for (i, file) in self.files.iter().enumerate() {
+            let mut file_citations = Citations::collect(&file.ast);
+            file_citations.citations.iter_mut().for_each(|c| c.file = i);
+            citations.extend(file_citations);
+        }
+        citations
+    }
+
+    /// The sectioning structure of the document, with the sections of included files
+    /// spliced where they are included.
+    pub fn outline(&self) -> Outline {
+        outline::outline_with(&self.root().ast, &|file, node| self.included(file, node))
+    }
+
+    /// The counts of the document with `options`, included files being counted where
+    /// they are included.
+    pub fn stats(&self, options: &CountOptions) -> Stats {
+        stats::count(&self.root().ast, options, &|file, node| self.included(file, node))
+    }
+
+    /// The file included by the macro `node` of `file`, with its index.
+    fn included(&self, file: usize, node: &Node) -> Option<(usize, &Node)> {
+        let offset = node.position()?.start.offset;
+        let include = self.files[file].includes.iter().find(|i| i.position.start.offset == offset)?;
+        // following a cycle would recurse forever
+        let cycle = self.issues.iter().any(|issue| {
+            matches!(issue, ProjectIssue::Cycle { file: f, include: i } if *f == file && i.range == include.range)
+        });
+        let included = include.file.filter(|_| !cycle)?;
+        Some((included, &self.files[included].ast))
+    }
+}
+
+/// Whether the include macro `name` takes a directory argument.
+fn takes_directory(name: &str) -> bool {
+    INCLUDE_MACROS.iter().any(|&(n, mandatory)| n == name && mandatory == 2)
+}
+
+/// The includes of the file at `path` parsed as `ast`, with paths resolved against `base`.
+///
+/// The directory argument of `\import` is relative to `base`, the one of `\subimport`
+/// and the other `sub` macros is relative to the directory of the including file.
+fn includes(ast: &Node, src: &str, base: &Path, path: &Path) -> Vec<Include> {
+    let map = OffsetMap::new(src);
+    let mut includes = vec![];
+    visit_lists(ast, &mut |siblings| {
+        for i in 0..siblings.len() {
+            let Some(&(_, mandatory)) = INCLUDE_MACROS
+                .iter()
+                .find(|(name, _)| matches!(&siblings[i], Node::Macro { content, .. } if content == name))
+            else {
+                continue;
+            };
+            let Some(call) = macro_call(siblings, i, mandatory) else {
+                continue;
+            };
+            if call.mandatory.len() < mandatory {
+                continue;
+            }
+            let (Some(position), Some(range)) = (call.node.position(), node_range(call.node, src, &map)) else {
+                continue;
+            };
+            let end = match call.consumed {
+                0 => range.end,
+                n => node_range(&siblings[i + n], src, &map).map_or(range.end, |r| r.end),
+            };
+            let target: String = call.mandatory.iter().map(|arg| text(arg)).collect();
+            let dir = if call.name.starts_with("sub") && mandatory == 2 {
+                path.parent().unwrap_or(Path::new(""))
+            } else {
+                base
+            };
+            includes.push(Include {
+                macro_name: call.name.to_string(),
+                path: normalize(&dir.join(&target)),
+                target,
+                file: None,
+                position: position.clone(),
+                range: range.start..end,
+            });
+        }
+    });
+    includes.sort_by_key(|include| include.range.start);
+    includes
+}
+
+/// Paths to try for `include`: `\include` always appends `.tex`, the other macros try
+/// with `.tex` first and then the name as written.
+fn candidates(include: &Include) -> Vec<PathBuf> {
+    let with_tex = {
+        let mut name = include.path.clone().into_os_string();
+        name.push(".tex");
+        PathBuf::from(name)
+    };
+    match include.macro_name.as_str() {
+        "include" | "includefrom" | "subincludefrom" => vec![with_tex],
+        _ if include.path.extension().is_some_and(|ext| ext == "tex") => vec![include.path.clone()],
+        _ => vec![with_tex, include.path.clone()],
+    }
+}
+
+/// Resolve `.` and `..` components of `path` lexically.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(out.components().next_back(), Some(Component::Normal(_))) => {
+                out.pop();
+            }
+            component => out.push(component),
+        }
+    }
+    out
+}
@@ -0,0 +1,262 @@
+//! Conversions between the offsets used by the JS engine and Rust string offsets.
+//!
+//! Offsets reported by the parser (see [`Position`](crate::info::Position)) and expected by
+//! the formatter count UTF-16 code units, while Rust strings are indexed by bytes.
+
+use std::ops::Range;
+
+use crate::ast::Node;
+
+/// A non-ASCII character of the source.
+#[derive(Debug, Clone, Copy)]
+struct Wide {
+    byte: usize,
+    utf16: usize,
+    byte_len: usize,
+    utf16_len: usize,
+}
+
+/// Maps offsets between bytes and UTF-16 code units for a given text.
+///
+/// Only non-ASCII characters are recorded, so the map is empty for ASCII text and
+/// conversions are a binary search otherwise.
+#[derive(Debug, Clone, Default)]
+pub struct OffsetMap {
+    wide: Vec<Wide>,
+    len: usize,
+    len_utf16: usize,
+}
+
+impl OffsetMap {
+    /// Build the map for `text`.
+    pub fn new(text: &str) -> OffsetMap {
+        let mut wide = vec![];
+        let mut utf16 = 0;
+        for (byte, c) in text.char_indices() {
+            if !c.is_ascii() {
+                wide.push(Wide { byte, utf16, byte_len: c.len_utf8(), utf16_len: c.len_utf16() });
+            }
+            utf16 += c.len_utf16();
+        }
+        OffsetMap { wide, len: text.len(), len_utf16: utf16 }
+    }
+
+    /// Convert an offset in UTF-16 code units to a byte offset.
+    ///
+    /// Offsets inside a surrogate pair are moved to the start of the character, and
+    /// offsets past the end are clamped to the length of the text.
+    pub fn to_byte(&self, offset: usize) -> usize {
+        let offset = offset.min(self.len_utf16);
+        let i = self.wide.partition_point(|w| w.utf16 + w.utf16_len <= offset);
+        if let Some(w) = self.wide.get(i) {
+            if w.utf16 < offset {
+                return w.byte;
+            }
+        }
+        match i.checked_sub(1).map(|i| self.wide[i]) {
+            Some(w) => w.byte + w.byte_len + (offset - w.utf16 - w.utf16_len),
+            None => offset,
+        }
+    }
+
+    /// Convert a byte offset to an offset in UTF-16 code units.
+    ///
+    /// Offsets inside a character are moved to its start, and offsets past the end are
+    /// clamped to the length of the text.
+    pub fn to_utf16(&self, offset: usize) -> usize {
+        let offset = offset.min(self.len);
+        let i = self.wide.partition_point(|w| w.byte + w.byte_len <= offset);
+        if let Some(w) = self.wide.get(i) {
+            if w.byte < offset {
+                return w.utf16;
+            }
+        }
+        match i.checked_sub(1).map(|i| self.wide[i]) {
+            Some(w) => w.utf16 + w.utf16_len + (offset - w.byte - w.byte_len),
+            None => offset,
+        }
+    }
+}
+
+/// Maps byte offsets of a text to 0-based lines and UTF-16 columns, as used by editors.
+#[derive(Debug, Clone, Default)]
+pub struct LineIndex {
+    line_starts: Vec<usize>,
+    map: OffsetMap,
+}
+
+impl LineIndex {
+    /// Build the index for `text`.
+    pub fn new(text: &str) -> LineIndex {
+        let line_starts = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        LineIndex { line_starts, map: OffsetMap::new(text) }
+    }
+
+    /// Number of lines of the text.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Line and UTF-16 column of the byte `offset`.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.map.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let column = self.map.to_utf16(offset) - self.map.to_utf16(self.line_starts[line]);
+        (line, column)
+    }
+
+    /// Byte offset of the UTF-16 `column` of `line`.
+    ///
+    /// Positions past the end of a line are clamped to the end of the line, and lines
+    /// past the end of the text to the end of the text.
+    pub fn offset(&self, line: usize, column: usize) -> usize {
+        let Some(&start) = self.line_starts.get(line) else {
+            return self.map.len;
+        };
+        let end = self.line_starts.get(line + 1).map_or(self.map.len, |&next| next - 1);
+        let offset = self.map.to_byte(self.map.to_utf16(start) + column);
+        offset.min(end)
+    }
+
+    /// The [`OffsetMap`] of the text.
+    pub fn offset_map(&self) -> &OffsetMap {
+        &self.map
+    }
+}
+
+/// Convert a byte offset in `text` to an offset in UTF-16 code units.
+pub fn to_utf16_offset(text: &str, offset: usize) -> usize {
+    OffsetMap::new(text).to_utf16(offset)
+}
+
+/// Convert an offset in UTF-16 code units to a byte offset in `text`.
+pub fn to_byte_offset(text: &str, offset: usize) -> usize {
+    OffsetMap::new(text).to_byte(offset)
+}
+
+/// Byte range covering the lines `start..=end` (1-based) of `text`, including the
+/// line break ending the last line.
+///
+/// Returns `None` if `start` is out of bounds, `end` is clamped to the last line.
+pub fn line_range(text: &str, start: usize, end: usize) -> Option<Range<usize>> {
+    let mut line_starts = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1));
+    let first = line_starts.nth(start.checked_sub(1)?)?;
+    let last = line_starts.nth(end.checked_sub(start)?).unwrap_or(text.len());
+    Some(first..last)
+}
+
+/// Byte range of `node` in `src`, the source it was parsed from.
+///
+/// The parser does not record positions for macro arguments, so they are found by
+/// scanning the source after the macro name. Returns `None` for nodes without position.
+pub fn node_range(node: &Node, src: &str, map: &OffsetMap) -> Option<Range<usize>> {
+    let position = node.position()?;
+    let start = map.to_byte(position.start.offset);
+    let mut end = map.to_byte(position.end.offset);
+    if let Node::Macro { args, .. } = node {
+        for arg in args {
+            if let Node::Argument { open_mark, close_mark, .. } = arg {
+                if !open_mark.is_empty() {
+                    end = skip_argument(src, end, open_mark, close_mark).unwrap_or(end);
+                }
+            }
+        }
+    }
+    Some(start..end)
+}
+
+/// Skip whitespace and an argument delimited by `open` and `close` starting at byte `from`,
+/// returning the offset after the closing mark.
+fn skip_argument(src: &str, from: usize, open: &str, close: &str) -> Option<usize> {
+    let rest = &src[from..];
+    let skipped = rest.len() - rest.trim_start().len();
+    let mut i = from + skipped;
+    if !src[i..].starts_with(open) {
+        return None;
+    }
+    i += open.len();
+
+    let mut depth = 0usize;
+    while i < src.len() {
+        let rest = &src[i..];
+        if depth == 0 && rest.starts_with(close) {
+            return Some(i + close.len());
+        }
+        let c = rest.chars().next()?;
+        match c {
+            '\\' => i += rest[1..].chars().next().map_or(0, char::len_utf8),
+            '%' => i += rest.find('\n').unwrap_or(rest.len()),
+            '{' => depth += 1,
+            '}' => depth = depth.checked_sub(1)?,
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// Replace the byte ranges of `src` given in `edits`. Edits overlapping an edit starting
+/// before them are dropped.
+pub(crate) fn apply_edits(src: &str, mut edits: Vec<(Range<usize>, String)>) -> String {
+    edits.sort_by_key(|(range, _)| range.start);
+    let mut out = String::with_capacity(src.len());
+    let mut last = 0;
+    for (range, replacement) in edits {
+        if range.start < last {
+            continue;
+        }
+        out.push_str(&src[last..range.start]);
+        out.push_str(&replacement);
+        last = range.end;
+    }
+    out.push_str(&src[last..]);
+    out
+}
+
+/// Expand the byte `range` to the paragraphs of `root` it touches.
+///
+/// When the range lies within the body of an environment, the paragraphs of that
+/// body are used instead. Returns `None` if the range only covers whitespace, or lies
+/// within the body of an environment aligning its content, such as `tabular`.
+pub(crate) fn paragraphs_range(root: &Node, src: &str, map: &OffsetMap, range: &Range<usize>) -> Option<Range<usize>> {
+    let touches = |r: &Range<usize>| {
+        if range.is_empty() {
+            r.start <= range.start && range.start <= r.end
+        } else {
+            r.start < range.end && range.start < r.end
+        }
+    };
+    let extent = |nodes: &[Node]| {
+        let mut ranges = nodes
+            .iter()
+            .filter(|n| !matches!(n, Node::WhiteSpace { .. } | Node::Parbreak { .. }))
+            .filter_map(|n| node_range(n, src, map));
+        let first = ranges.next()?;
+        let last = ranges.next_back().unwrap_or_else(|| first.clone());
+        Some(first.start..last.end)
+    };
+
+    let mut content = root.content();
+    loop {
+        let mut hits = content
+            .iter()
+            .filter(|n| node_range(n, src, map).is_some_and(|r| touches(&r)));
+        match (hits.next(), hits.next()) {
+            (Some(env @ Node::Environment { render_info, .. }), None)
+            if extent(env.content()).is_some_and(|r| r.start <= range.start && range.end <= r.end) => {
+                // the rows of tabulars, matrices... are only laid out along with each other
+                if render_info.as_ref().is_some_and(|info| info.align_content) {
+                    return None;
+                }
+                content = env.content();
+            }
+            _ => break,
+        }
+    }
+
+    content
+        .split(|n| matches!(n, Node::Parbreak { .. }))
+        .filter_map(extent)
+        .filter(touches)
+        .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+}
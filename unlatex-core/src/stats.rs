@@ -0,0 +1,432 @@
+//! Word, equation and float counts of a document, like texcount.
+//!
+//! [`stats`] counts the words of a document, split between body text, section headers,
+//! captions and footnotes, along with its equations, figures, tables and citations, for
+//! the whole document and for each section.
+//!
+//! Words are runs of characters between whitespace holding a letter or a digit, so a
+//! dash or punctuation standing alone is not a word, and math is counted as equations
+//! rather than words. How the arguments of a macro are counted is set by
+//! [`CountOptions`]: `\caption{...}` holds caption words and `\label{...}` none, while
+//! the arguments of other macros, such as `\emph{...}`, count like the text around them.
+//! Only the body of the document is counted when it has a `document` environment.
+//!
+//! ```
+//! let ast = unlatex::parse(r"\section{The intro} Some text\footnote{A note.} and $x$.").unwrap();
+//! let stats = unlatex::stats(&ast);
+//! assert_eq!(stats.total.words, 3);
+//! assert_eq!(stats.total.header_words, 2);
+//! assert_eq!(stats.total.footnote_words, 2);
+//! assert_eq!(stats.sections[0].total.inline_equations, 1);
+//! ```
+
+use std::collections::BTreeMap;
+use std::ops::AddAssign;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::citations::CITE_MACROS;
+use crate::ast::Tree;
+use crate::info::PositionInfo;
+use crate::macros::{keys, macro_call};
+use crate::outline::{IncludeResolver, SectionKind};
+
+/// How the words of a macro argument or an environment are counted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Region {
+    /// Like the text around them: body text, or caption words inside a caption.
+    Text,
+    /// As words of section headers.
+    Header,
+    /// As words of float captions.
+    Caption,
+    /// As words of footnotes.
+    Footnote,
+    /// Not at all, along with the equations, floats and citations they hold.
+    Ignore,
+}
+
+/// How the mandatory arguments of macros are counted when [`CountOptions`] does not say.
+pub const MACRO_RULES: &[(&str, &[Region])] = &[
+    ("part", &[Region::Header]),
+    ("chapter", &[Region::Header]),
+    ("section", &[Region::Header]),
+    ("subsection", &[Region::Header]),
+    ("subsubsection", &[Region::Header]),
+    ("paragraph", &[Region::Header]),
+    ("subparagraph", &[Region::Header]),
+    ("title", &[Region::Header]),
+    ("caption", &[Region::Caption]),
+    ("subcaption", &[Region::Caption]),
+    ("captionof", &[Region::Ignore, Region::Caption]),
+    ("footnote", &[Region::Footnote]),
+    ("footnotetext", &[Region::Footnote]),
+    ("thanks", &[Region::Footnote]),
+    ("marginpar", &[Region::Footnote]),
+    ("href", &[Region::Ignore, Region::Text]),
+    ("textcolor", &[Region::Ignore, Region::Text]),
+    ("author", &[Region::Ignore]),
+    ("date", &[Region::Ignore]),
+    ("label", &[Region::Ignore]),
+    ("ref", &[Region::Ignore]),
+    ("eqref", &[Region::Ignore]),
+    ("pageref", &[Region::Ignore]),
+    ("autoref", &[Region::Ignore]),
+    ("nameref", &[Region::Ignore]),
+    ("cref", &[Region::Ignore]),
+    ("Cref", &[Region::Ignore]),
+    ("url", &[Region::Ignore]),
+    ("includegraphics", &[Region::Ignore]),
+    ("input", &[Region::Ignore]),
+    ("include", &[Region::Ignore]),
+    ("usepackage", &[Region::Ignore]),
+    ("bibliography", &[Region::Ignore]),
+    ("bibliographystyle", &[Region::Ignore]),
+    ("addbibresource", &[Region::Ignore]),
+    ("color", &[Region::Ignore]),
+    ("vspace", &[Region::Ignore]),
+    ("hspace", &[Region::Ignore]),
+    ("newcommand", &[Region::Ignore, Region::Ignore]),
+    ("renewcommand", &[Region::Ignore, Region::Ignore]),
+    ("providecommand", &[Region::Ignore, Region::Ignore]),
+    ("newenvironment", &[Region::Ignore, Region::Ignore, Region::Ignore]),
+    ("setlength", &[Region::Ignore, Region::Ignore]),
+    ("setcounter", &[Region::Ignore, Region::Ignore]),
+    ("addtocounter", &[Region::Ignore, Region::Ignore]),
+];
+
+/// How the content of environments is counted when [`CountOptions`] does not say, the
+/// other environments counting like the text around them.
+pub const ENVIRONMENT_RULES: &[(&str, Region)] = &[
+    ("tikzpicture", Region::Ignore),
+    ("pgfpicture", Region::Ignore),
+    ("thebibliography", Region::Ignore),
+    ("filecontents", Region::Ignore),
+    ("filecontents*", Region::Ignore),
+];
+
+/// Environments counted as figures.
+const FIGURE_ENVIRONMENTS: &[&str] = &["figure", "figure*", "wrapfigure", "SCfigure"];
+
+/// Environments counted as tables.
+const TABLE_ENVIRONMENTS: &[&str] = &["table", "table*", "wraptable", "longtable"];
+
+/// Accents, which do not break the word they are in, such as `\"` in `na\"ive`.
+const ACCENTS: &[&str] = &["'", "`", "^", "\"", "~", "=", ".", "c", "v", "u", "H", "r", "t", "d", "b", "k"];
+
+/// Macros standing for a letter, such as `\o` in `Sch\o nberg`.
+const LETTERS: &[&str] = &["i", "j", "o", "O", "l", "L", "ss", "ae", "AE", "oe", "OE", "aa", "AA"];
+
+/// Rules overriding how words are counted, from the `[count]` table of the
+/// [configuration](crate::config).
+///
+/// ```toml
+/// [count.macros]
+/// todo = ["ignore"]
+/// keyword = ["header", "text"]
+///
+/// [count.environments]
+/// abstract = "ignore"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct CountOptions {
+    /// How the mandatory arguments of a macro are counted, in order, by macro name.
+    /// Takes precedence over [`MACRO_RULES`]. Optional arguments are never counted.
+    pub macros: BTreeMap<String, Vec<Region>>,
+    /// How the content of an environment is counted, by environment name. Takes
+    /// precedence over [`ENVIRONMENT_RULES`].
+    pub environments: BTreeMap<String, Region>,
+}
+
+/// Counts of a part of a document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Counts {
+    /// Words of the body text.
+    pub words: usize,
+    /// Words of section headers.
+    pub header_words: usize,
+    /// Words of captions.
+    pub caption_words: usize,
+    /// Words of footnotes.
+    pub footnote_words: usize,
+    /// Letters and digits of the words of the body text.
+    pub characters: usize,
+    /// Inline equations, such as `$...$`.
+    pub inline_equations: usize,
+    /// Display equations, such as `\[...\]` or an `equation` environment.
+    pub display_equations: usize,
+    /// Figure environments.
+    pub figures: usize,
+    /// Table environments.
+    pub tables: usize,
+    /// Cited keys, `\nocite` aside.
+    pub citations: usize,
+}
+
+impl AddAssign<&Counts> for Counts {
+    fn add_assign(&mut self, other: &Counts) {
+        self.words += other.words;
+        self.header_words += other.header_words;
+        self.caption_words += other.caption_words;
+        self.footnote_words += other.footnote_words;
+        self.characters += other.characters;
+        self.inline_equations += other.inline_equations;
+        self.display_equations += other.display_equations;
+        self.figures += other.figures;
+        self.tables += other.tables;
+        self.citations += other.citations;
+    }
+}
+
+/// Counts of a section of the document and of its subsections.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SectionStats {
+    /// The sectioning command starting the section.
+    pub kind: SectionKind,
+    /// Whether the starred variant (e.g. `\section*`) was used.
+    pub starred: bool,
+    /// Title of the section, as LaTeX source.
+    pub title: String,
+    /// Position of the sectioning macro.
+    pub position: PositionInfo,
+    /// Index of the file the section starts in, see [`Project`](crate::project::Project).
+    pub file: usize,
+    /// Counts of the section before its first subsection, its title included.
+    pub counts: Counts,
+    /// Counts of the section with its subsections.
+    pub total: Counts,
+    /// Subsections.
+    pub children: Vec<SectionStats>,
+}
+
+/// Counts of a document, see the [module documentation](self).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    /// Counts before the first section.
+    pub counts: Counts,
+    /// Top-level sections.
+    pub sections: Vec<SectionStats>,
+    /// Counts of the whole document.
+    pub total: Counts,
+}
+
+/// Count the words, equations, floats and citations of the document `root`.
+pub fn stats<N: Tree>(root: &N) -> Stats {
+    stats_with(root, &CountOptions::default())
+}
+
+/// Count the words, equations, floats and citations of the document `root`, with the
+/// rules of `options`.
+pub fn stats_with<N: Tree>(root: &N, options: &CountOptions) -> Stats {
+    count(root, options, &|_, _| None)
+}
+
+/// Count the document `root` with `options`, visiting the files returned by `include`
+/// in place of the macros including them.
+pub(crate) fn count<'a, N: Tree>(root: &N, options: &CountOptions, include: &'a IncludeResolver<'a, N>) -> Stats {
+    let mut counter = Counter {
+        options,
+        stats: Stats::default(),
+        open: vec![],
+        file: 0,
+        include,
+        word: (0, Region::Text),
+    };
+    let document = root.content().iter().find(|node| node.environment() == Some("document"));
+    counter.visit(document.unwrap_or(root).content(), Region::Text);
+    counter.close(None);
+    let mut stats = counter.stats;
+    stats.total = stats.counts;
+    for section in &stats.sections {
+        stats.total += &section.total;
+    }
+    stats
+}
+
+/// Where a rule sends the words of the text in `region`.
+fn resolve(rule: Region, region: Region) -> Region {
+    match rule {
+        Region::Text => region,
+        rule => rule,
+    }
+}
+
+struct Counter<'a, 'o, N> {
+    options: &'o CountOptions,
+    stats: Stats,
+    /// Sections whose end has not been seen yet.
+    open: Vec<SectionStats>,
+    /// Index of the file being visited.
+    file: usize,
+    include: &'a IncludeResolver<'a, N>,
+    /// Letters and digits of the word being read, and where it is.
+    word: (usize, Region),
+}
+
+impl<N: Tree> Counter<'_, '_, N> {
+    /// Counts of the innermost open section.
+    fn counts(&mut self) -> &mut Counts {
+        match self.open.last_mut() {
+            Some(section) => &mut section.counts,
+            None => &mut self.stats.counts,
+        }
+    }
+
+    fn close(&mut self, kind: Option<SectionKind>) {
+        while self.open.last().is_some_and(|s| kind.is_none_or(|kind| s.kind >= kind)) {
+            let mut section = self.open.pop().unwrap();
+            section.total = section.counts;
+            for child in &section.children {
+                section.total += &child.total;
+            }
+            match self.open.last_mut() {
+                Some(parent) => parent.children.push(section),
+                None => self.stats.sections.push(section),
+            }
+        }
+    }
+
+    fn end_word(&mut self) {
+        let (letters, region) = std::mem::replace(&mut self.word, (0, Region::Text));
+        if letters == 0 {
+            return;
+        }
+        let counts = self.counts();
+        match region {
+            Region::Text => {
+                counts.words += 1;
+                counts.characters += letters;
+            }
+            Region::Header => counts.header_words += 1,
+            Region::Caption => counts.caption_words += 1,
+            Region::Footnote => counts.footnote_words += 1,
+            Region::Ignore => {}
+        }
+    }
+
+    /// Add `letters` letters to the word being read.
+    fn letters(&mut self, letters: usize, region: Region) {
+        self.word = (self.word.0 + letters, region);
+    }
+
+    fn visit(&mut self, content: &[N], region: Region) {
+        if region == Region::Ignore {
+            return;
+        }
+        self.end_word();
+        let mut i = 0;
+        while i < content.len() {
+            i += 1 + self.node(content, i, region);
+        }
+        self.end_word();
+    }
+
+    /// Count `siblings[i]`, returning how many of the following siblings it took as
+    /// arguments.
+    fn node(&mut self, siblings: &[N], i: usize, region: Region) -> usize {
+        let node = &siblings[i];
+        if let Some(content) = node.string() {
+            for c in content.chars() {
+                if c.is_whitespace() || c == '~' {
+                    self.end_word();
+                } else if c.is_alphanumeric() {
+                    self.letters(1, region);
+                }
+            }
+            return 0;
+        }
+        match node.macro_name() {
+            Some(name) if LETTERS.contains(&name) => {
+                self.letters(1, region);
+                // the space ending the name of the macro is not printed
+                return usize::from(siblings.get(i + 1).is_some_and(N::is_whitespace));
+            }
+            Some(name) if node.args().is_empty() && ACCENTS.contains(&name) => {
+                // the accented letter is in a group, or the string that follows
+                if siblings.get(i + 1).is_some_and(N::is_group) {
+                    self.letters(1, region);
+                    return 1;
+                }
+                return 0;
+            }
+            _ => {}
+        }
+        self.end_word();
+        if node.is_inline_math() {
+            self.counts().inline_equations += 1;
+        } else if node.is_display_math() {
+            self.counts().display_equations += 1;
+        } else if node.is_group() {
+            self.visit(node.content(), region);
+        } else if let Some(env) = node.environment() {
+            if FIGURE_ENVIRONMENTS.contains(&env) {
+                self.counts().figures += 1;
+            } else if TABLE_ENVIRONMENTS.contains(&env) {
+                self.counts().tables += 1;
+            }
+            let rule = self.options.environments.get(env).copied();
+            let rule = rule.or_else(|| ENVIRONMENT_RULES.iter().find(|(name, _)| *name == env).map(|(_, rule)| *rule));
+            self.visit(node.content(), resolve(rule.unwrap_or(Region::Text), region));
+        } else if node.macro_name().is_some() {
+            return self.macro_node(siblings, i, region);
+        }
+        0
+    }
+
+    fn macro_node(&mut self, siblings: &[N], i: usize, region: Region) -> usize {
+        let node = &siblings[i];
+        if let Some((file, ast)) = (self.include)(self.file, node) {
+            let parent = std::mem::replace(&mut self.file, file);
+            self.visit(ast.content(), region);
+            self.file = parent;
+            return 0;
+        }
+        let Some(name) = node.macro_name() else {
+            return 0;
+        };
+
+        if CITE_MACROS.contains(&name) {
+            let Some(call) = macro_call(siblings, i, 1) else {
+                return 0;
+            };
+            if name != "nocite" {
+                self.counts().citations += call.mandatory.last().map_or(0, |arg| keys(arg).len());
+            }
+            return call.consumed;
+        }
+
+        let rules = self.options.macros.get(name).map(Vec::as_slice);
+        let Some(rules) = rules.or_else(|| MACRO_RULES.iter().find(|(n, _)| *n == name).map(|(_, rules)| *rules)) else {
+            // the arguments the parser attached, such as the body of `\item`; the groups
+            // following unknown macros are visited as siblings
+            for arg in node.args() {
+                if arg.open_mark().is_some_and(|open| open != "[") {
+                    self.visit(arg.content(), region);
+                }
+            }
+            return 0;
+        };
+        let Some(call) = macro_call(siblings, i, rules.len()) else {
+            return 0;
+        };
+        if let Some(kind) = SectionKind::from_macro(name) {
+            self.close(Some(kind));
+            self.open.push(SectionStats {
+                kind,
+                starred: call.star,
+                title: call.last_text().unwrap_or_default(),
+                position: node.position().cloned().unwrap_or_default(),
+                file: self.file,
+                counts: Counts::default(),
+                total: Counts::default(),
+                children: vec![],
+            });
+        }
+        for (content, rule) in call.mandatory.iter().zip(rules) {
+            self.visit(content, resolve(*rule, region));
+        }
+        call.consumed
+    }
+}
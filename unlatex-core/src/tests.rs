@@ -69,3 +69,1134 @@ Check it out!
 
     println!("{}", ast);
 }
+
+#[test]
+fn test_config_overrides() {
+    let config = Config::from_toml(r#"
+print-width = 100
+tab-width = 4
+
+[[overrides]]
+files = ["tables/*.tex"]
+print-width = 200
+
+[[overrides]]
+files = ["*.sty.tex"]
+use-tabs = true
+"#, "/project/.latexformat.toml").unwrap().unwrap();
+
+    let base = FormatOptions::default();
+    let opts = config.resolve("/project/main.tex", &base);
+    assert_eq!(opts, FormatOptions { print_width: 100, tab_width: 4, ..base.clone() });
+
+    let opts = config.resolve("/project/tables/results.tex", &base);
+    assert_eq!(opts, FormatOptions { print_width: 200, tab_width: 4, ..base.clone() });
+
+    let opts = config.resolve("/project/sub/tables/results.tex", &base);
+    assert_eq!(opts.print_width, 100);
+
+    let opts = config.resolve("/project/sub/macros.sty.tex", &base);
+    assert!(opts.use_tabs);
+
+    assert!(Config::from_toml("[tool.black]\nline-length = 3", "/project/pyproject.toml").unwrap().is_none());
+    assert!(matches!(Config::from_toml("print-width = \"wide\"", "/project/.latexformat.toml"), Err(Error::Config { .. })));
+
+    // misspelled keys are errors rather than silently ignored
+    for src in ["print-widht = 100", "[[overrides]]\nfiles = [\"*.tex\"]\ntab_width = 4", "[bib]\nsort-entry = true", "[lint]\ndisabled = [\"quotes\"]", "[count]\nmacro = {}"] {
+        let err = Config::from_toml(src, "/project/.latexformat.toml").unwrap_err();
+        assert!(matches!(&err, Error::Config { message, .. } if message.contains("unknown field")), "{src}: {err}");
+    }
+    let src = "[tool.latexformat]\nprint-width = 100\nlint = { disable = [\"quotes\"] }\n";
+    assert!(Config::from_toml(src, "/project/pyproject.toml").unwrap().is_some());
+}
+
+#[test]
+fn test_config_discover() {
+    let dir = tempfile::tempdir().unwrap();
+    let nested = dir.path().join("chapters/intro");
+    std::fs::create_dir_all(&nested).unwrap();
+    std::fs::write(dir.path().join("pyproject.toml"), "[tool.latexformat]\nprint-width = 90\n").unwrap();
+    std::fs::write(dir.path().join("chapters/.latexformat.toml"), "tab-width = 8\n").unwrap();
+
+    let opts = config::resolve_options(nested.join("intro.tex"), &FormatOptions::default()).unwrap();
+    assert_eq!((opts.print_width, opts.tab_width), (80, 8));
+
+    let opts = config::resolve_options(dir.path().join("main.tex"), &FormatOptions::default()).unwrap();
+    assert_eq!((opts.print_width, opts.tab_width), (90, 2));
+}
+
+#[test]
+fn test_format_range() {
+    let input = "\\section{A}\n$a  +   b$\n\n\\emph{é}   and   $c  +   d$\n";
+    let start = input.find("\\emph").unwrap();
+    let formatted = format_range(input, start..start + 10, &FormatOptions::default()).unwrap();
+    assert_eq!(formatted, "\\section{A}\n$a  +   b$\n\n\\emph{é} and $c + d$\n");
+
+    let input = "\\begin{document}\n  a    b\n\n  \\begin{itemize}\n    \\item c    d\n  \\end{itemize}\n\\end{document}";
+    let start = input.find("c ").unwrap();
+    let formatted = format_range(input, start..start, &FormatOptions::default()).unwrap();
+    assert_eq!(formatted, "\\begin{document}\n  a    b\n\n  \\begin{itemize}\n    \\item c d\n  \\end{itemize}\n\\end{document}");
+
+    let input = "a    b\n\n\\begin{tabular}{ll} a & b \\\\ c & d \\end{tabular}\n";
+    let start = input.find("c &").unwrap();
+    let formatted = format_range(input, start..start + 1, &FormatOptions::default()).unwrap();
+    assert_eq!(formatted, input);
+    let formatted = format_range(input, 0..input.len(), &FormatOptions::default()).unwrap();
+    assert_eq!(formatted, "a b\n\n\\begin{tabular}{ll}\n  a & b \\\\\n  c & d\n\\end{tabular}\n");
+
+    assert!(matches!(format_range("é", 0..1, &FormatOptions::default()), Err(Error::InvalidRange { .. })));
+}
+
+#[test]
+fn test_span_offsets() {
+    let text = "é😀 $x$\nb\nc";
+    assert_eq!(span::to_utf16_offset(text, 6), 3);
+    assert_eq!(span::to_byte_offset(text, 3), 6);
+    assert_eq!(span::to_byte_offset(text, 100), text.len());
+    assert_eq!(span::to_byte_offset(text, 2), 2);
+    assert_eq!(span::line_range(text, 2, 2), Some(11..13));
+    assert_eq!(span::line_range(text, 2, 9), Some(11..14));
+    assert_eq!(span::line_range(text, 4, 4), None);
+
+    let index = span::LineIndex::new(text);
+    assert_eq!(index.line_count(), 3);
+    assert_eq!(index.line_col(7), (0, 4));
+    assert_eq!(index.line_col(12), (1, 1));
+    assert_eq!(index.offset(0, 4), 7);
+    assert_eq!(index.offset(1, 9), 12);
+    assert_eq!(index.offset(7, 0), text.len());
+}
+
+#[test]
+fn test_format_ignore() {
+    let input = r#"\section{A}   B
+
+% latexformat-ignore
+\begin{tabular}{ll}
+a   & b \\
+cc  & d
+\end{tabular}
+
+x    y % latexformat-ignore
+$a   +   b$ z    w
+
+% latexformat: off
+\begin{tikzpicture}
+    \draw   (0,0) -- (1,1);
+\end{tikzpicture}
+% latexformat: on
+p    q"#;
+    let formatted = format(input).unwrap();
+    assert_eq!(formatted, r#"\section{A}
+B
+
+% latexformat-ignore
+\begin{tabular}{ll}
+a   & b \\
+cc  & d
+\end{tabular}
+
+x y % latexformat-ignore
+$a   +   b$ z w
+
+% latexformat: off
+\begin{tikzpicture}
+    \draw   (0,0) -- (1,1);
+\end{tikzpicture}
+% latexformat: on
+p q"#);
+
+    let input = "% latexformat-ignore-file\n\\section{A}   B";
+    assert_eq!(format(input).unwrap(), input);
+
+    // letters right after an ignored node are not taken into the placeholder
+    let input = "% latexformat-ignore\n\\textbf{x   }y and    more\n";
+    assert_eq!(format(input).unwrap(), "% latexformat-ignore\n\\textbf{x   }y and more");
+
+    // a placeholder the formatter dropped is an error rather than lost text
+    let input = "a\n% latexformat-ignore\n\\textbf{x}y\n";
+    let ast = parse(input).unwrap();
+    let lost = ignore::format_preserving(input, &ast, |masked| Ok(masked.replace("{}", "")));
+    assert!(matches!(lost, Err(Error::IgnoredRegionLost { line: 3 })));
+}
+
+#[test]
+fn test_outline() {
+    let source = r#"\label{top}
+\chapter{One}
+\section[Short]{A \emph{long} title}\label{sec:a}
+\begin{figure}\label{fig:a}\end{figure}
+\subsection*{Sub}
+\paragraph{Para}
+\section{B}
+\part{Two}
+\chapter{Three}"#;
+    let ast = parse(source).unwrap();
+    let outline = outline::outline(&ast);
+    assert_eq!(outline::outline(&parse_borrowed(source).unwrap()), outline);
+
+    assert_eq!(outline.labels.iter().map(|l| l.name.as_str()).collect::<Vec<_>>(), ["top"]);
+    assert_eq!(outline.sections.len(), 2);
+    let one = &outline.sections[0];
+    assert_eq!((one.kind, one.title.as_str()), (outline::SectionKind::Chapter, "One"));
+    assert_eq!(one.children.len(), 2);
+
+    let a = &one.children[0];
+    assert_eq!(a.title, "A \\emph{long} title");
+    assert_eq!(a.short_title.as_deref(), Some("Short"));
+    assert_eq!(a.labels.iter().map(|l| l.name.as_str()).collect::<Vec<_>>(), ["sec:a"]);
+    assert_eq!(a.position.start.line, 3);
+    assert!(!a.starred);
+    let figure = &a.environments[0];
+    assert_eq!((figure.name.as_str(), figure.labels[0].name.as_str(), figure.position.start.line), ("figure", "fig:a", 4));
+
+    let sub = &a.children[0];
+    assert!(sub.starred);
+    assert_eq!(sub.children[0].kind, outline::SectionKind::Paragraph);
+
+    let two = &outline.sections[1];
+    assert_eq!((two.kind, two.children[0].title.as_str()), (outline::SectionKind::Part, "Three"));
+}
+
+#[test]
+fn test_refs() {
+    use analysis::refs::{References, RefIssue};
+
+    let ast = parse(r#"\section{A}\label{sec:a}
+\begin{equation}x\label{eq:x}\end{equation}
+\label{sec:a}\label{unused}
+See \ref{sec:a}, \eqref{eq:x}, \cref{sec:a, fig:missing} and \autoref*{tab:b}.
+% \ref{commented}
+"#).unwrap();
+
+    let refs = References::collect(&ast);
+    assert_eq!(refs.labels.iter().map(|l| l.name.as_str()).collect::<Vec<_>>(), ["sec:a", "eq:x", "sec:a", "unused"]);
+    assert_eq!(refs.references.iter().map(|r| r.name.as_str()).collect::<Vec<_>>(), ["sec:a", "eq:x", "sec:a", "fig:missing", "tab:b"]);
+
+    let issues = refs.check();
+    let names: Vec<_> = issues.iter().map(|i| match i {
+        RefIssue::UndefinedReference { name, .. } => format!("undefined {name}"),
+        RefIssue::DuplicateLabel { name, first, .. } => format!("duplicate {name} {}", first.start.line),
+        RefIssue::UnusedLabel { name, .. } => format!("unused {name}"),
+    }).collect();
+    assert_eq!(names, ["duplicate sec:a 1", "undefined fig:missing", "undefined tab:b", "unused unused"]);
+    assert_eq!(issues[1].position().start.line, 4);
+}
+
+#[test]
+fn test_bib() {
+    use bib::{Bibliography, Item, ValuePart};
+
+    let src = r#"% a comment line
+@String{ pub = "Addison" }
+@comment{ ignored @book{not, an = entry} }
+@preamble{ "\newcommand{\noop}[1]{}" }
+@ARTICLE(knuth84,
+  author = {Donald {E.} Knuth},
+  title  = "Literate {"}Programming{"}",
+  publisher = pub # { Wesley},
+  year = 1984,
+  month = mar,
+)
+@book{broken, title = {unbalanced}
+@misc{ok, note = {fine}}
+"#;
+    let bib = Bibliography::parse(src);
+    assert_eq!(bib.entries().map(|e| e.key.as_str()).collect::<Vec<_>>(), ["knuth84", "ok"]);
+    assert!(matches!(&bib.items[0], Item::Comment { text, .. } if text.trim() == "% a comment line"));
+    assert!(matches!(&bib.items[2], Item::Comment { text, .. } if text.contains("@book")));
+
+    let entry = bib.get("knuth84").unwrap();
+    assert_eq!(entry.entry_type, "ARTICLE");
+    assert_eq!(entry.fields.len(), 5);
+    assert_eq!(&src[entry.key_span.clone()], "knuth84");
+    assert!(src[entry.span.clone()].starts_with("@ARTICLE(") && src[entry.span.clone()].ends_with(')'));
+    assert_eq!(entry.field("AUTHOR").unwrap().value.parts, [ValuePart::Braced("Donald {E.} Knuth".to_string())]);
+    assert_eq!(bib.resolve(&entry.field("title").unwrap().value), r#"Literate {"}Programming{"}"#);
+    assert_eq!(bib.resolve(&entry.field("publisher").unwrap().value), "Addison Wesley");
+    assert_eq!(bib.resolve(&entry.field("year").unwrap().value), "1984");
+    assert_eq!(bib.resolve(&entry.field("month").unwrap().value), "March");
+
+    assert_eq!(bib.errors.len(), 1);
+    assert!(src[bib.errors[0].span.clone()].starts_with("@book{broken"));
+}
+
+#[test]
+fn test_citations() {
+    use analysis::citations::{Citations, CiteIssue};
+    use bib::Bibliography;
+
+    let ast = parse(r#"\cite{a} \citet[see][ch.~2]{b, c}
+\parencite[12]{missing} % \cite{commented}
+\autocite*{a}
+\bibliography{refs,more}
+"#).unwrap();
+    let citations = Citations::collect(&ast);
+    let cited: Vec<_> = citations.citations.iter().map(|c| (c.key.as_str(), c.macro_name.as_str())).collect();
+    assert_eq!(cited, [("a", "cite"), ("b", "citet"), ("c", "citet"), ("missing", "parencite"), ("a", "autocite")]);
+    assert_eq!(citations.citations[1].prenote.as_deref(), Some("see"));
+    assert_eq!(citations.citations[1].postnote.as_deref(), Some("ch.~2"));
+    assert_eq!(citations.citations[3].postnote.as_deref(), Some("12"));
+    assert_eq!(citations.citations[0].prenote, None);
+    assert_eq!(citations.databases, ["refs", "more"]);
+
+    let databases = [
+        Bibliography::parse("@book{a, title={A}}\n@book{unused, title={U}}"),
+        Bibliography::parse("@book{b,}\n@book{c,}"),
+    ];
+    let issues = citations.check(&databases);
+    assert_eq!(issues.len(), 2);
+    assert!(matches!(&issues[0], CiteIssue::MissingEntry { key, position, .. } if key == "missing" && position.start.line == 2));
+    assert!(matches!(&issues[1], CiteIssue::UncitedEntry { key, database: 0, span } if key == "unused" && *span == (26..32)));
+
+    let ast = parse(r"\nocite{*}\cite{a}").unwrap();
+    assert!(Citations::collect(&ast).check(&databases).is_empty());
+}
+
+#[test]
+fn test_bib_format() {
+    use bib::{BibOptions, Bibliography, Delimiter};
+
+    let src = r#"% Header comment
+@STRING{tug = "TUG"}
+
+@Article{lamport94,TITLE="A  Document
+   Preparation System",   Author = {Leslie Lamport},
+  URL = {http://example.com/a  b}, publisher = tug # { Press}}
+% about knuth
+@book{Knuth84, title = {The {\TeX}book}, year = 1984,}
+@misc{empty,}
+"#;
+    let opts = FormatOptions::default();
+    assert_eq!(bib::format(src, &opts, &BibOptions::default()).unwrap(), r#"% Header comment
+
+@string{tug = {TUG}}
+
+@article{lamport94,
+  title     = {A Document Preparation System},
+  author    = {Leslie Lamport},
+  url       = {http://example.com/a  b},
+  publisher = tug # { Press},
+}
+
+% about knuth
+
+@book{Knuth84,
+  title = {The {\TeX}book},
+  year  = 1984,
+}
+
+@misc{empty}
+"#);
+
+    let bib_opts = BibOptions {
+        field_order: vec!["author".to_string(), "year".to_string()],
+        sort_entries: true,
+        delimiter: Delimiter::Quotes,
+        trailing_comma: false,
+    };
+    let opts = FormatOptions { print_width: 30, use_tabs: true, tab_width: 4, ..FormatOptions::default() };
+    assert_eq!(bib::format(src, &opts, &bib_opts).unwrap(), "% Header comment
+
+@string{tug = \"TUG\"}
+
+@misc{empty}
+
+% about knuth
+
+@book{Knuth84,
+\tyear  = 1984,
+\ttitle = \"The {\\TeX}book\"
+}
+
+@article{lamport94,
+\tauthor    = \"Leslie
+\t             Lamport\",
+\ttitle     = \"A Document
+\t             Preparation
+\t             System\",
+\turl       = \"http://example.com/a  b\",
+\tpublisher = tug # \" Press\"
+}
+");
+
+    // formatting is stable
+    let formatted = bib::format(src, &opts, &bib_opts).unwrap();
+    assert_eq!(bib::format(&formatted, &opts, &bib_opts).unwrap(), formatted);
+
+    assert!(matches!(bib::format("@book{a, title = {x}", &opts, &bib_opts), Err(Error::Bib { line: 1, .. })));
+
+    let bib = Bibliography::parse("@book{a,}\n@book{b,}\n@book{A,}");
+    let duplicates: Vec<_> = bib.duplicates().iter().map(|(first, dup)| (first.key.as_str(), dup.key.as_str())).collect();
+    assert_eq!(duplicates, [("a", "A")]);
+
+    let config = Config::from_toml("[bib]\nfield-order = [\"title\"]\ndelimiter = \"quotes\"", "latexformat.toml").unwrap().unwrap();
+    assert_eq!(config.bib.field_order, ["title"]);
+    assert_eq!(config.bib.delimiter, Delimiter::Quotes);
+    assert!(config.bib.trailing_comma);
+}
+
+#[test]
+fn test_project() {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use analysis::refs::RefIssue;
+    use project::{Project, ProjectIssue};
+
+    let files: HashMap<PathBuf, String> = [
+        ("thesis/main.tex", r"\documentclass{report}
+\begin{document}
+\include{chapters/intro}
+% \input{commented}
+\subfile{chapters/results.tex}
+\import{parts/}{appendix}
+\input{missing}
+\ref{sec:intro} \ref{sec:app} \cite{knuth}
+\end{document}"),
+        ("thesis/chapters/intro.tex", r"\chapter{Intro}\label{sec:intro}\input{chapters/shared}"),
+        ("thesis/chapters/results.tex", r"\chapter{Results}\input{chapters/shared}\ref{sec:nowhere}"),
+        ("thesis/chapters/shared.tex", r"\section{Shared}"),
+        ("thesis/parts/appendix.tex", r"\chapter{Appendix}\label{sec:app}\input{notes}"),
+        ("thesis/parts/notes.tex", r"\section{Notes}\input{appendix}"),
+    ]
+    .into_iter()
+    .map(|(path, src)| (PathBuf::from(path), src.to_string()))
+    .collect();
+
+    let missing = Project::load_with("thesis/other.tex", &files).unwrap_err();
+    assert!(matches!(&missing, Error::Include { path, .. } if path == "thesis/other.tex"), "{missing}");
+    assert!(missing.to_string().contains("not found"));
+
+    let project = Project::load_with("thesis/./main.tex", &files).unwrap();
+    let paths: Vec<_> = project.files.iter().map(|f| f.path.to_str().unwrap()).collect();
+    assert_eq!(paths, [
+        "thesis/main.tex",
+        "thesis/chapters/intro.tex",
+        "thesis/chapters/shared.tex",
+        "thesis/chapters/results.tex",
+        "thesis/parts/appendix.tex",
+        "thesis/parts/notes.tex",
+    ]);
+
+    let root = project.root();
+    let includes: Vec<_> = root.includes.iter().map(|i| (i.macro_name.as_str(), i.target.as_str(), i.file)).collect();
+    assert_eq!(includes, [
+        ("include", "chapters/intro", Some(1)),
+        ("subfile", "chapters/results.tex", Some(3)),
+        ("import", "parts/appendix", Some(4)),
+        ("input", "missing", None),
+    ]);
+    assert_eq!(&root.source[root.includes[2].range.clone()], r"\import{parts/}{appendix}");
+    assert_eq!(project.file("thesis/chapters/results.tex").unwrap().includes[0].file, Some(2));
+
+    let issues: Vec<_> = project.issues.iter().map(|issue| match issue {
+        ProjectIssue::MissingFile { file, include, .. } => format!("missing {} in {file}", include.target),
+        ProjectIssue::Cycle { file, include } => format!("cycle {} in {file}", include.target),
+    }).collect();
+    assert_eq!(issues, ["cycle appendix in 5", "missing missing in 0"]);
+
+    let refs: Vec<_> = project.references().check().iter().map(|issue| match issue {
+        RefIssue::UndefinedReference { name, file, .. } => format!("undefined {name} in {file}"),
+        issue => issue.to_string(),
+    }).collect();
+    assert_eq!(refs, ["undefined sec:nowhere in 3"]);
+    assert_eq!(project.citations().citations[0].key, "knuth");
+
+    let outline = project.outline();
+    let titles: Vec<_> = outline.sections.iter().map(|s| (s.title.as_str(), s.file, s.children.len())).collect();
+    assert_eq!(titles, [("Intro", 1, 1), ("Results", 3, 1), ("Appendix", 4, 1)]);
+    assert_eq!(outline.sections[0].children[0].file, 2);
+    assert_eq!(outline.sections[0].labels[0].file, 1);
+}
+
+#[test]
+fn test_flatten() {
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use flatten::{flatten_with, FlattenOptions};
+
+    let files: HashMap<PathBuf, String> = [
+        ("paper/main.tex", "\\documentclass{article}\n\\begin{document}\n\\include{intro}\n% \\input{old}\n\\subfile{sections/body}\nText% joined\nmore % note\n  % own line\n\\bibliographystyle{plain}\n\\bibliography{refs}\n\\end{document}\n"),
+        ("paper/intro.tex", "Intro \\input{sections/fig}.\n"),
+        ("paper/sections/fig.tex", "Fig"),
+        ("paper/sections/body.tex", "\\documentclass[../main]{subfiles}\n\\begin{document}\nBody é.\n\\end{document}\n"),
+        ("paper/main.bbl", "\\begin{thebibliography}{1}\n\\end{thebibliography}\n"),
+    ]
+    .into_iter()
+    .map(|(path, src)| (PathBuf::from(path), src.to_string()))
+    .collect();
+
+    let flat = flatten_with("paper/main.tex", &FlattenOptions::default(), &files).unwrap();
+    assert_eq!(flat, "\\documentclass{article}\n\\begin{document}\n\\clearpage\nIntro Fig.\n\\clearpage\n% \\input{old}\nBody é.\nText% joined\nmore % note\n  % own line\n\\bibliographystyle{plain}\n\\bibliography{refs}\n\\end{document}\n");
+
+    let opts = FlattenOptions { strip_comments: true, inline_bbl: true };
+    let flat = flatten_with("paper/main.tex", &opts, &files).unwrap();
+    assert_eq!(flat, "\\documentclass{article}\n\\begin{document}\n\\clearpage\nIntro Fig.\n\\clearpage\nBody é.\nText%\nmore\n\\bibliographystyle{plain}\n\\begin{thebibliography}{1}\n\\end{thebibliography}\n\\end{document}\n");
+
+    let mut broken = files.clone();
+    broken.remove(&PathBuf::from("paper/sections/fig.tex"));
+    assert!(matches!(flatten_with("paper/main.tex", &opts, &broken), Err(Error::Include { path, .. }) if path == "paper/intro.tex"));
+}
+
+#[test]
+fn test_cleanup() {
+    use cleanup::{cleanup, CleanupOptions};
+
+    let src = r"\documentclass{article}
+% preamble note
+\begin{document}
+First paragraph% no space
+continues. % trailing
+  % indented own line
+\todo[inline]{rewrite}
+Text \todo{check} here \emph{kept}.
+% between paragraphs
+
+\begin{comment}
+draft
+\end{comment}
+\iffalse
+\ifx\a\b nested \fi
+old
+\fi
+Value: \iffalse 1\else 2\fi.
+\verb|% not a comment| and 50\% off.
+\end{document}
+";
+    assert_eq!(cleanup(src, &CleanupOptions::default()).unwrap(), r"\documentclass{article}
+\begin{document}
+First paragraph%
+continues.
+Text here \emph{kept}.
+
+Value: 2.
+\verb|% not a comment| and 50\% off.
+\end{document}
+");
+
+    let opts = CleanupOptions {
+        comments: false,
+        environments: vec![],
+        macros: vec!["emph".to_string()],
+        conditionals: false,
+    };
+    let out = cleanup(src, &opts).unwrap();
+    assert!(out.contains("Text \\todo{check} here .\n") && out.contains("% preamble note") && out.contains("\\iffalse"));
+
+    // indented comments starting a list or the document have no position
+    let src = "\\begin{itemize}\n  % note\n  \\item a\n\\end{itemize}\n";
+    assert_eq!(cleanup(src, &CleanupOptions::comments_only()).unwrap(), "\\begin{itemize}\n  \\item a\n\\end{itemize}\n");
+    assert_eq!(cleanup("  % x\ntext", &CleanupOptions::comments_only()).unwrap(), "text");
+    assert_eq!(cleanup("\t% x\n\t% x\ntext % y\n", &CleanupOptions::comments_only()).unwrap(), "text\n");
+}
+
+#[test]
+fn test_lint() {
+    use lint::{lint, LintOptions, Severity};
+
+    let src = r#"\documentclass{article}
+\def\x{x}
+\begin{document}
+\def\y{y}
+See "this" and Figure \ref{a}, Figure~\ref{b}.
+{\bf bold} $a \over b$ and $\left( x \right)$, $\left[ y$.
+$$ z $$ \[ w \]
+Hmm... and .. or $1, 2, \ldots$.
+\end{document}
+"#;
+    let diagnostics = lint(src, &LintOptions::default()).unwrap();
+    let found: Vec<_> = diagnostics.iter().map(|d| (d.rule.as_str(), d.position.start.line)).collect();
+    assert_eq!(found, [
+        ("def-in-body", 4),
+        ("quotes", 5),
+        ("quotes", 5),
+        ("ref-tie", 5),
+        ("obsolete-font", 6),
+        ("over", 6),
+        ("left-right", 6),
+        ("dollar-display-math", 7),
+        ("ellipsis", 8),
+    ]);
+    assert_eq!(diagnostics[6].severity, Severity::Error);
+
+    let opts = LintOptions {
+        disable: vec!["quotes".to_string()],
+        severity: [("ellipsis".to_string(), Severity::Info)].into(),
+        ..LintOptions::default()
+    };
+    let diagnostics = lint(src, &opts).unwrap();
+    assert!(diagnostics.iter().all(|d| d.rule != "quotes"));
+    assert_eq!(diagnostics.last().unwrap().severity, Severity::Info);
+
+    let config = Config::from_toml("[lint]\nenable = [\"x\"]\nseverity = { over = \"error\" }\n", "a.toml").unwrap().unwrap();
+    assert_eq!(config.lint.enable, ["x"]);
+    assert_eq!(config.lint.severity["over"], Severity::Error);
+
+    let fixes = diagnostics.iter().filter(|d| d.fix.is_some()).map(|d| d.rule.as_str()).collect::<Vec<_>>();
+    assert_eq!(fixes, ["ref-tie", "obsolete-font", "dollar-display-math", "ellipsis"]);
+    assert_eq!(lint::fix(src, &opts).unwrap(), r#"\documentclass{article}
+\def\x{x}
+\begin{document}
+\def\y{y}
+See "this" and Figure~\ref{a}, Figure~\ref{b}.
+\textbf{bold} $a \over b$ and $\left( x \right)$, $\left[ y$.
+\[ z \] \[ w \]
+Hmm\dots{} and .. or $1, 2, \ldots$.
+\end{document}
+"#);
+
+    // overlapping fixes are applied over several passes
+    let fixed = lint::fix(r#"{\it {\bf x} "y"} \rm z"#, &LintOptions::default()).unwrap();
+    assert_eq!(fixed, r"\textit{\textbf{x} ``y''} \rmfamily z");
+
+    // a group which may be the argument of a macro before it is kept
+    let fixed = lint::fix(r"\mytitle{\bf Foo} \frac{1}{\rm x} \item[a] {\it b} $\sqrt{\bf v}$", &LintOptions::default()).unwrap();
+    assert_eq!(fixed, r"\mytitle{\bfseries Foo} \frac{1}{\rmfamily x} \item[a] {\itshape b} $\sqrt{\bf v}$");
+}
+
+#[test]
+fn test_parse_diagnostics() {
+    use diagnostics::Severity;
+
+    let src = r"\begin{document}
+\begin{itemize}
+\item {a} } and $x + y
+\item \begin{center} b \end{figure}
+\[ z
+\end{document}
+";
+    let (ast, diagnostics) = parse_with_diagnostics(src).unwrap();
+    assert!(matches!(&ast.content()[0], ast::Node::Environment { env, .. } if env == "document"));
+    let found: Vec<_> = diagnostics.iter().map(|d| (&src[d.span.clone()], d.message.as_str())).collect();
+    assert_eq!(found, [
+        (r"\begin{itemize}", "environment `itemize` is never closed"),
+        ("}", "`}` closes no group"),
+        ("$", "inline math `$` is never closed"),
+        (r"\end{figure}", r"`\end{figure}` does not match `\begin{center}`"),
+        (r"\[", r"display math `\[` is never closed"),
+    ]);
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+
+    let (_, diagnostics) = parse_with_diagnostics("$$x$$ \\verb|a{| \\begin{verbatim}\nx").unwrap();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message, "verbatim environment `verbatim` is never closed");
+
+    // the parser overflows its stack, which is reported rather than failing
+    let nested = "{".repeat(3000);
+    let (ast, diagnostics) = parse_with_diagnostics(&nested).unwrap();
+    assert!(ast.content().is_empty());
+    assert_eq!((diagnostics[0].span.clone(), diagnostics[0].severity), (0..3000, Severity::Error));
+    assert!(parse_with_diagnostics("a {b} c").unwrap().1.is_empty());
+
+    // math delimiters in column specifications are balanced by the cells
+    for src in [
+        "\\begin{tabular}{>{$}c<{$}} a \\end{tabular}",
+        "\\begin{tabular}[t]{l>{$}c<{$}} a & b \\end{tabular}",
+        "\\begin{tabularx}{\\linewidth}{>{$}X<{$}} a \\end{tabularx}",
+        "\\newcolumntype{C}{>{$}c<{$}}",
+        "\\newcolumntype{P}[1]{>{$}p{#1}<{$}}",
+    ] {
+        assert!(parse_with_diagnostics(src).unwrap().1.is_empty(), "{src}");
+    }
+    // but the cells are checked
+    let (_, diagnostics) = parse_with_diagnostics("\\begin{tabular}{>{$}c<{$}} $a \\multicolumn{2}{>{$}c<{$}}{b {} \\end{tabular}").unwrap();
+    let messages: Vec<_> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+    assert_eq!(messages, ["inline math `$` is never closed", "`{` is never closed"]);
+}
+
+#[test]
+fn test_render_diagnostics() {
+    use diagnostics::{render, Diagnostic, Severity};
+
+    let src = "é \\begin{x}\n  a\n  b\n\tc \\end{y} d\n";
+    let start = src.find("\\begin").unwrap();
+    let end = src.find(" d").unwrap();
+    let diagnostic = Diagnostic::new(Severity::Error, "mismatched", start..end);
+    assert_eq!(render(&diagnostic, "doc.tex", src), "error: mismatched
+ --> doc.tex:1:3
+  |
+1 | é \\begin{x}
+  |   ^^^^^^^^^
+  ...
+4 | \tc \\end{y} d
+  | ^^^^^^^^^^
+");
+
+    // the rule ID is shown as code, and a span ending a line stays on it
+    let lint = lint::lint("Wait...\n", &lint::LintOptions::default()).unwrap();
+    let mut diagnostic = lint[0].to_diagnostic(&span::OffsetMap::new("Wait...\n"));
+    assert_eq!(render(&diagnostic, "a.tex", "Wait...\n"), "warning[ellipsis]: use `\\dots` instead of `...`
+ --> a.tex:1:5
+  |
+1 | Wait...
+  |     ^^^
+");
+    diagnostic.span = 4..8;
+    assert!(render(&diagnostic, "a.tex", "Wait...\n").ends_with("1 | Wait...\n  |     ^^^\n"));
+    diagnostic.span = 8..8;
+    assert!(render(&diagnostic, "a.tex", "Wait...\n").contains(" --> a.tex:2:1\n  |\n2 | \n  | ^\n"));
+}
+
+#[cfg(feature = "native-parser")]
+#[test]
+fn test_native_parse() {
+    let snippets = [
+        "",
+        "   ",
+        "\n\n\n",
+        "a  b\n\nc",
+        "%only a comment",
+        "  % indented\ntext % sameline\n\n% before parbreak\n\nmore",
+        "\\item a \\item b",
+        "\\begin{itemize}\\end{itemize}",
+        "\\begin{itemize} text before \\item a %c\n\n\\item b\n\\end{itemize}",
+        "\\begin{enumerate}\\item{}\\item[x]\\end{enumerate}",
+        "\\frac{a}{b}c \\frac a b \\frac{ab}",
+        "$\\frac{x^2}{y_1}$ \\sqrt{a b}",
+        "\\section*[short]{long} \\section {spaced}",
+        "\\textbf\n{x} \\textbf%\n{y} \\textbf\n\n{z}",
+        "\\newcommand{\\a}[1]{#1} \\renewcommand*\\b{c}",
+        "\\begin{equation}\\begin{aligned}a&=b\\end{aligned}\\end{equation}",
+        "\\begin{tabular}{|c|}a\\end{tabular}",
+        "\\begin{align}%\n x\\end{align}",
+        "\\begin{center} % c\n  x \\end{center}",
+        "$$$$ $ $ \\[\\]",
+        "\\makeatletter",
+        "\\makeatletter\\a@b\\makeatother\\c@d",
+        "{\\makeatletter\\x@y}\\x@y",
+        "\\makeatother\\makeatletter\\a@b\\ExplSyntaxOff x \\ExplSyntaxOn y",
+        "\\ExplSyntaxOn\\makeatletter\\a_b:c@d\\makeatother\\e_f:g@h\\ExplSyntaxOff",
+        "\\verb!x! \\verb*|y| \\lstinline|z|",
+        "1.5 .5 5. a.b 1,5",
+        "\\\\\\\\ \\\\[1em]",
+        "\\begin{x}[opt]{arg}\\end{x}",
+        "$\\left(\\right)$ $a^$ $_b$",
+        "\\href{a%b}{c} \\url{x}",
+        "\\begin{document}\\end{document}",
+    ];
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+    let mut files: Vec<_> = std::fs::read_dir(dir).unwrap().map(|entry| entry.unwrap().path()).collect();
+    files.sort();
+    let corpus = files.iter().map(|path| std::fs::read_to_string(path).unwrap());
+    for input in snippets.into_iter().map(String::from).chain(corpus) {
+        let expected = format!("{:#?}", js_parse(&input).unwrap());
+        let actual = format!("{:#?}", native::parse(&input).unwrap());
+        if let Some((line, (e, a))) = expected.lines().zip(actual.lines()).enumerate().find(|(_, (e, a))| e != a) {
+            panic!("native parse of {input:?} differs at line {line}:\n  expected {e}\n  actual   {a}");
+        }
+        assert_eq!(expected, actual, "native parse of {input:?}");
+    }
+
+    // deep nesting is an error rather than a stack overflow
+    let deep = format!("{}{}", "{".repeat(100_000), "}".repeat(100_000));
+    assert!(matches!(native::parse(&deep), Err(Error::TooDeep { .. })));
+    let (ast, diagnostics) = parse_with_diagnostics(&deep).unwrap();
+    assert!(ast.content().is_empty());
+    assert_eq!(diagnostics.len(), 1);
+}
+
+#[test]
+fn test_parse_borrowed() {
+    use std::borrow::Cow;
+    use ast::borrowed;
+
+    let input = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus/article.tex")).unwrap();
+    let input = format!("é😀 {input} \\verb|x| $\\frac{{a b}}{{c}}$");
+    let ast = parse_borrowed(&input).unwrap();
+
+    // every text slice points into the input
+    let range = input.as_bytes().as_ptr_range();
+    fn check<'a>(node: &borrowed::Node<'a>, inside: &dyn Fn(&str) -> bool, owned: &mut Vec<String>) {
+        let texts: Vec<&Cow<str>> = match node {
+            borrowed::Node::String { content, .. } | borrowed::Node::Comment { content, .. } => vec![content],
+            borrowed::Node::Macro { content, .. } => vec![content],
+            borrowed::Node::Environment { env, .. } | borrowed::Node::MathEnv { env, .. } => vec![env],
+            borrowed::Node::VerbatimEnvironment { env, content, .. } => vec![env, content],
+            borrowed::Node::Verb { env, escape, content, .. } => vec![env, escape, content],
+            _ => vec![],
+        };
+        for text in texts {
+            match text {
+                Cow::Borrowed(text) => assert!(inside(text)),
+                Cow::Owned(text) => owned.push(text.clone()),
+            }
+        }
+        node.args().iter().chain(node.content()).for_each(|child| check(child, inside, owned));
+    }
+    let mut owned = vec![];
+    check(&ast, &|text: &str| range.contains(&text.as_ptr()) || text.is_empty(), &mut owned);
+    // only tokens of math parsed again from its printed content, whose positions are off
+    assert!(!owned.is_empty() && owned.iter().all(|text| text.chars().count() == 1), "{owned:?}");
+
+    let Some(borrowed::Node::Verb { content, .. }) = ast.content().iter().rev().nth(2) else { panic!() };
+    assert_eq!(content, "x");
+    assert_eq!(format!("{:?}", ast.into_owned()), format!("{:?}", parse(&input).unwrap()));
+}
+
+#[test]
+fn test_document_edits() {
+    use document::Document;
+
+    // mostly typing, with a few edits unbalancing the document
+    let inserts = [
+        "", "", "a", "a", "b", "word ", "word ", " ", " ", "\n", "\n\n", "\n\n", "x\n\ny", "  \n\n  ", "%c\n", " % note\n\n", "{", "}", "{x}", "$", "$x^2$", "\\[y\\]",
+        "\\emph{e}", "\\section{S}", "\\item ", "\\begin{itemize}\\item i\\end{itemize}", "\\begin{align}a&=b\\end{align}",
+        "\\frac12", "\\sqrt[3]{x y}", "\\verb|v|", "é", "中", "\\\\", "\\label{l}", "\\begin{itemize}", "\\end{itemize}",
+    ];
+    let mut seed: u64 = 0x2545f4914f6cdd1d;
+    let mut next = move |n: usize| {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed % n as u64) as usize
+    };
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+    let mixed = "\\section{Intro}\\label{s}\nSome $a^2$ text % c\n\n  % own line\n\\begin{itemize}\n  \\item[x] one\n\n  \\item two\n\\end{itemize}\n\n\\begin{align}\n  a &= \\frac{b c}{d} \\\\\n  e &= f\n\\end{align}\n\n\\[ x \\] and \\verb|y| \\textbf{z}\n\n\n% end";
+    let sources = [
+        mixed.to_string(),
+        format!("\\documentclass{{article}}\n\\begin{{document}}\n{mixed}\n\n\\begin{{theorem}}\nt\n\n$u$\n\n\\item[v] w\n\\end{{theorem}}\n\\end{{document}}\n"),
+        std::fs::read_to_string(format!("{dir}/unicode.tex")).unwrap(),
+        std::fs::read_to_string(format!("{dir}/crlf.tex")).unwrap(),
+    ];
+    for source in sources {
+        for _ in 0..6 {
+            let mut doc = Document::new(source.as_str()).unwrap();
+            for _ in 0..6 {
+                let text = doc.text();
+                let boundary = |i: usize| (i..=text.len()).find(|&i| text.is_char_boundary(i)).unwrap();
+                let start = boundary(next(text.len() + 1));
+                let end = boundary((start + next(4)).min(text.len()));
+                let insert = inserts[next(inserts.len())];
+                let old = text.to_string();
+                let Ok(_) = doc.apply_edit(start..end, insert) else {
+                    // e.g. math splitting a character outside of the BMP
+                    assert!(parse(&format!("{}{insert}{}", &old[..start], &old[end..])).is_err());
+                    assert_eq!(doc.text(), old);
+                    continue;
+                };
+                let expected = parse(doc.text()).unwrap();
+                let (actual, expected_debug) = (format!("{:#?}", doc.root()), format!("{expected:#?}"));
+                if let Some((line, (a, e))) = actual.lines().zip(expected_debug.lines()).enumerate().find(|(_, (a, e))| a != e) {
+                    panic!("replacing {start}..{end} of {old:?} with {insert:?} differs at line {line}:\n  expected {e}\n  actual   {a}");
+                }
+                assert_eq!(actual, expected_debug, "replacing {start}..{end} of {old:?} with {insert:?}");
+            }
+        }
+    }
+
+    // typing in a paragraph only parses it again, including in the `document` environment
+    let paragraphs: String = (0..50).map(|i| format!("Paragraph {i} with \\emph{{words}} and $x_{i}$. % note\n\n")).collect();
+    let sources = [paragraphs.clone(), format!("\\documentclass{{article}}\n\\begin{{document}}\n{paragraphs}\\end{{document}}\n")];
+    for source in sources {
+        let mut doc = Document::new(source).unwrap();
+        let mut offset = doc.text().find("Paragraph 25").unwrap() + "Paragraph 25 ".len();
+        for typed in ["N", "e", "w", " ", "\\", "t", "e", "x", "t", "b", "f", "{b}", " ", "\n", "\n", "x"] {
+            let reparsed = doc.apply_edit(offset..offset, typed).unwrap();
+            offset += typed.len();
+            let text = &doc.text()[reparsed];
+            assert!(!text.contains("Paragraph 24") && !text.contains("Paragraph 26"), "{text:?}");
+            assert_eq!(format!("{:?}", doc.root()), format!("{:?}", parse(doc.text()).unwrap()));
+        }
+    }
+
+    let mut doc = Document::new("a\n\né").unwrap();
+    assert!(matches!(doc.apply_edit(0..9, ""), Err(Error::InvalidRange { .. })));
+    assert!(matches!(doc.apply_edit(3..4, ""), Err(Error::InvalidRange { .. })));
+    assert_eq!(doc.text(), "a\n\né");
+}
+
+#[test]
+fn test_format_chunked() {
+    use chunked::{format_chunked, ChunkOptions};
+
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+    let report: String = (0..8)
+        .map(|i| match i % 4 {
+            0 => format!("\\section{{Part {i}}}\\label{{s{i}}}\n"),
+            1 => format!("Paragraph {i}   with \\emph{{words}}, $x_{i} + y$ and a rather long tail of words that wraps. % note\n"),
+            2 => "\\begin{itemize}\n\\item one\n\n\\item two\n\\end{itemize}\n".to_string(),
+            _ => "\\[ a = b \\]\n\n\n".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let body = FormatOptions { document_only: false, ..FormatOptions::default() };
+    let narrow = FormatOptions { print_width: 40, use_tabs: true, ..body.clone() };
+    let (each, parallel) = (ChunkOptions { chunk_size: 0, jobs: 1 }, ChunkOptions { chunk_size: 300, jobs: 3 });
+    let cases = [
+        (report.clone(), &narrow, &parallel),
+        (format!("\\documentclass{{article}}\n\n\\usepackage{{x}}\n\\begin{{document}}\n{report}\\end{{document}}\n"), &body, &each),
+        (format!("% latexformat: off\n{}% latexformat: on\n\n{report}", &report[..report.find("\\begin").unwrap()]), &narrow, &each),
+        (std::fs::read_to_string(format!("{dir}/article.tex")).unwrap(), &FormatOptions::default(), &each),
+        (std::fs::read_to_string(format!("{dir}/crlf.tex")).unwrap(), &body, &each),
+        ("a\n\n{b\n\nc".to_string(), &body, &each),
+        (format!("\\begin{{document}} % c\n\n{report}\\item[a\n\nb] c\n\n\\begin{{verbatim}}\n\n}}\n\\end{{verbatim}}\n\n$x$\n\\end{{document}}\n"), &narrow, &each),
+    ];
+    for (input, opts, chunk_opts) in cases {
+        let expected = format_with_options(&input, opts).unwrap();
+        assert_eq!(format_chunked(&input, opts, chunk_opts).unwrap(), expected, "{chunk_opts:?} {opts:?}\n{input}");
+    }
+}
+
+#[test]
+fn test_format_chunked_large() {
+    use chunked::{format_chunked, ChunkOptions};
+
+    let body: String = (0..150)
+        .map(|i| match i % 3 {
+            0 => format!("\\subsection{{Run {i}}}\\label{{r{i}}}\n"),
+            1 => format!("Run {i} took   ${i}\\,\\mathrm{{s}}$ on the interval [{i}, {}] \\cite{{r{i}}}. % generated\n", i + 1),
+            _ => format!("\\begin{{tabular}}{{ll}}\n$t_{{{i}}}$ & x \\\\\n\\end{{tabular}}\n"),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let input = format!("\\documentclass{{report}}\n\\begin{{document}}\n{body}\\end{{document}}\n");
+    let opts = FormatOptions { document_only: false, ..FormatOptions::default() };
+    let chunked = format_chunked(&input, &opts, &ChunkOptions { chunk_size: 2048, jobs: 4 }).unwrap();
+    assert_eq!(chunked, format_with_options(&input, &opts).unwrap());
+}
+
+#[test]
+fn test_cached_formatter() {
+    use cache::CachedFormatter;
+
+    let dir = tempfile::tempdir().unwrap();
+    let formatter = CachedFormatter::new(dir.path().join("cache"));
+    let opts = FormatOptions::default();
+    let entries = || std::fs::read_dir(formatter.dir()).map_or(0, |dir| dir.count());
+
+    let formatted = formatter.format("$a  +   b$", &opts).unwrap();
+    assert_eq!(formatted, format_with_options("$a  +   b$", &opts).unwrap());
+    assert_eq!(entries(), 1);
+    let cached = formatter.format_with("$a  +   b$", &opts, |_| -> Result<String> { panic!("not cached") });
+    assert_eq!(cached.unwrap(), formatted);
+
+    // formatted documents and other options get their own entries
+    assert_eq!(formatter.format(&formatted, &opts).unwrap(), formatted);
+    let narrow = FormatOptions { print_width: 20, ..opts.clone() };
+    assert_eq!(formatter.format_with("$a  +   b$", &narrow, |_| Ok::<_, Error>("narrow".to_string())).unwrap(), "narrow");
+    assert_eq!(entries(), 3);
+    assert_eq!(formatter.format_with(&formatted, &opts, |_| Ok::<_, Error>(String::new())).unwrap(), formatted);
+
+    // failures are not cached, and neither are entries the cache cannot read
+    assert!(formatter.format_with("x", &opts, |_| Err(Error::Unknown)).is_err());
+    assert_eq!(entries(), 3);
+    for entry in std::fs::read_dir(formatter.dir()).unwrap() {
+        std::fs::write(entry.unwrap().path(), "garbage").unwrap();
+    }
+    assert_eq!(formatter.format_with("$a  +   b$", &opts, |_| Ok::<_, Error>("again".to_string())).unwrap(), "again");
+    assert_eq!(formatter.format("$a  +   b$", &opts).unwrap(), "again");
+
+    // pruning keeps the entries within the limits
+    formatter.prune(cache::MAX_AGE, cache::MAX_SIZE).unwrap();
+    assert_eq!(entries(), 3);
+    formatter.prune(cache::MAX_AGE, 10).unwrap();
+    assert_eq!(entries(), 1);
+    formatter.prune(cache::MAX_AGE, 0).unwrap();
+    assert_eq!(entries(), 0);
+}
+
+#[test]
+fn test_verify() {
+    use verify::{check_equivalent, check_idempotent, format_verified};
+
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+    let opts = FormatOptions { document_only: false, ..FormatOptions::default() };
+    assert_eq!(format_verified("Some   text\n\n$a  +   b$ % note\n", &opts).unwrap(), "Some text\n\n$a + b$ % note");
+    let crlf = std::fs::read_to_string(format!("{dir}/crlf.tex")).unwrap();
+    assert_eq!(format_verified(&crlf, &opts).unwrap(), format_with_options(&crlf, &opts).unwrap());
+
+    // the printer adds a group to URLs with `%` every time
+    let input = "Intro.\n\n\\href{http://example.com/a_b%20c}{link}\n";
+    let formatted = format_with_options(input, &opts).unwrap();
+    let Err(Error::NotIdempotent { line, diff }) = check_idempotent(&formatted, &opts) else { panic!() };
+    assert_eq!(line, 3);
+    assert_eq!(diff, " Intro.\n \n-\\href{{}{http}://example.com/a_b%20c}{link}\n+\\href{{}{}{http}://example.com/a_b%20c}{link}");
+    let Err(Error::NotEquivalent { line, formatted_line, message }) = check_equivalent(input, &formatted) else { panic!() };
+    assert_eq!((line, formatted_line, message.as_str()), (3, 3, "`http` became nothing"));
+    assert!(matches!(format_verified(input, &opts), Err(Error::NotIdempotent { .. })));
+
+    // reflowed text is the same document, paragraphs and comments are not
+    assert!(check_equivalent("a  b\nc {d}  \\e", "a b c {d} \\e").is_ok());
+    let changed = |a, b| match check_equivalent(a, b) {
+        Err(Error::NotEquivalent { line, formatted_line, message }) => (line, formatted_line, message),
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(changed("a\n\nb", "a b"), (1, 1, "a paragraph break became `b`".to_string()));
+    assert_eq!(changed("a % x\nb", "a % y\nb"), (1, 1, "comment `% x` became comment `% y`".to_string()));
+    assert_eq!(changed("\\emph{a}\n\\emph{b}", "\\emph{a}"), (2, 1, "`\\emph` became nothing".to_string()));
+    assert_eq!(changed("x", "$x$"), (1, 1, "`x` became inline math".to_string()));
+}
+
+#[test]
+fn test_semantic_eq() {
+    use compare::semantic_diff;
+
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+    let opts = FormatOptions { document_only: false, print_width: 30, ..FormatOptions::default() };
+    for file in ["article.tex", "math.tex", "unicode.tex", "crlf.tex"] {
+        let input = std::fs::read_to_string(format!("{dir}/{file}")).unwrap();
+        assert!(semantic_eq(&input, &format_with_options(&input, &opts).unwrap()).unwrap(), "{file}");
+    }
+    assert!(semantic_eq("\n\na\n\n\n\n% c\n\nb\n\n", "a\r\n\r\n%   c   \r\nb").unwrap());
+    assert!(semantic_eq("\\begin{itemize}\n\n\\item a\n\n\\end{itemize}", "\\begin{itemize}\\item a\\end{itemize}").unwrap());
+
+    // differences are located in both documents
+    let spans = |a: &str, b: &str| {
+        let difference = semantic_diff(a, b).unwrap().unwrap();
+        (a[difference.left.clone()].to_string(), b[difference.right.clone()].to_string(), difference.to_string())
+    };
+    assert_eq!(spans("x \\cite{a} y", "x\n\\cite{b}\ny"), ("a".into(), "b".into(), "`a` became `b`".into()));
+    assert_eq!(spans("a\n\nb", "a b"), ("\n\n".into(), "b".into(), "a paragraph break became `b`".into()));
+    assert_eq!(spans("é $x + y$", "é $x$"), ("+".into(), "".into(), "`+` became nothing".into()));
+    assert_eq!(spans("a % one\r\n\r\nb % two", "a % one\n\nb % three"), ("% two".into(), "% three".into(), "comment `% two` became comment `% three`".into()));
+    assert_eq!(spans("a % c", "a"), ("% c".into(), "".into(), "comment `% c` became nothing".into()));
+    let difference = semantic_diff("x\r\ny\r\n\\emph{z}", "x y \\textbf{z}").unwrap().unwrap();
+    assert_eq!((difference.left, difference.right), (6..14, 4..14));
+}
+
+#[test]
+fn test_diff() {
+    use diff::{report, Edit};
+
+    let edits = |old: &str, new: &str| -> Vec<(&'static str, String, String, String)> {
+        let slice = |text: &str, range: Option<std::ops::Range<usize>>| range.map_or(String::new(), |range| text[range].to_string());
+        diff(old, new).unwrap().iter().map(|edit| (edit.kind(), edit.node().to_string(), slice(old, edit.old_range()), slice(new, edit.new_range()))).collect()
+    };
+    let edit = |kind, node: &str, old: &str, new: &str| (kind, node.to_string(), old.to_string(), new.to_string());
+    assert!(diff("a  b\n\n\n% c\n\\emph{d}", "a\nb\n\n\\emph{d}\n").unwrap().is_empty());
+
+    let old = "\\begin{figure}\\caption{F}\\end{figure}\n\\section{A}\nOne \\cite{x}.\n\\label{a}\n\n\\section{B}\nTwo.\n";
+    let new = "\\section{A}\nOne more \\cite{y}.\n\\label{a}\n\n\\section{C}\n$x$\n\n\\begin{figure}\\caption{F}\\end{figure}\n";
+    let figure = "\\begin{figure}\\caption{F}\\end{figure}";
+    assert_eq!(
+        edits(old, new),
+        [
+            edit("move", "environment `figure`", figure, figure),
+            edit("insert", "text", "", "more"),
+            edit("update", "`\\cite`", "\\cite{x}", "\\cite{y}"),
+            edit("update", "`\\section`", "\\section{B}", "\\section{C}"),
+            edit("delete", "text", "Two.", ""),
+            edit("insert", "inline math", "", "$x$"),
+        ]
+    );
+    // environments are matched inside unless their arguments changed
+    assert_eq!(
+        edits("\\begin{itemize}\\item a\\item b\\end{itemize}", "\\begin{itemize}\\item a\\item c\\end{itemize}"),
+        [edit("update", "text", "b", "c")]
+    );
+    assert_eq!(
+        edits("\\begin{tabular}{ll}a\\end{tabular}", "\\begin{tabular}{lr}a\\end{tabular}"),
+        [edit("update", "environment `tabular`", "\\begin{tabular}{ll}a\\end{tabular}", "\\begin{tabular}{lr}a\\end{tabular}")]
+    );
+    assert_eq!(edits("a\r\n\r\nb c\r\n", "a b\nd"), [edit("delete", "a paragraph break", "\r\n\r\n", ""), edit("update", "text", "c", "d")]);
+
+    let (old, new) = ("\\section{Intro}\ntext\n", "\\section{Introduction}\n\ntext\n\n\\section{More}\n");
+    let edits = diff(old, new).unwrap();
+    assert_eq!(edits[0], Edit::Update { node: "`\\section`".into(), old: 0..15, new: 0..22 });
+    assert_eq!(
+        report(&edits, old, new),
+        "update `\\section` at line 1\n  - \\section{Intro}\n  + \\section{Introduction}\ninsert `\\section` at line 5\n  + \\section{More}\n"
+    );
+}
+
+#[test]
+fn test_mark_changes() {
+    use markup::{mark_changes, PREAMBLE};
+
+    let body = |marked: String| marked.split_once("\\begin{document}\n").unwrap().1.trim_end_matches("\\end{document}\n").to_string();
+    let doc = |body: &str| format!("\\documentclass{{article}}\n\\begin{{document}}\n{body}\\end{{document}}\n");
+    let unchanged = doc("Some \\emph{text}.\n");
+    assert_eq!(mark_changes(&unchanged, &unchanged).unwrap(), unchanged.replace("\\begin{document}", &format!("{PREAMBLE}\\begin{{document}}")));
+    // fragments get the definitions at the top
+    assert_eq!(mark_changes("a b", "a c").unwrap(), format!("{PREAMBLE}a \\DIFdel{{b}} \\DIFadd{{c}}"));
+
+    // words in paragraphs, whole nodes otherwise, and deleted labels left out
+    let old = doc("One two three.\n\nFour \\cite{a} five.\n\\label{x}\n");
+    let new = doc("One three and more.\n\nFour \\cite{b} five.\n");
+    assert_eq!(
+        body(mark_changes(&old, &new).unwrap()),
+        "One \\DIFdel{two} three \\DIFadd{and more}.\n\nFour \\DIFdelbegin \\cite{a}\\DIFdelend{} \\DIFaddbegin \\cite{b}\\DIFaddend{} five.\n"
+    );
+    let old = doc("A\n\\begin{equation}\n  x = 1\n\\end{equation}\n");
+    let new = doc("A\n\\begin{equation}\n  x = 2\n\\end{equation}\nand $y$ too\n");
+    assert_eq!(
+        body(mark_changes(&old, &new).unwrap()),
+        "A\n\\DIFdelbegin \\begin{equation*}\n  x = 1\n\\end{equation*}\\DIFdelend{} \\DIFaddbegin \\begin{equation}\n  x = 2\n\\end{equation}\\DIFaddend{}\n\\DIFadd{and} \\DIFaddbegin $y$\\DIFaddend{} \\DIFadd{too}\n"
+    );
+
+    // items are marked up whole, tables without breaking rows and rules apart
+    let old = doc("\\begin{itemize}\n  \\item one\n\\end{itemize}\n\\begin{tabular}{ll}\n  a & b \\\\\n  c & d \\\\\n  \\hline\n\\end{tabular}\n");
+    let new = doc("\\begin{itemize}\n  \\item one\n  \\item two\n\\end{itemize}\n\\begin{tabular}{ll}\n  a & e \\\\\n  \\hline\n  f & g \\\\\n\\end{tabular}\n");
+    assert_eq!(
+        body(mark_changes(&old, &new).unwrap()),
+        "\\begin{itemize}\n  \\item one\n  \\DIFaddbegin \\item two\\DIFaddend{}\n\\end{itemize}\n\\begin{tabular}{ll}\n  a & \\DIFdel{b} \\DIFadd{e} \\\\\n  \\hline\n  \\DIFdel{c} \\DIFdel{d} \\DIFadd{f} & \\DIFadd{g} \\\\\n\\end{tabular}\n"
+    );
+
+    // deleted commands stepping counters are commented out, deleted equations unnumbered
+    let old = doc("\\section{Old}\nText.\n\\section{Kept}\n\\begin{enumerate}\n  \\item one\n  \\item two\n\\end{enumerate}\n");
+    let new = doc("Text.\n\\section{Kept}\n\\begin{enumerate}\n  \\item two\n\\end{enumerate}\n");
+    assert_eq!(
+        body(mark_changes(&old, &new).unwrap()),
+        "%DIFDELCMD < \\section{Old}\nText.\n\\section{Kept}\n\\begin{enumerate}\n  %DIFDELCMD < \\item one\n  \\item two\n\\end{enumerate}\n"
+    );
+    let old = doc("\\begin{figure}\n  \\caption{Old}\n\\end{figure}\nSee \\begin{equation}\\label{eq:old}\n  x\n\\end{equation}\n");
+    let new = doc("See\n");
+    assert_eq!(
+        body(mark_changes(&old, &new).unwrap()),
+        "%DIFDELCMD < \\begin{figure}\n%DIFDELCMD <   \\caption{Old}\n%DIFDELCMD < \\end{figure}\nSee \\DIFdelbegin \\begin{equation*}\n  x\n\\end{equation*}\\DIFdelend{}\n"
+    );
+
+    let marked = mark_changes("\\begin{document}\r\nOld\r\n\\end{document}\r\n", "\\begin{document}\nNew\n\\end{document}\n").unwrap();
+    assert!(marked.ends_with("\\begin{document}\n\\DIFdel{Old} \\DIFadd{New}\n\\end{document}\n"));
+}
+
+#[test]
+fn test_stats() {
+    use stats::{stats_with, CountOptions, Region};
+
+    let doc = |body: &str| format!("\\documentclass{{article}}\n\\title{{Not counted}}\n\\begin{{document}}\n{body}\\end{{document}}\n");
+    let source = doc("Intro \\emph{text}~here --- see \\cite{a, b}\\nocite{c}.\n\
+        \\section{First part}\\label{sec:first}\n\
+        Na\\\"ive Sch\\o nberg $x$ and\n\\[ y \\]\n\
+        \\begin{figure}\\includegraphics{plot}\\caption[Short]{A plot.}\\end{figure}\n\
+        \\subsection*{Details}\n\
+        Note\\footnote{Two words.} \\href{http://x.org}{a link}\n\
+        \\begin{table}\\begin{tabular}{ll} a & b \\\\\\end{tabular}\\end{table}\n\
+        \\begin{tikzpicture}\\node {none};\\end{tikzpicture}\n\
+        \\begin{align} a &= b \\end{align}\n");
+    let ast = parse(&source).unwrap();
+    let counted = stats(&ast);
+    assert_eq!(stats(&parse_borrowed(&source).unwrap()), counted);
+    assert_eq!((counted.counts.words, counted.counts.citations), (4, 2));
+    let first = &counted.sections[0];
+    assert_eq!(first.title, "First part");
+    assert_eq!(first.counts.words, 3);
+    assert_eq!(first.counts.characters, "NaiveSchonbergand".len());
+    assert_eq!((first.counts.header_words, first.counts.caption_words), (2, 2));
+    assert_eq!((first.counts.inline_equations, first.counts.display_equations, first.counts.figures), (1, 1, 1));
+    let details = &first.children[0];
+    assert!(details.starred);
+    assert_eq!(details.counts, stats::Counts {
+        words: 5,
+        header_words: 1,
+        footnote_words: 2,
+        characters: "Notealinkab".len(),
+        display_equations: 1,
+        tables: 1,
+        ..Default::default()
+    });
+    assert_eq!(first.total.words, 8);
+    assert_eq!(counted.total.words, 12);
+    assert_eq!(counted.total.header_words, 3);
+
+    // configured rules take precedence over the built-in ones
+    let ast = parse("\\todo{fix this} \\keyword{Head}{body text} \\caption{cap}\\begin{abstract}Summary\\end{abstract}").unwrap();
+    let mut options = CountOptions::default();
+    options.macros.insert("todo".to_string(), vec![Region::Ignore]);
+    options.macros.insert("keyword".to_string(), vec![Region::Header, Region::Text]);
+    options.macros.insert("caption".to_string(), vec![Region::Text]);
+    options.environments.insert("abstract".to_string(), Region::Ignore);
+    let counts = stats_with(&ast, &options).total;
+    assert_eq!((counts.words, counts.header_words, counts.caption_words), (3, 1, 0));
+    assert_eq!(stats(&ast).total.words, 6);
+}
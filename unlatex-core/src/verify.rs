@@ -0,0 +1,100 @@
+//! Checking the output of the formatter.
+//!
+//! [`format_verified`] formats a document and makes sure the output can be trusted:
+//!
+//! - formatting it again changes nothing, or [`Error::NotIdempotent`] is returned with the
+//!   lines the second pass changes;
+//! - it has the [meaning](crate::compare) of the input, or [`Error::NotEquivalent`] is
+//!   returned with the first node that differs.
+//!
+//! ```
+//! use unlatex::verify::format_verified;
+//! use unlatex::{Error, FormatOptions};
+//!
+//! let opts = FormatOptions::default();
+//! assert_eq!(format_verified("$a  +   b$", &opts).unwrap(), "$a + b$");
+//! // the printer breaks URLs containing `%`
+//! let err = format_verified("\\href{http://x.org/a%20b}{link}", &opts).unwrap_err();
+//! assert!(matches!(err, Error::NotIdempotent { line: 1, .. }));
+//! ```
+
+use crate::compare::semantic_diff;
+use crate::{format_with_options, Error, FormatOptions, Result};
+
+/// Lines of context shown around the changes of [`Error::NotIdempotent`].
+const CONTEXT: usize = 2;
+
+/// Format LaTeX document like [`format_with_options`], checking the output with [`verify`].
+pub fn format_verified(input: &str, opts: &FormatOptions) -> Result<String> {
+    let formatted = format_with_options(input, opts)?;
+    verify(input, &formatted, opts)?;
+    Ok(formatted)
+}
+
+/// Check that `formatted`, the output of the formatter for `input` with `opts`, is stable
+/// and has the meaning of `input`.
+pub fn verify(input: &str, formatted: &str, opts: &FormatOptions) -> Result<()> {
+    check_idempotent(formatted, opts)?;
+    check_equivalent(input, formatted)
+}
+
+/// Check that formatting `formatted` with `opts` leaves it unchanged.
+pub fn check_idempotent(formatted: &str, opts: &FormatOptions) -> Result<()> {
+    let again = format_with_options(formatted, opts)?;
+    if again == formatted {
+        return Ok(());
+    }
+    let (line, diff) = diff_lines(formatted, &again);
+    Err(Error::NotIdempotent { line, diff })
+}
+
+/// Check that `formatted` has the meaning of `input`, as [`semantic_diff`] compares them.
+pub fn check_equivalent(input: &str, formatted: &str) -> Result<()> {
+    let Some(difference) = semantic_diff(input, formatted)? else {
+        return Ok(());
+    };
+    let line = |text: &str, offset: usize| text[..offset].matches('\n').count() + 1;
+    Err(Error::NotEquivalent {
+        line: line(input, difference.left.start),
+        formatted_line: line(formatted, difference.right.start),
+        message: difference.to_string(),
+    })
+}
+
+/// Line of the first change from `old` to `new`, and the changed lines with some context,
+/// prefixed by `-` when removed, `+` when added and a space otherwise.
+fn diff_lines(old: &str, new: &str) -> (usize, String) {
+    let (old, new): (Vec<&str>, Vec<&str>) = (old.split('\n').collect(), new.split('\n').collect());
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (removed, added) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    // longest common subsequence of the changed lines, which are usually few
+    let mut common = vec![vec![0usize; added.len() + 1]; removed.len() + 1];
+    for i in (0..removed.len()).rev() {
+        for j in (0..added.len()).rev() {
+            common[i][j] = match removed[i] == added[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+    let mut diff = Vec::new();
+    diff.extend(old[prefix.saturating_sub(CONTEXT)..prefix].iter().map(|line| format!(" {line}")));
+    let (mut i, mut j) = (0, 0);
+    while i < removed.len() || j < added.len() {
+        if i < removed.len() && j < added.len() && removed[i] == added[j] {
+            diff.push(format!(" {}", removed[i]));
+            (i, j) = (i + 1, j + 1);
+        } else if j == added.len() || (i < removed.len() && common[i + 1][j] >= common[i][j + 1]) {
+            diff.push(format!("-{}", removed[i]));
+            i += 1;
+        } else {
+            diff.push(format!("+{}", added[j]));
+            j += 1;
+        }
+    }
+    let end = old.len() - suffix;
+    diff.extend(old[end..(end + CONTEXT).min(old.len())].iter().map(|line| format!(" {line}")));
+    (prefix + 1, diff.join("\n"))
+}
@@ -0,0 +1,248 @@
+//! Language server for LaTeX speaking LSP over stdio.
+
+mod symbols;
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{
+    DocumentSymbolRequest, FoldingRangeRequest, Formatting, RangeFormatting, Request as _,
+};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DocumentFormattingParams, DocumentRangeFormattingParams, DocumentSymbolParams,
+    DocumentSymbolResponse, FoldingRange, FoldingRangeParams, FoldingRangeProviderCapability, FormattingOptions,
+    OneOf, PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind,
+    TextEdit, Url,
+};
+use unlatex::diagnostics::Severity;
+use unlatex::span::LineIndex;
+use unlatex::{config, format_range, format_with_options, FormatOptions};
+
+use crate::symbols::position;
+
+/// An open text document.
+struct Document {
+    text: String,
+    index: LineIndex,
+}
+
+impl Document {
+    fn new(text: String) -> Self {
+        let index = LineIndex::new(&text);
+        Document { text, index }
+    }
+
+    fn offset(&self, position: lsp_types::Position) -> usize {
+        self.index.offset(position.line as usize, position.character as usize)
+    }
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    fn run(&mut self, connection: &Connection) -> Result<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.on_request(request);
+                    connection.sender.send(Message::Response(response))?;
+                }
+                Message::Notification(notification) => {
+                    if let Some(uri) = self.on_notification(notification)? {
+                        let diagnostics = self.diagnostics(&uri);
+                        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+                        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+                        connection.sender.send(Message::Notification(notification))?;
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn on_request(&mut self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            Formatting::METHOD => self.call(request, Self::formatting),
+            RangeFormatting::METHOD => self.call(request, Self::range_formatting),
+            DocumentSymbolRequest::METHOD => self.call(request, Self::document_symbols),
+            FoldingRangeRequest::METHOD => self.call(request, Self::folding_ranges),
+            method => {
+                return Response::new_err(id, ErrorCode::MethodNotFound as i32, format!("unhandled method {method}"));
+            }
+        };
+        match result {
+            Ok(result) => Response::new_ok(id, result),
+            Err(err) => Response::new_err(id, ErrorCode::RequestFailed as i32, err.to_string()),
+        }
+    }
+
+    fn call<P, R>(&mut self, request: Request, handler: fn(&mut Self, P) -> Result<R>) -> Result<serde_json::Value>
+        where P: serde::de::DeserializeOwned, R: serde::Serialize
+    {
+        let params = serde_json::from_value(request.params)?;
+        Ok(serde_json::to_value(handler(self, params)?)?)
+    }
+
+    /// Handle a notification, returning the document whose diagnostics should be refreshed.
+    fn on_notification(&mut self, notification: Notification) -> Result<Option<Url>> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams = serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                self.documents.insert(uri.clone(), Document::new(params.text_document.text));
+                Ok(Some(uri))
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams = serde_json::from_value(notification.params)?;
+                let uri = params.text_document.uri;
+                // documents are synchronized in full, the last change holds the whole text
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    self.documents.insert(uri.clone(), Document::new(change.text));
+                }
+                Ok(Some(uri))
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams = serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                Ok(None)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn document(&self, uri: &Url) -> Result<&Document> {
+        self.documents.get(uri).ok_or_else(|| anyhow::anyhow!("unknown document {uri}"))
+    }
+
+    /// Options for formatting `uri`: the editor settings, overridden by configuration files.
+    fn format_options(uri: &Url, options: &FormattingOptions) -> Result<FormatOptions> {
+        let base = FormatOptions {
+            print_width: 80,
+            use_tabs: !options.insert_spaces,
+            tab_width: options.tab_size as i32,
+            document_only: false,
+        };
+        Ok(match uri.to_file_path() {
+            Ok(path) => config::resolve_options(path, &base)?,
+            Err(()) => base,
+        })
+    }
+
+    fn formatting(&mut self, params: DocumentFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        let document = self.document(&uri)?;
+        let formatted = format_with_options(&document.text, &Self::format_options(&uri, &params.options)?)?;
+        Ok(Some(edits(document, &formatted)))
+    }
+
+    fn range_formatting(&mut self, params: DocumentRangeFormattingParams) -> Result<Option<Vec<TextEdit>>> {
+        let uri = params.text_document.uri;
+        let document = self.document(&uri)?;
+        let range = document.offset(params.range.start)..document.offset(params.range.end);
+        let formatted = format_range(&document.text, range, &Self::format_options(&uri, &params.options)?)?;
+        Ok(Some(edits(document, &formatted)))
+    }
+
+    fn document_symbols(&mut self, params: DocumentSymbolParams) -> Result<Option<DocumentSymbolResponse>> {
+        let document = self.document(&params.text_document.uri)?;
+        let ast = unlatex::parse(&document.text)?;
+        let symbols = symbols::document_symbols(&ast, &document.text, &document.index);
+        Ok(Some(DocumentSymbolResponse::Nested(symbols)))
+    }
+
+    fn folding_ranges(&mut self, params: FoldingRangeParams) -> Result<Option<Vec<FoldingRange>>> {
+        let document = self.document(&params.text_document.uri)?;
+        let ast = unlatex::parse(&document.text)?;
+        let symbols = symbols::document_symbols(&ast, &document.text, &document.index);
+        Ok(Some(symbols::folding_ranges(&symbols)))
+    }
+
+    fn diagnostics(&self, uri: &Url) -> Vec<Diagnostic> {
+        let Some(document) = self.documents.get(uri) else {
+            return vec![];
+        };
+        let diagnostics = match unlatex::parse_with_diagnostics(&document.text) {
+            Ok((_, diagnostics)) => diagnostics,
+            Err(err) => {
+                return vec![Diagnostic {
+                    range: Range::default(),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("unlatex".to_string()),
+                    message: err.to_string(),
+                    ..Diagnostic::default()
+                }]
+            }
+        };
+        diagnostics
+            .into_iter()
+            .map(|diagnostic| Diagnostic {
+                range: Range::new(
+                    position(&document.index, diagnostic.span.start),
+                    position(&document.index, diagnostic.span.end),
+                ),
+                severity: Some(match diagnostic.severity {
+                    Severity::Error => DiagnosticSeverity::ERROR,
+                    Severity::Warning => DiagnosticSeverity::WARNING,
+                    Severity::Info => DiagnosticSeverity::INFORMATION,
+                }),
+                source: Some("unlatex".to_string()),
+                message: diagnostic.message,
+                ..Diagnostic::default()
+            })
+            .collect()
+    }
+}
+
+/// The edit turning the text of `document` into `new`, covering only the part which changed.
+fn edits(document: &Document, new: &str) -> Vec<TextEdit> {
+    let old = document.text.as_str();
+    if old == new {
+        return vec![];
+    }
+    let prefix = old
+        .char_indices()
+        .zip(new.chars())
+        .find(|((_, a), b)| a != b)
+        .map_or(old.len().min(new.len()), |((i, _), _)| i);
+    let suffix = old[prefix..]
+        .chars()
+        .rev()
+        .zip(new[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a.len_utf8())
+        .sum::<usize>();
+    let range = Range::new(position(&document.index, prefix), position(&document.index, old.len() - suffix));
+    vec![TextEdit::new(range, new[prefix..new.len() - suffix].to_string())]
+}
+
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    }
+}
+
+fn main() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    Server::default().run(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
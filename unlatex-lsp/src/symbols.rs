@@ -0,0 +1,119 @@
+//! Document symbols and folding ranges.
+
+use lsp_types::{DocumentSymbol, FoldingRange, FoldingRangeKind, Position, Range, SymbolKind};
+use unlatex::ast::Node;
+use unlatex::info;
+use unlatex::outline::{outline, Environment, Label, Section};
+use unlatex::span::LineIndex;
+
+/// LSP position of the byte `offset`.
+pub fn position(index: &LineIndex, offset: usize) -> Position {
+    let (line, column) = index.line_col(offset);
+    Position::new(line as u32, column as u32)
+}
+
+/// Byte offset of the parser `position`.
+fn offset(index: &LineIndex, position: &info::Position) -> usize {
+    index.offset_map().to_byte(position.offset)
+}
+
+#[allow(deprecated)]
+fn symbol(name: String, detail: Option<String>, kind: SymbolKind, range: Range) -> DocumentSymbol {
+    DocumentSymbol {
+        name,
+        detail,
+        kind,
+        tags: None,
+        deprecated: None,
+        range,
+        selection_range: range,
+        children: None,
+    }
+}
+
+/// Builds the symbols of an [outline](unlatex::outline::Outline) of `src`.
+struct Builder<'a> {
+    src: &'a str,
+    index: &'a LineIndex,
+}
+
+impl Builder<'_> {
+    fn range(&self, start: usize, end: usize) -> Range {
+        Range::new(position(self.index, start), position(self.index, end))
+    }
+
+    /// The symbol of a `\label`, spanning its argument.
+    fn label(&self, label: &Label) -> DocumentSymbol {
+        let start = offset(self.index, &label.position.start);
+        let end = offset(self.index, &label.position.end);
+        let end = match self.src[end..].trim_start().starts_with('{') {
+            true => self.src[end..].find('}').map_or(end, |i| end + i + 1),
+            false => end,
+        };
+        symbol(label.name.clone(), None, SymbolKind::CONSTANT, self.range(start, end))
+    }
+
+    fn environment(&self, environment: &Environment) -> DocumentSymbol {
+        let range = self.range(offset(self.index, &environment.position.start), offset(self.index, &environment.position.end));
+        let children = self.children(&environment.labels, &environment.children, &[], range.end);
+        let mut symbol = symbol(environment.name.clone(), None, SymbolKind::STRUCT, range);
+        symbol.children = (!children.is_empty()).then_some(children);
+        symbol
+    }
+
+    /// The symbols of `sections`, the last one ending at `end`.
+    fn sections(&self, sections: &[Section], end: Position) -> Vec<DocumentSymbol> {
+        let starts: Vec<Position> = sections.iter().map(|s| position(self.index, offset(self.index, &s.position.start))).collect();
+        let mut symbols = vec![];
+        for (i, section) in sections.iter().enumerate() {
+            // a section ends where the next one at its level, or above it, starts
+            let range = Range::new(starts[i], starts.get(i + 1).copied().unwrap_or(end));
+            let children = self.children(&section.labels, &section.environments, &section.children, range.end);
+            let detail = format!("\\{}", section.kind.macro_name());
+            let mut symbol = symbol(section.title.clone(), Some(detail), SymbolKind::MODULE, range);
+            symbol.children = (!children.is_empty()).then_some(children);
+            symbols.push(symbol);
+        }
+        symbols
+    }
+
+    /// The symbols of `labels`, `environments` and `sections`, in the order they start in.
+    fn children(&self, labels: &[Label], environments: &[Environment], sections: &[Section], end: Position) -> Vec<DocumentSymbol> {
+        let mut symbols: Vec<_> = labels.iter().map(|l| self.label(l)).collect();
+        symbols.extend(environments.iter().map(|e| self.environment(e)));
+        symbols.extend(self.sections(sections, end));
+        symbols.sort_by_key(|s| (s.range.start.line, s.range.start.character));
+        symbols
+    }
+}
+
+/// Symbols of the sections, labels and environments of the document `root`, parsed from `src`.
+pub fn document_symbols(root: &Node, src: &str, index: &LineIndex) -> Vec<DocumentSymbol> {
+    let outline = outline(root);
+    let builder = Builder { src, index };
+    builder.children(&outline.labels, &outline.environments, &outline.sections, position(index, src.len()))
+}
+
+/// Folding ranges of the sections and environments among `symbols`.
+pub fn folding_ranges(symbols: &[DocumentSymbol]) -> Vec<FoldingRange> {
+    let mut ranges = vec![];
+    for symbol in symbols {
+        // keep the line ending the range visible, it holds `\end` or the next section
+        let start_line = symbol.range.start.line;
+        let end_line = symbol.range.end.line.saturating_sub(1);
+        if symbol.kind != SymbolKind::CONSTANT && end_line > start_line {
+            ranges.push(FoldingRange {
+                start_line,
+                start_character: None,
+                end_line,
+                end_character: None,
+                kind: Some(FoldingRangeKind::Region),
+                collapsed_text: None,
+            });
+        }
+        if let Some(children) = &symbol.children {
+            ranges.extend(folding_ranges(children));
+        }
+    }
+    ranges
+}
@@ -0,0 +1,209 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value};
+
+/// A scripted LSP client talking to the server binary over stdio.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+}
+
+impl Client {
+    fn start() -> Client {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_unlatex-lsp"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut client = Client { child, stdin, stdout, next_id: 0 };
+
+        let result = client.request("initialize", json!({ "capabilities": {} }));
+        assert_eq!(result["capabilities"]["documentFormattingProvider"], json!(true));
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn send(&mut self, message: Value) {
+        let body = message.to_string();
+        write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            self.stdout.read_line(&mut line).unwrap();
+            let line = line.trim();
+            if line.is_empty() {
+                break;
+            }
+            if let Some(value) = line.strip_prefix("Content-Length: ") {
+                length = value.parse().unwrap();
+            }
+        }
+        let mut body = vec![0; length];
+        self.stdout.read_exact(&mut body).unwrap();
+        serde_json::from_slice(&body).unwrap()
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message["id"] == json!(id) {
+                assert!(message.get("error").is_none(), "{message}");
+                return message["result"].clone();
+            }
+        }
+    }
+
+    /// Wait for the next diagnostics published by the server.
+    fn diagnostics(&mut self) -> Value {
+        loop {
+            let message = self.receive();
+            if message["method"] == "textDocument/publishDiagnostics" {
+                return message["params"].clone();
+            }
+        }
+    }
+
+    fn open(&mut self, uri: &str, text: &str) {
+        self.notify("textDocument/didOpen", json!({
+            "textDocument": { "uri": uri, "languageId": "latex", "version": 1, "text": text }
+        }));
+    }
+
+    fn shutdown(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+const URI: &str = "untitled:main.tex";
+
+const DOCUMENT: &str = r#"\documentclass{article}
+\begin{document}
+\section{Intro}\label{sec:intro}
+Café    text.
+
+\subsection{Details}
+\begin{figure}
+  \label{fig:a}
+\end{figure}
+
+\section[Short]{Results}
+Done.
+\end{document}
+"#;
+
+#[test]
+fn test_formatting() {
+    let mut client = Client::start();
+    client.open(URI, "\\section{A}   B\n\nC    $x$\n");
+    client.diagnostics();
+
+    let edits = client.request("textDocument/formatting", json!({
+        "textDocument": { "uri": URI },
+        "options": { "tabSize": 2, "insertSpaces": true }
+    }));
+    assert_eq!(edits, json!([{
+        "range": { "start": { "line": 0, "character": 11 }, "end": { "line": 3, "character": 0 } },
+        "newText": "\nB\n\nC $x$"
+    }]));
+
+    let edits = client.request("textDocument/rangeFormatting", json!({
+        "textDocument": { "uri": URI },
+        "range": { "start": { "line": 2, "character": 0 }, "end": { "line": 2, "character": 1 } },
+        "options": { "tabSize": 2, "insertSpaces": true }
+    }));
+    assert_eq!(edits, json!([{
+        "range": { "start": { "line": 2, "character": 2 }, "end": { "line": 2, "character": 5 } },
+        "newText": ""
+    }]));
+
+    client.shutdown();
+}
+
+#[test]
+fn test_document_symbols() {
+    let mut client = Client::start();
+    client.open(URI, DOCUMENT);
+    let diagnostics = client.diagnostics();
+    assert_eq!(diagnostics["diagnostics"], json!([]));
+
+    let symbols = client.request("textDocument/documentSymbol", json!({ "textDocument": { "uri": URI } }));
+    let names = |symbols: &Value| -> Vec<String> {
+        symbols.as_array().unwrap().iter().map(|s| s["name"].as_str().unwrap().to_string()).collect()
+    };
+    assert_eq!(names(&symbols), ["Intro", "Results"]);
+    assert_eq!(names(&symbols[0]["children"]), ["sec:intro", "Details"]);
+    assert_eq!(names(&symbols[0]["children"][1]["children"]), ["figure"]);
+    assert_eq!(names(&symbols[0]["children"][1]["children"][0]["children"]), ["fig:a"]);
+    assert_eq!(symbols[0]["range"], json!({
+        "start": { "line": 2, "character": 0 }, "end": { "line": 10, "character": 0 }
+    }));
+    assert_eq!(symbols[0]["children"][0]["range"], json!({
+        "start": { "line": 2, "character": 15 }, "end": { "line": 2, "character": 32 }
+    }));
+
+    let folding = client.request("textDocument/foldingRange", json!({ "textDocument": { "uri": URI } }));
+    let lines: Vec<(u64, u64)> = folding
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| (r["startLine"].as_u64().unwrap(), r["endLine"].as_u64().unwrap()))
+        .collect();
+    assert_eq!(lines, [(2, 9), (5, 9), (6, 7), (10, 12)]);
+
+    client.shutdown();
+}
+
+#[test]
+fn test_utf16_positions() {
+    let mut client = Client::start();
+    client.open(URI, "é😀 \\label{x}\n");
+    client.diagnostics();
+
+    let symbols = client.request("textDocument/documentSymbol", json!({ "textDocument": { "uri": URI } }));
+    assert_eq!(symbols[0]["range"], json!({
+        "start": { "line": 0, "character": 4 }, "end": { "line": 0, "character": 13 }
+    }));
+
+    client.shutdown();
+}
+
+#[test]
+fn test_parse_diagnostics() {
+    let mut client = Client::start();
+    client.open(URI, "é \\begin{itemize}\n\\item $x\n");
+    let diagnostics = client.diagnostics();
+    assert_eq!(diagnostics["diagnostics"], json!([
+        {
+            "range": { "start": { "line": 0, "character": 2 }, "end": { "line": 0, "character": 17 } },
+            "severity": 1,
+            "source": "unlatex",
+            "message": "environment `itemize` is never closed"
+        },
+        {
+            "range": { "start": { "line": 1, "character": 6 }, "end": { "line": 1, "character": 7 } },
+            "severity": 1,
+            "source": "unlatex",
+            "message": "inline math `$` is never closed"
+        }
+    ]));
+
+    client.shutdown();
+}